@@ -0,0 +1,231 @@
+//! 插件端的匯出巨集
+//!
+//! 插件 crate 使用 [`export_plugin!`](crate::export_plugin) 產生載入器預期的匯出符號
+//! (同一動態庫提供多個插件時使用 [`export_plugins!`](crate::export_plugins))，
+//! 不需要手寫 `#[no_mangle]` 函數，也不會因簽名錯誤而在載入時造成未定義行為。
+
+/// 載入器與插件之間的 ABI 版本
+/// - 由 [`export_plugin!`](crate::export_plugin) 以 `plugin_abi_version` 符號匯出，
+///   也是載入器支援的最新版本
+/// - 匯出符號的簽名或語意改變時遞增
+pub const PLUGIN_ABI_VERSION: u32 = 1;
+
+/// 載入器仍支援的最舊 ABI 版本
+/// - 載入器接受此版本至 [`PLUGIN_ABI_VERSION`] 之間 (含兩端) 的插件；
+///   插件可另外匯出 `plugin_abi_min` (`fn() -> u32`) 宣告它能配合的最舊版本，
+///   兩個範圍有交集時才會載入
+/// - 移除對舊版本的相容處理時提高
+pub const MIN_SUPPORTED_ABI_VERSION: u32 = 1;
+
+#[doc(hidden)]
+pub mod __private {
+    pub use chm_core_define::plugin_define::Plugin;
+}
+
+/// 產生插件的匯出符號
+/// - `create_plugin`: 創建插件實例 (以 `try` 指定建構函數時改為 `try_create_plugin`)
+/// - `unload_plugin`: 動態庫的清理函數 (不做任何事)
+/// - `plugin_abi_version`: 返回 [`PLUGIN_ABI_VERSION`]
+/// - 以 `send` 開頭時另外匯出 `create_plugin_send` (或 `try_create_plugin_send`)，
+///   返回 `Box<dyn Plugin + Send>`，插件型別不是 `Send` 時無法編譯;
+///   啟用 `send` 功能的載入器只接受匯出此符號的動態庫
+///
+/// 只接受型別時以 `Default::default()` 建立實例，也可以另外指定建構函數，
+/// 或以 `try` 指定返回 `Result<_, String>` 的建構函數，讓建構失敗時回報錯誤而非 panic:
+///
+/// ```ignore
+/// #[derive(Debug, Default)]
+/// struct MyPlugin;
+/// impl Plugin for MyPlugin { /* ... */ }
+///
+/// main_loader::export_plugin!(MyPlugin);
+/// // 或
+/// main_loader::export_plugin!(MyPlugin, MyPlugin::new);
+/// // 或 (`MyPlugin::try_new` 返回 `Result<MyPlugin, String>`)
+/// main_loader::export_plugin!(MyPlugin, try MyPlugin::try_new);
+/// // 同時供啟用 `send` 功能的載入器使用
+/// main_loader::export_plugin!(send MyPlugin, MyPlugin::new);
+/// ```
+#[macro_export]
+macro_rules! export_plugin {
+    (@common) => {
+        #[no_mangle]
+        pub fn unload_plugin() {}
+        #[no_mangle]
+        pub fn plugin_abi_version() -> u32 {
+            $crate::PLUGIN_ABI_VERSION
+        }
+    };
+    (send $plugin:ty) => {
+        $crate::export_plugin!(send $plugin, <$plugin as ::core::default::Default>::default);
+    };
+    (send $plugin:ty, try $constructor:expr) => {
+        #[no_mangle]
+        pub fn try_create_plugin_send() -> ::std::result::Result<
+            ::std::boxed::Box<dyn $crate::__private::Plugin + ::core::marker::Send>,
+            ::std::string::String,
+        > {
+            let plugin: $plugin = $constructor()?;
+            ::std::result::Result::Ok(::std::boxed::Box::new(plugin))
+        }
+        $crate::export_plugin!($plugin, try $constructor);
+    };
+    (send $plugin:ty, $constructor:expr) => {
+        #[no_mangle]
+        pub fn create_plugin_send(
+        ) -> ::std::boxed::Box<dyn $crate::__private::Plugin + ::core::marker::Send> {
+            let plugin: $plugin = $constructor();
+            ::std::boxed::Box::new(plugin)
+        }
+        $crate::export_plugin!($plugin, $constructor);
+    };
+    ($plugin:ty) => {
+        $crate::export_plugin!($plugin, <$plugin as ::core::default::Default>::default);
+    };
+    ($plugin:ty, try $constructor:expr) => {
+        #[no_mangle]
+        pub fn try_create_plugin() -> ::std::result::Result<
+            ::std::boxed::Box<dyn $crate::__private::Plugin>,
+            ::std::string::String,
+        > {
+            let plugin: $plugin = $constructor()?;
+            ::std::result::Result::Ok(::std::boxed::Box::new(plugin))
+        }
+        $crate::export_plugin!(@common);
+    };
+    ($plugin:ty, $constructor:expr) => {
+        #[no_mangle]
+        pub fn create_plugin() -> ::std::boxed::Box<dyn $crate::__private::Plugin> {
+            let plugin: $plugin = $constructor();
+            ::std::boxed::Box::new(plugin)
+        }
+        $crate::export_plugin!(@common);
+    };
+}
+
+/// 產生提供多個插件的動態庫的匯出符號
+/// - `create_plugins`: 依序創建每個插件的實例，載入器會把每個實例註冊為獨立的插件
+/// - `unload_plugin` 與 `plugin_abi_version`: 與 [`export_plugin!`](crate::export_plugin) 相同
+/// - 以 `send` 開頭時另外匯出返回 `Box<dyn Plugin + Send>` 的 `create_plugins_send`
+///
+/// 每個型別都以 `Default::default()` 建立實例:
+///
+/// ```ignore
+/// main_loader::export_plugins!(FirstPlugin, SecondPlugin);
+/// // 同時供啟用 `send` 功能的載入器使用
+/// main_loader::export_plugins!(send FirstPlugin, SecondPlugin);
+/// ```
+#[macro_export]
+macro_rules! export_plugins {
+    (send $($plugin:ty),+ $(,)?) => {
+        #[no_mangle]
+        pub fn create_plugins_send() -> ::std::vec::Vec<
+            ::std::boxed::Box<dyn $crate::__private::Plugin + ::core::marker::Send>,
+        > {
+            ::std::vec![$(
+                ::std::boxed::Box::new(<$plugin as ::core::default::Default>::default())
+                    as ::std::boxed::Box<dyn $crate::__private::Plugin + ::core::marker::Send>
+            ),+]
+        }
+        $crate::export_plugins!($($plugin),+);
+    };
+    ($($plugin:ty),+ $(,)?) => {
+        #[no_mangle]
+        pub fn create_plugins() -> ::std::vec::Vec<::std::boxed::Box<dyn $crate::__private::Plugin>> {
+            ::std::vec![$(
+                ::std::boxed::Box::new(<$plugin as ::core::default::Default>::default())
+                    as ::std::boxed::Box<dyn $crate::__private::Plugin>
+            ),+]
+        }
+        $crate::export_plugin!(@common);
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{LibraryHandle, LoadedLibrary, PluginLoader, PluginManager, PluginState};
+    use chm_core_define::plugin_define::{Event, Plugin};
+    use chm_core_define::Result;
+    use std::path::Path;
+
+    #[derive(Debug, Default)]
+    struct Exported;
+    impl Plugin for Exported {
+        fn name(&self) -> &str {
+            "exported"
+        }
+        fn version(&self) -> &str {
+            "1.0.0"
+        }
+        fn description(&self) -> &str {
+            "plugin exported with export_plugin!"
+        }
+        fn on_load(&self) -> Result<()> {
+            Ok(())
+        }
+        fn on_unload(&self) -> Result<()> {
+            Ok(())
+        }
+        fn handle_event(&self, _event: &Event) -> Result<Option<Event>> {
+            Ok(None)
+        }
+        fn subscribed_events(&self) -> Vec<String> {
+            Vec::new()
+        }
+    }
+
+    crate::export_plugin!(send Exported);
+
+    #[test]
+    fn send_form_exports_both_constructors() {
+        let plugin: Box<dyn Plugin> = create_plugin();
+        assert_eq!(plugin.name(), "exported");
+        let plugin: Box<dyn Plugin + Send> = create_plugin_send();
+        let plugin = std::thread::spawn(move || plugin.name().to_string())
+            .join()
+            .unwrap();
+        assert_eq!(plugin, "exported");
+        assert_eq!(plugin_abi_version(), crate::PLUGIN_ABI_VERSION);
+        unload_plugin();
+    }
+
+    /// 以巨集產生的函數作為匯出符號的動態庫
+    struct MacroLibrary;
+    impl LibraryHandle for MacroLibrary {
+        fn symbol(&self, symbol: &[u8]) -> Option<*const ()> {
+            let address = match symbol {
+                b"create_plugin" => create_plugin as fn() -> Box<dyn Plugin> as *const (),
+                b"create_plugin_send" => {
+                    create_plugin_send as fn() -> Box<dyn Plugin + Send> as *const ()
+                }
+                b"unload_plugin" => unload_plugin as fn() as *const (),
+                b"plugin_abi_version" => plugin_abi_version as fn() -> u32 as *const (),
+                _ => return None,
+            };
+            Some(address)
+        }
+    }
+    struct MacroLoader;
+    impl PluginLoader for MacroLoader {
+        fn load(&self, _path: &Path) -> Result<LoadedLibrary> {
+            Ok(LoadedLibrary::new(MacroLibrary))
+        }
+    }
+
+    #[test]
+    fn manager_loads_a_plugin_exported_with_the_macro() {
+        let dir = std::env::temp_dir().join(format!("main_loader-export-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("exported.so");
+        std::fs::write(&path, b"exported").unwrap();
+
+        let mut manager = PluginManager::new(&dir);
+        manager.set_loader(Box::new(MacroLoader));
+        manager.load_plugin(&path).unwrap();
+        assert_eq!(manager.state_of("exported"), Some(&PluginState::Enabled));
+        let outcome = manager.unload_plugin("exported").unwrap();
+        assert!(outcome.ran_native_cleanup);
+        drop(manager);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
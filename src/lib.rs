@@ -1,2 +1,4 @@
+mod export;
 mod plugin_manager;
+pub use export::*;
 pub use plugin_manager::*;
@@ -1,20 +1,32 @@
-/// 插件管理器
-mod plugin_manager;
-use chm_core_define::{Event, PluginError, Result};
-use plugin_manager::PluginManager;
-use std::{collections::HashMap, path::Path};
+use chm_core_define::Result;
+use main_loader::PluginManager;
+use std::path::Path;
 
 fn main() -> Result<()> {
-    // 創建插件目錄
+    // 創建插件管理器 (插件目錄不存在時會自動創建)
     let plugin_dir = Path::new("./plugins");
-    if !plugin_dir.exists() {
-        std::fs::create_dir_all(plugin_dir).map_err(|e| {
-            PluginError::LoadError(format!("Failed to create plugin directory: {}", e))
-        })?;
-    }
+    let mut manager = PluginManager::new_create(plugin_dir)?;
 
-    // 創建插件管理器
-    let mut manager = PluginManager::new(plugin_dir);
+    // `--check`: 只檢查插件目錄，不載入插件
+    if std::env::args().any(|arg| arg == "--check") {
+        let report = manager.validate_plugin_dir()?;
+        for (name, path) in &report.plugins {
+            println!("ok: {} ({:?})", name, path);
+        }
+        for (name, paths) in &report.duplicates {
+            println!("duplicate: {} in {:?}", name, paths);
+        }
+        for (path, reason) in &report.invalid {
+            println!("invalid: {:?}: {}", path, reason);
+        }
+        for (path, reason) in &report.permission_denied {
+            println!("permission denied: {:?}: {}", path, reason);
+        }
+        if report.plugins.is_empty() {
+            println!("no valid plugins found");
+        }
+        std::process::exit(if report.is_ok() { 0 } else { 1 });
+    }
 
     // 載入所有插件
     manager.load_all_plugins()?;
@@ -25,7 +37,7 @@ fn main() -> Result<()> {
     for (name, version, description) in manager.get_all_plugins() {
         println!("{} v{}: {}", name, version, description);
     }
-    dbg!(&manager);
+    println!("\n{}", manager.describe());
 
     let ret = manager.get_plugin("basic_plugin");
     if let Some(r) = ret {
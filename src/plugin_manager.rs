@@ -1,15 +1,344 @@
+//! 插件管理器
+//!
+//! # 錯誤的編碼
+//! `PluginError` 定義於 `chm_core_define`，無法增加變體或欄位，因此管理器沿用原本的變體，
+//! 以訊息的格式附加資訊：
+//! - `[名稱] ` 前綴表示來源插件，以 [`error_plugin`] 讀取
+//! - [`INCOMPATIBLE_PLUGIN_PREFIX`] 表示檔案不是相容的插件 (一律為 `LoadError`)
+//! - [`FROZEN_ERROR_PREFIX`] 表示管理器已凍結 (變體與被拒絕的操作對應，例如載入為 `LoadError`)
+//!
+//! 兩種前綴同時出現時插件名稱在前，判斷函數會先略過插件名稱
 #[cfg(unix)]
-use std::os::unix::fs::PermissionsExt;
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
 
 use chm_core_define::plugin_define::Event;
 use chm_core_define::PluginError;
 use chm_core_define::{plugin_define::Plugin, Result};
 use libloading::Library;
-use std::collections::{HashMap, HashSet};
+use serde::{Deserialize, Serialize};
+use std::any::Any;
+use std::cell::Cell;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
 
 use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+/// 記錄表達式的執行時間並寫入 `$slot`，未啟用 `metrics` 功能時只執行表達式
+macro_rules! timed {
+    ($slot:expr, $e:expr) => {{
+        #[cfg(feature = "metrics")]
+        let started = std::time::Instant::now();
+        let ret = $e;
+        #[cfg(feature = "metrics")]
+        {
+            $slot = started.elapsed();
+        }
+        ret
+    }};
+}
+
+/// 插件名稱的最大長度 (字元數)
+pub const MAX_PLUGIN_NAME_LEN: usize = 128;
+/// 插件版本字串的最大長度 (字元數)
+pub const MAX_PLUGIN_VERSION_LEN: usize = 64;
+/// 插件描述的最大長度 (字元數)
+pub const MAX_PLUGIN_DESCRIPTION_LEN: usize = 4096;
+/// 傳給插件的設定中，資料目錄路徑的鍵
+pub const DATA_DIR_CONFIG_KEY: &str = "data_dir";
+/// 插件資料目錄所在的子目錄，位於插件目錄之下
+const DATA_DIR_NAME: &str = ".data";
+
+#[cfg(feature = "archive")]
+mod archive;
+mod binary_arch;
+mod config;
+mod describe;
+mod event_bus;
+mod host;
+mod loader;
+#[cfg(feature = "mock")]
+mod mock;
+#[cfg(feature = "remote")]
+mod remote;
+#[cfg(feature = "signal")]
+mod signal;
+mod state_events;
+#[cfg(test)]
+mod test_util;
+#[cfg(test)]
+mod tests;
+mod validate;
+#[cfg(feature = "archive")]
+pub use archive::{ArchiveManifest, ARCHIVE_MANIFEST, ASSETS_DIR_CONFIG_KEY};
+pub use config::PluginManagerConfig;
+pub use event_bus::EventBus;
+use event_bus::SUBSCRIBER_CACHE_CAPACITY;
+use host::SharedHost;
+pub use host::{HostHandle, LogRecord, PLUGIN_LOG_TARGET};
+pub use loader::{LibloadingLoader, LibraryHandle, LoadedLibrary, PluginLoader};
+#[cfg(feature = "remote")]
+pub use remote::{ChecksumVerifier, MAX_PLUGIN_DOWNLOAD_SIZE};
+#[cfg(feature = "signal")]
+pub use signal::{install_sighup_flag, SighupReload};
+#[cfg(all(feature = "signal", feature = "send"))]
+pub use signal::{install_sighup_reload, SighupWatcher};
+use state_events::StateNotifier;
+#[cfg(feature = "async")]
+pub use state_events::DEFAULT_STATE_CHANNEL_CAPACITY;
+pub use validate::ValidationReport;
+
+// 編譯期檢查 [`PluginManager`] 文件中的執行緒安全保證
+const _: fn() = || {
+    fn assert_send_sync<T: Send + Sync>() {}
+    fn assert_send<T: Send>() {}
+    assert_send_sync::<EventBus>();
+    assert_send_sync::<Arc<dyn HostHandle>>();
+    #[cfg(feature = "signal")]
+    assert_send_sync::<SighupReload>();
+    assert_send_sync::<PluginManagerConfig>();
+    assert_send_sync::<PluginState>();
+    assert_send_sync::<LoadReport>();
+    assert_send_sync::<UnloadOutcome>();
+    assert_send_sync::<ValidationReport>();
+    assert_send_sync::<PluginSummary>();
+    assert_send_sync::<PluginPage>();
+    assert_send_sync::<ManagerSnapshot>();
+    assert_send_sync::<EventMetrics>();
+    assert_send_sync::<BroadcastOutcome>();
+    assert_send_sync::<ResourceUsage>();
+    // 非同步載入時動態庫會在阻塞執行緒上開啟後再交回目前的任務
+    assert_send::<OpenedLibrary>();
+    #[cfg(feature = "send")]
+    assert_send::<PluginManager>();
+};
+
+/// 管理器持有的插件實例
+/// - 啟用 `send` 功能時為 `Box<dyn Plugin + Send>`，見 [`PluginManager`] 的執行緒安全說明
+#[cfg(not(feature = "send"))]
+pub type BoxedPlugin = Box<dyn Plugin>;
+/// 管理器持有的插件實例
+/// - 啟用 `send` 功能時為 `Box<dyn Plugin + Send>`，見 [`PluginManager`] 的執行緒安全說明
+#[cfg(feature = "send")]
+pub type BoxedPlugin = Box<dyn Plugin + Send>;
+
+/// 建構函數的符號名稱，啟用 `send` 功能時改用返回 `Box<dyn Plugin + Send>` 的版本
+#[cfg(not(feature = "send"))]
+const CREATE_PLUGIN_SYMBOL: &str = "create_plugin";
+#[cfg(feature = "send")]
+const CREATE_PLUGIN_SYMBOL: &str = "create_plugin_send";
+#[cfg(not(feature = "send"))]
+const TRY_CREATE_PLUGIN_SYMBOL: &str = "try_create_plugin";
+#[cfg(feature = "send")]
+const TRY_CREATE_PLUGIN_SYMBOL: &str = "try_create_plugin_send";
+#[cfg(not(feature = "send"))]
+const CREATE_PLUGINS_SYMBOL: &str = "create_plugins";
+#[cfg(feature = "send")]
+const CREATE_PLUGINS_SYMBOL: &str = "create_plugins_send";
+
+/// 暫存插件檔案的流水號，用於產生唯一的檔案名稱
+static TEMP_FILE_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// 讀取動態庫中可選的匯出符號
+/// - `lib`: 動態庫
+/// - `symbol`: 符號名稱
+/// - 返回值: 符號的值，符號不存在時返回 `None`
+///
+/// # Safety
+/// 呼叫者必須確保 `T` 與符號實際的型別一致
+unsafe fn optional_symbol<T: Copy>(lib: &LoadedLibrary, symbol: &[u8]) -> Option<T> {
+    lib.get::<T>(symbol)
+}
+
+/// 不相容插件錯誤 (`LoadError`) 訊息的前綴，以 [`is_incompatible_plugin`] 判斷
+pub const INCOMPATIBLE_PLUGIN_PREFIX: &str = "Incompatible plugin: ";
+
+/// 建立不相容插件的錯誤
+/// - `msg`: 錯誤訊息，不含前綴
+fn incompatible_plugin_error(msg: String) -> PluginError {
+    PluginError::LoadError(format!("{}{}", INCOMPATIBLE_PLUGIN_PREFIX, msg))
+}
+
+/// 判斷錯誤是否表示檔案不是相容的插件
+/// - 缺少 `create_plugin` 符號、ABI 版本不符或動態庫為其他架構編譯時為 `true`，
+///   檔案無法讀取或動態庫無法開啟等其他載入錯誤為 `false`
+/// - `error`: 載入時返回的錯誤
+/// - 返回值: 是否為不相容插件的錯誤
+pub fn is_incompatible_plugin(error: &PluginError) -> bool {
+    matches!(error, PluginError::LoadError(_))
+        && strip_plugin_context(error_message(error)).starts_with(INCOMPATIBLE_PLUGIN_PREFIX)
+}
+
+/// 管理器凍結後拒絕變更時，錯誤訊息的前綴，以 [`is_frozen_error`] 判斷
+pub const FROZEN_ERROR_PREFIX: &str = "Plugin manager is frozen: ";
+
+/// 判斷錯誤是否因管理器已凍結而被拒絕
+/// - `error`: 管理器返回的錯誤
+/// - 返回值: 是否為凍結的錯誤
+pub fn is_frozen_error(error: &PluginError) -> bool {
+    strip_plugin_context(error_message(error)).starts_with(FROZEN_ERROR_PREFIX)
+}
+
+/// 獲取錯誤的訊息內容
+fn error_message(error: &PluginError) -> &str {
+    match error {
+        PluginError::LoadError(msg)
+        | PluginError::InitError(msg)
+        | PluginError::ConfigError(msg)
+        | PluginError::EventError(msg)
+        | PluginError::EnableError(msg)
+        | PluginError::DisableError(msg) => msg,
+    }
+}
+
+/// 為錯誤加上來源插件的名稱
+/// - 保留原本的變體，在訊息前加上 `[名稱] `，並以 [`error_plugin`] 讀取
+/// - 一律加上前綴，插件自身的訊息以 `[...] ` 開頭時也不例外；
+///   呼叫端只對插件鉤子返回的錯誤與管理器建立、尚未帶有名稱的錯誤調用
+/// - `name`: 插件的註冊名稱
+/// - `error`: 原始錯誤
+fn in_plugin(name: &str, error: PluginError) -> PluginError {
+    let with_name = |msg: String| format!("[{}] {}", name, msg);
+    match error {
+        PluginError::LoadError(msg) => PluginError::LoadError(with_name(msg)),
+        PluginError::InitError(msg) => PluginError::InitError(with_name(msg)),
+        PluginError::ConfigError(msg) => PluginError::ConfigError(with_name(msg)),
+        PluginError::EventError(msg) => PluginError::EventError(with_name(msg)),
+        PluginError::EnableError(msg) => PluginError::EnableError(with_name(msg)),
+        PluginError::DisableError(msg) => PluginError::DisableError(with_name(msg)),
+    }
+}
+
+/// 獲取錯誤的來源插件
+/// - 插件的鉤子 (`on_load`、`on_enable`、`on_disable`、`handle_event`、健康檢查) 返回的錯誤，
+///   以及管理器針對已載入插件的啟用/禁用錯誤，都會帶有插件名稱
+/// - `error`: 管理器返回的錯誤
+/// - 返回值: 插件的註冊名稱 (`on_load` 失敗時為插件的 `name()`)，錯誤不屬於特定插件時返回 `None`
+pub fn error_plugin(error: &PluginError) -> Option<&str> {
+    let (name, _) = error_message(error).strip_prefix('[')?.split_once("] ")?;
+    Some(name)
+}
+
+/// 移除錯誤訊息中的插件名稱
+fn strip_plugin_context(msg: &str) -> &str {
+    msg.strip_prefix('[')
+        .and_then(|rest| rest.split_once("] "))
+        .map_or(msg, |(_, msg)| msg)
+}
+
+/// 讀取檔案的修改時間
+/// - `path`: 檔案路徑
+/// - 返回值: 修改時間，無法讀取時返回 `None`
+fn file_modified(path: &Path) -> Option<SystemTime> {
+    path.metadata()
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+/// 計算檔案內容的雜湊值
+/// - 只用於同一行程內比較檔案內容是否改變，不保證跨版本穩定
+/// - `path`: 檔案路徑
+/// - 返回值: 雜湊值，無法讀取時返回 `None`
+fn file_hash(path: &Path) -> Option<u64> {
+    use std::hash::Hasher;
+    use std::io::Read;
+    let mut file = std::fs::File::open(path).ok()?;
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    let mut buf = [0u8; 64 * 1024];
+    loop {
+        match file.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => hasher.write(&buf[..n]),
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(_) => return None,
+        }
+    }
+    Some(hasher.finish())
+}
+
+/// 預設的插件名稱驗證規則
+/// - 拒絕空名稱、超過 [`MAX_PLUGIN_NAME_LEN`] 個字元的名稱，以及包含控制字元的名稱
+/// - `name`: 插件名稱
+/// - 返回值: 名稱是否有效
+pub fn default_name_validator(name: &str) -> bool {
+    !name.is_empty()
+        && name.chars().count() <= MAX_PLUGIN_NAME_LEN
+        && !name.chars().any(char::is_control)
+}
+/// 在 `catch_unwind` 中檢查插件實例的中繼資料
+/// - `plugin`: 剛創建的插件實例
+/// - 返回值: 實例的存取函數 panic 或返回不合理的內容時返回不相容插件的錯誤
+fn check_instance(plugin: &dyn Plugin) -> Result<()> {
+    let checked = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        check_metadata("name", plugin.name(), MAX_PLUGIN_NAME_LEN, false)?;
+        check_metadata("version", plugin.version(), MAX_PLUGIN_VERSION_LEN, false)?;
+        check_metadata(
+            "description",
+            plugin.description(),
+            MAX_PLUGIN_DESCRIPTION_LEN,
+            true,
+        )
+    }));
+    match checked {
+        Ok(ret) => ret.map_err(incompatible_plugin_error),
+        Err(_) => Err(incompatible_plugin_error(
+            "Plugin panicked while reporting its metadata".into(),
+        )),
+    }
+}
+/// 檢查單個中繼資料字串
+/// - `field`: 欄位名稱，用於錯誤訊息
+/// - `value`: 插件返回的字串
+/// - `max_len`: 最大長度 (字元數)
+/// - `multiline`: 是否允許換行與 tab
+/// - 返回值: 不合理時返回錯誤訊息
+fn check_metadata(
+    field: &str,
+    value: &str,
+    max_len: usize,
+    multiline: bool,
+) -> std::result::Result<(), String> {
+    // 損壞的實例可能返回不符合 `str` 不變量的位元組，重新驗證 UTF-8
+    if std::str::from_utf8(value.as_bytes()).is_err() {
+        return Err(format!("Plugin {} is not valid UTF-8", field));
+    }
+    if value.is_empty() {
+        return Err(format!("Plugin {} is empty", field));
+    }
+    if value.chars().count() > max_len {
+        return Err(format!(
+            "Plugin {} is longer than {} characters",
+            field, max_len
+        ));
+    }
+    if value
+        .chars()
+        .any(|c| c.is_control() && !(multiline && (c == '\n' || c == '\t')))
+    {
+        return Err(format!("Plugin {} contains control characters", field));
+    }
+    Ok(())
+}
+/// 目前平台預設接受的插件副檔名
+/// - Windows 為 `dll`，Linux 為 `so`，macOS 為 `dylib` 與 `so`
+///   (部分交叉編譯工具鏈在 macOS 上產生 `.so`，同樣可以 dlopen)
+/// - 返回值: 不含 `.` 的副檔名列表
+pub fn default_plugin_extensions() -> Vec<String> {
+    #[cfg(target_os = "windows")]
+    let extensions = vec!["dll".into()];
+    #[cfg(target_os = "linux")]
+    let extensions = vec!["so".into()];
+    #[cfg(target_os = "macos")]
+    let extensions = vec!["dylib".into(), "so".into()];
+    #[cfg(not(any(target_os = "windows", target_os = "linux", target_os = "macos")))]
+    let extensions = Vec::new();
+    extensions
+}
 /// 插件狀態
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[allow(unused)]
 /// 插件狀態枚舉，用來表示插件的不同狀態
 pub enum PluginState {
@@ -28,234 +357,3498 @@ pub enum PluginState {
 #[derive(Debug)]
 struct PluginEntry {
     /// 插件的具體實例
-    plugin: Box<dyn Plugin>,
+    plugin: BoxedPlugin,
     /// 動態庫的句柄，用於管理插件的生命周期
-    library: Library,
+    /// - 同一動態庫建立的多個插件共用此句柄，最後一個插件釋放時才卸載動態庫
+    library: Arc<LoadedLibrary>,
+    /// 插件檔案的路徑
+    path: PathBuf,
+    /// 由管理器建立的暫存插件檔案，卸載時會一併刪除
+    temp_file: Option<PathBuf>,
+    /// 由管理器解壓縮的資源目錄，卸載時會一併刪除
+    extracted_dir: Option<PathBuf>,
+    /// 傳給插件的設定
+    config: HashMap<String, String>,
+    /// 插件專屬的資料目錄
+    data_dir: PathBuf,
+    /// 載入時插件檔案的修改時間，無法讀取時為 `None`
+    modified: Option<SystemTime>,
+    /// 載入時插件檔案內容的雜湊值，無法讀取時為 `None`
+    content_hash: Option<u64>,
+    /// 交給插件的宿主句柄識別碼
+    host_client: u64,
+    /// 註冊到管理器的流水號，同優先級的插件依此決定派送順序；重新載入或替換時沿用
+    load_seq: u64,
+    /// 延遲初始化時 `on_load` 是否尚未調用
+    pending_on_load: bool,
+    /// 進行中的借用數量 (動態庫存取與事件處理)，卸載時必須為 0
+    in_flight: Cell<usize>,
+    /// 插件處理事件的累計耗時
+    handler_time: Duration,
+    /// 插件宣告支援的能力
+    capabilities: Vec<String>,
+    /// 插件的事件處理優先級，數值越大越先處理
+    priority: i32,
+    /// 插件依賴的其他插件名稱
+    dependencies: Vec<String>,
+    /// 插件會發布的事件名稱 (可選的 `plugin_published_events` 符號)
+    published_events: Vec<String>,
+    /// 將插件實例轉為 `Any` 的函數 (可選的 `plugin_as_any` 符號)
+    as_any: Option<fn(&dyn Plugin) -> &dyn Any>,
+    /// 插件的健康檢查函數 (可選的 `plugin_health_check` 符號)
+    health_check: Option<fn(&dyn Plugin) -> Result<()>>,
+    /// 插件的資源用量回報函數 (可選的 `plugin_resource_usage` 符號)
+    resource_usage: Option<fn(&dyn Plugin) -> ResourceUsage>,
+    /// 插件的設定變更鉤子 (可選的 `plugin_config_change` 符號)
+    config_change: Option<ConfigChangeHook>,
+    /// 插件的狀態匯出鉤子 (可選的 `plugin_export_state` 符號)
+    export_state: Option<ExportStateHook>,
+    /// 插件的狀態遷移鉤子 (可選的 `plugin_on_migrate` 符號)
+    on_migrate: Option<MigrateHook>,
+    /// 批次載入完成後的鉤子 (可選的 `plugin_on_all_loaded` 符號)
+    on_all_loaded: Option<fn(&mut dyn Plugin) -> Result<()>>,
+    /// 動態庫的 `unload_plugin` 清理函數，載入時讀取，只在動態庫的最後一個插件卸載時執行
+    native_unload: Option<fn()>,
     /// 插件當前的狀態      
     state: PluginState,
+    /// 插件載入各階段的耗時
+    #[cfg(feature = "metrics")]
+    timings: LoadTimings,
+}
+impl PluginEntry {
+    /// 調用延遲的 `on_load`，已調用過時不做任何事
+    /// - 失敗時維持未調用的狀態，下次啟用時會重試
+    fn ensure_loaded(&mut self) -> Result<()> {
+        if self.pending_on_load {
+            timed!(self.timings.on_load, self.plugin.on_load())?;
+            self.pending_on_load = false;
+        }
+        Ok(())
+    }
+    /// 調用延遲的 `on_load` 與 `on_enable`，不改變狀態
+    fn run_enable_hooks(&mut self) -> Result<()> {
+        self.ensure_loaded()?;
+        timed!(self.timings.on_enable, self.plugin.on_enable())
+    }
+    /// 記錄一次進行中的借用，守衛釋放時結束
+    fn borrow_guard(&self) -> BorrowGuard<'_> {
+        self.in_flight.set(self.in_flight.get() + 1);
+        BorrowGuard(&self.in_flight)
+    }
+}
+
+/// 插件條目的借用守衛，用於在卸載時偵測仍在使用中的插件
+struct BorrowGuard<'a>(&'a Cell<usize>);
+impl Drop for BorrowGuard<'_> {
+    fn drop(&mut self) {
+        self.0.set(self.0.get() - 1);
+    }
+}
+
+/// 插件載入各階段的耗時
+#[cfg(feature = "metrics")]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct LoadTimings {
+    /// 開啟動態庫 ([`PluginLoader::load`]) 的耗時
+    pub library_open: Duration,
+    /// 調用 `create_plugin` 創建插件實例的耗時
+    pub create: Duration,
+    /// 加載鉤子 `on_load` 的耗時
+    pub on_load: Duration,
+    /// 最近一次啟用鉤子 `on_enable` 的耗時
+    pub on_enable: Duration,
+}
+#[cfg(feature = "metrics")]
+impl LoadTimings {
+    /// 所有階段的總耗時
+    pub fn total(&self) -> Duration {
+        self.library_open + self.create + self.on_load + self.on_enable
+    }
+}
+
+/// 插件檔案的檢查結果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PluginFileCheck {
+    /// 有效的插件檔案
+    Valid,
+    /// 副檔名不符合目前平台的動態庫格式
+    WrongExtension,
+    /// 檔案不存在
+    NotAFile,
+    /// 路徑是目錄 (例如建置系統產生的 `foo.so/` 目錄)
+    Directory,
+    /// 路徑是 FIFO、socket 或裝置等特殊檔案
+    SpecialFile,
+    /// 檔案沒有執行權限 (macOS 為沒有讀取權限，非 Unix 平台為唯讀)
+    NotExecutable,
+    /// 無法讀取檔案的中繼資料
+    Unreadable,
+    /// 檔案是符號連結，且管理器設定為不跟隨符號連結
+    Symlink,
+}
+
+/// 卸載單個插件的結果
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct UnloadOutcome {
+    /// 是否執行了動態庫匯出的 `unload_plugin` 清理函數
+    pub ran_native_cleanup: bool,
 }
 
-/// 事件系統，用於管理事件的訂閱和通知
+/// 排程中的延遲事件
 #[derive(Debug)]
-struct EventBus {
-    /// 每個事件對應的訂閱插件集合
-    subscribers: HashMap<String, HashSet<String>>, // event_name -> plugin_names
+struct ScheduledEvent {
+    /// 事件的觸發時間
+    fire_at: Instant,
+    /// 排程的流水號，觸發時間相同時依排程順序發送
+    seq: u64,
+    /// 排程此事件的插件，插件卸載時事件會一併取消
+    owner: Option<String>,
+    /// 要發送的事件
+    event: Event,
+}
+
+/// 已開啟但尚未創建插件實例的動態庫
+struct OpenedLibrary {
+    /// 動態庫的句柄
+    library: LoadedLibrary,
+    /// 插件檔案的路徑
+    path: PathBuf,
+    /// 開啟前讀取的檔案修改時間
+    modified: Option<SystemTime>,
+    /// 開啟前計算的檔案內容雜湊值
+    content_hash: Option<u64>,
+    /// 開啟動態庫的耗時
+    #[cfg(feature = "metrics")]
+    library_open: Duration,
+}
+
+/// 批次載入的結果報告
+#[derive(Debug, Default)]
+pub struct LoadReport {
+    /// 成功載入的插件名稱
+    pub loaded: Vec<String>,
+    /// 載入失敗的插件檔案路徑及錯誤訊息
+    pub failed: Vec<(PathBuf, String)>,
+    /// 是否因取消而未載入全部插件
+    pub cancelled: bool,
+}
+
+/// 插件的設定變更鉤子，參數為插件實例與新的設定
+type ConfigChangeHook = fn(&mut dyn Plugin, &HashMap<String, String>) -> Result<()>;
+
+/// 重新載入或替換插件時由舊實例交給新實例的狀態
+/// - 內容的格式由插件自行決定，管理器只負責傳遞
+pub type MigrationBlob = Vec<u8>;
+
+/// 插件的狀態匯出鉤子，參數為舊的插件實例
+type ExportStateHook = fn(&dyn Plugin) -> Option<MigrationBlob>;
+
+/// 插件的狀態遷移鉤子，參數為新的插件實例、舊實例的版本與舊實例匯出的狀態
+type MigrateHook = fn(&mut dyn Plugin, &str, MigrationBlob) -> Result<()>;
+
+/// 依派送順序排列的訂閱者名稱，快取與多次派送共用同一份列表
+type DispatchOrder = Arc<[Arc<str>]>;
+
+/// 派送順序快取的項目
+struct CachedDispatchOrder {
+    /// 計算時事件總線返回的訂閱者列表，總線返回的列表不再是同一個 `Arc` 時表示訂閱已變更
+    subscribers: Arc<[Arc<str>]>,
+    /// 依派送順序排序的結果
+    order: DispatchOrder,
+}
+
+/// 插件自行回報的資源用量
+/// - 數值由插件估計，管理器不會驗證或限制
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct ResourceUsage {
+    /// 約略佔用的記憶體位元組數
+    pub approx_bytes: u64,
+    /// 開啟中的檔案、連線等資源數量
+    pub open_handles: u32,
+}
+
+/// 事件處理的統計計數
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct EventMetrics {
+    /// 派送的事件總數，包含回應事件、宿主句柄發送的事件與 `request`
+    pub events_dispatched: u64,
+    /// 調用插件 `handle_event` 的總次數
+    pub handler_calls: u64,
+    /// `handle_event` 返回錯誤的總次數
+    pub handler_errors: u64,
+    /// 被事件中介函數捨棄的事件總數，不計入 `events_dispatched`
+    pub events_dropped: u64,
+    /// 每個事件名稱的派送次數
+    pub per_event: HashMap<String, u64>,
+}
+impl EventMetrics {
+    /// 記錄一次事件派送，只有第一次出現的事件名稱需要配置記憶體
+    fn record_event(&mut self, name: &str) {
+        self.events_dispatched += 1;
+        match self.per_event.get_mut(name) {
+            Some(count) => *count += 1,
+            None => {
+                self.per_event.insert(name.to_string(), 1);
+            }
+        }
+    }
+}
+
+/// [`PluginManager::broadcast_to`] 的發送結果
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BroadcastOutcome {
+    /// 成功處理事件的插件名稱，依處理順序排列
+    pub delivered: Vec<String>,
+    /// 處理失敗的插件名稱及錯誤訊息
+    pub failed: Vec<(String, String)>,
+    /// 未載入、未啟用或未訂閱而略過的插件名稱
+    pub skipped: Vec<String>,
+}
+
+/// 事件的派送模式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DispatchMode {
+    /// 派送給所有已啟用的訂閱者
+    #[default]
+    Broadcast,
+    /// 以優先級作為權重輪流選出一個已啟用的訂閱者，用於在可互換的插件間分配負載
+    RoundRobin,
+}
+
+/// 卸載已啟用的插件時，`on_disable` 失敗的處理方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DisableErrorPolicy {
+    /// 記錄錯誤後繼續卸載，禁用總是失敗的插件仍可被移除
+    #[default]
+    ForceUnloadOnDisableError,
+    /// 中止卸載並返回錯誤，插件維持載入
+    StrictDisable,
+}
+
+/// 單一插件對單一事件的斷路器狀態
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    /// 正常派送
+    Closed,
+    /// 連續失敗達到門檻，冷卻期間不派送
+    Open,
+    /// 冷卻期已過，下一次派送作為試驗，成功時關閉、失敗時重新斷開
+    HalfOpen,
+}
+
+/// 斷路器的失敗記錄
+#[derive(Debug, Clone, Copy, Default)]
+struct CircuitBreaker {
+    /// 連續失敗次數
+    failures: u32,
+    /// 斷開的時間，未斷開時為 `None`
+    opened_at: Option<Instant>,
+}
+
+/// 插件檔案的過期檢查結果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Staleness {
+    /// 修改時間未改變
+    Unchanged,
+    /// 修改時間較新但內容未改變，附帶目前的修改時間
+    Touched(SystemTime),
+    /// 內容已改變
+    Changed,
+}
+
+/// 事件交給單一訂閱者的處理結果
+enum Delivery {
+    /// 訂閱者不存在或未啟用
+    Skipped,
+    /// 訂閱者已處理，附帶回應事件
+    Handled(Option<Event>),
+    /// 訂閱者處理失敗，附帶錯誤訊息
+    Failed(String),
+}
+
+/// 插件的摘要資訊
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PluginSummary {
+    /// 插件的註冊名稱
+    pub name: String,
+    /// 插件版本
+    pub version: String,
+    /// 插件描述
+    pub description: String,
+    /// 插件當前的狀態
+    pub state: PluginState,
+    /// 插件檔案的路徑
+    pub path: PathBuf,
+}
+
+/// 分頁查詢的結果
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct PluginPage {
+    /// 此頁的插件摘要，依名稱排序
+    pub items: Vec<PluginSummary>,
+    /// 符合篩選條件的插件總數
+    pub total: usize,
+}
+
+/// 單個插件的快照資訊
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginSnapshot {
+    /// 插件名稱
+    pub name: String,
+    /// 插件檔案的路徑
+    pub path: PathBuf,
+    /// 快照時的插件狀態
+    pub state: PluginState,
+}
+
+/// 插件管理器的狀態快照，用於崩潰後恢復
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ManagerSnapshot {
+    /// 所有已載入插件的快照
+    pub plugins: Vec<PluginSnapshot>,
+    /// 已定義的插件設定檔，鍵為設定檔名稱
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Vec<String>>,
+}
+
+/// 事件處理錯誤的處理函數，參數為插件名稱、事件與錯誤
+#[cfg(not(feature = "send"))]
+pub type EventErrorHandler = Box<dyn Fn(&str, &Event, &PluginError)>;
+/// 事件處理錯誤的處理函數，參數為插件名稱、事件與錯誤
+#[cfg(feature = "send")]
+pub type EventErrorHandler = Box<dyn Fn(&str, &Event, &PluginError) + Send>;
+
+/// 事件處理耗時的策略函數，參數為插件名稱與累計耗時，返回 `true` 時禁用插件
+#[cfg(not(feature = "send"))]
+pub type HandlerTimePolicy = Box<dyn Fn(&str, Duration) -> bool>;
+/// 事件處理耗時的策略函數，參數為插件名稱與累計耗時，返回 `true` 時禁用插件
+#[cfg(feature = "send")]
+pub type HandlerTimePolicy = Box<dyn Fn(&str, Duration) -> bool + Send>;
+
+/// 插件名稱驗證函數，返回 `false` 的名稱會被拒絕載入
+#[cfg(not(feature = "send"))]
+pub type NameValidator = Box<dyn Fn(&str) -> bool>;
+/// 插件名稱驗證函數，返回 `false` 的名稱會被拒絕載入
+#[cfg(feature = "send")]
+pub type NameValidator = Box<dyn Fn(&str) -> bool + Send>;
+
+/// 事件中介函數的處理結果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MiddlewareAction {
+    /// 不修改事件，繼續交給下一個中介函數
+    Continue,
+    /// 已直接修改事件，繼續交給下一個中介函數
+    Modify,
+    /// 捨棄事件，之後的中介函數與插件都不會收到
+    Drop,
+}
+
+/// 事件中介函數，在事件送達插件前調用，可直接修改事件
+#[cfg(not(feature = "send"))]
+pub type EventMiddleware = Box<dyn Fn(&mut Event) -> MiddlewareAction>;
+/// 事件中介函數，在事件送達插件前調用，可直接修改事件
+#[cfg(feature = "send")]
+pub type EventMiddleware = Box<dyn Fn(&mut Event) -> MiddlewareAction + Send>;
+
+/// 插件管理器，用於管理插件的加載、啟用、禁用和事件通知
+///
+/// # 執行緒安全
+/// 預設情況下管理器既不是 `Send` 也不是 `Sync`，只能在建立它的執行緒上使用:
+/// - 插件實例是 [`BoxedPlugin`] (`Box<dyn Plugin>`)，而 `Plugin` trait 沒有 `Send` 約束，
+///   插件可能持有只能在單一執行緒上使用的資源 (例如 `Rc` 或執行緒區域變數)
+/// - 名稱驗證函數、事件中介函數等回呼函數沒有 `Send` 約束
+///
+/// 啟用 `send` 功能時管理器是 `Send`，可以移動到其他執行緒，或放入 `Mutex` 由多個執行緒共用:
+/// - 插件實例改為 `Box<dyn Plugin + Send>`；`Plugin` trait 定義於 `chm_core_define`，
+///   無法在此加上約束，因此動態庫必須另外匯出 `create_plugin_send`
+///   (或 `try_create_plugin_send`、`create_plugins_send`)，由插件端的編譯器檢查型別為 `Send`;
+///   以 `export_plugin!(send MyPlugin)` 匯出即可，只匯出 `create_plugin` 的動態庫會被視為不相容
+/// - [`NameValidator`]、[`EventErrorHandler`]、[`HandlerTimePolicy`]、[`EventMiddleware`]
+///   等回呼函數型別要求 `Send`
+/// - 同時啟用 `signal` 功能時，可以用 `install_sighup_reload` 在背景執行緒上重新載入
+///   `Arc<Mutex<PluginManager>>`
+///
+/// 進行中的借用以 `Cell` 計數，因此在任何設定下管理器都不是 `Sync`；未啟用 `send` 功能時
+/// 需要跨執行緒操作，應讓管理器常駐於一個執行緒，其他執行緒透過通道把請求交給該執行緒
+/// (例如 [`HostHandle`] 與 `SighupReload` 的做法)。
+///
+/// 以下型別不含插件實例，保證是 `Send + Sync`，可以自由傳到其他執行緒:
+/// [`EventBus`]、[`HostHandle`] 句柄 (`Arc<dyn HostHandle>`)、`SighupReload` (`signal` 功能)、
+/// [`PluginManagerConfig`]，以及各種報告與快照 (例如 [`LoadReport`]、[`ManagerSnapshot`]、
+/// [`EventMetrics`])。這些保證在編譯期檢查，違反時無法編譯。
+pub struct PluginManager {
+    /// 插件的集合，鍵為插件名稱
+    plugins: HashMap<String, PluginEntry>,
+    /// 插件目錄的路徑
+    plugin_dir: PathBuf,
+    /// 事件總線
+    event_bus: EventBus,
+    /// 插件名稱驗證函數
+    name_validator: NameValidator,
+    /// 插件檔案的載入後端，非同步載入時會共享給阻塞執行緒
+    loader: Arc<dyn PluginLoader>,
+    /// 從記憶體載入插件時使用的暫存目錄
+    temp_dir: PathBuf,
+    /// 嚴格模式，啟用時將不一致或可疑的情況視為錯誤而非警告
+    strict: bool,
+    /// 插件設定檔，鍵為設定檔名稱，值為設定檔包含的插件名稱
+    profiles: HashMap<String, Vec<String>>,
+    /// 事件處理錯誤的處理函數，未設定時只輸出錯誤訊息
+    event_error_handler: Option<EventErrorHandler>,
+    /// 連續事件處理失敗達到此次數時自動禁用插件
+    auto_disable_threshold: Option<u32>,
+    /// 每個插件連續事件處理失敗的次數
+    event_failures: HashMap<String, u32>,
+    /// 連鎖禁用時一併禁用的依賴者，鍵為被禁用的插件名稱，值依禁用順序排列
+    cascade_disabled: HashMap<String, Vec<String>>,
+    /// 與插件共享的宿主狀態，包含待處理事件佇列
+    host: SharedHost,
+    /// 是否有事件派送正在進行，用於避免重入時遞歸派送
+    dispatching: bool,
+    /// 排程中的延遲事件
+    scheduled: Vec<ScheduledEvent>,
+    /// 下一個延遲事件的流水號
+    next_schedule_seq: u64,
+    /// 下一個註冊插件的流水號
+    next_load_seq: u64,
+    /// 事件名稱對應的派送順序，見 [`PluginManager::dispatch_order`]
+    /// - 快取持有總線字串池的引用，因此載入、合併、替換與卸載插件時在變更訂閱前清空
+    dispatch_cache: Mutex<HashMap<String, CachedDispatchOrder>>,
+    /// 卸載插件時是否刪除其資料目錄
+    purge_data_on_unload: bool,
+    /// 卸載時 `on_disable` 失敗的處理方式
+    disable_error_policy: DisableErrorPolicy,
+    /// 釋放管理器時是否跳過卸載程序，直接洩漏所有插件
+    leak_on_drop: bool,
+    /// 事件處理的統計計數
+    event_metrics: EventMetrics,
+    /// 接受的插件副檔名
+    extensions: Vec<String>,
+    /// 是否接受沒有副檔名的插件檔案
+    accept_extensionless: bool,
+    /// 是否跟隨符號連結的插件檔案
+    follow_symlinks: bool,
+    /// 已知的事件名稱，設定後會檢查插件的事件訂閱
+    known_events: Option<HashSet<String>>,
+    /// 事件處理耗時的策略函數
+    handler_time_policy: Option<HandlerTimePolicy>,
+    /// 事件中介函數，依加入順序調用
+    event_middleware: Vec<EventMiddleware>,
+    /// 載入後是否自動啟用插件
+    auto_enable: bool,
+    /// 可同時載入的插件數量上限
+    max_plugins: Option<usize>,
+    /// 健康檢查失敗時是否禁用插件
+    disable_unhealthy: bool,
+    /// 預設的事件派送模式
+    dispatch_mode: DispatchMode,
+    /// 個別事件的派送模式
+    event_dispatch_modes: HashMap<String, DispatchMode>,
+    /// 輪詢派送的狀態，鍵為事件名稱，值為每個插件目前的權重
+    /// - 以複製的名稱為鍵，不持有事件總線字串池的引用，插件卸載時移除其權重
+    round_robin: HashMap<String, HashMap<String, i64>>,
+    /// 事件歷史的容量，0 表示不記錄
+    event_history_capacity: usize,
+    /// 最近派送的事件及其派送結果
+    event_history: VecDeque<(Event, BroadcastOutcome)>,
+    /// 插件狀態變化的通知
+    state_notifier: StateNotifier,
+    /// 進入安全模式的門檻 (次數, 時間範圍)
+    safe_mode_threshold: Option<(usize, Duration)>,
+    /// 斷路器的設定 (連續失敗次數, 冷卻時間)
+    circuit_breaker: Option<(u32, Duration)>,
+    /// 每個 (插件, 事件) 的斷路器
+    circuits: HashMap<(String, String), CircuitBreaker>,
+    /// 時間範圍內插件進入錯誤狀態的時間
+    error_transitions: VecDeque<Instant>,
+    /// 是否處於安全模式
+    safe_mode: bool,
+    /// 是否延遲初始化插件
+    lazy_init: bool,
+    /// `load_all_plugins` 至少需要成功載入的插件數量
+    require_at_least: usize,
+    /// 是否已凍結，凍結後不能再載入、卸載、啟用或禁用插件
+    frozen: bool,
+    /// 遠端插件的驗證函數，未設定時拒絕從網址載入
+    #[cfg(feature = "remote")]
+    checksum_verifier: Option<ChecksumVerifier>,
+    /// 從網址下載的插件內容，鍵為網址
+    #[cfg(feature = "remote")]
+    download_cache: HashMap<String, remote::CachedDownload>,
+}
+impl fmt::Debug for PluginManager {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PluginManager")
+            .field("plugins", &self.plugins)
+            .field("plugin_dir", &self.plugin_dir)
+            .field("event_bus", &self.event_bus)
+            .field("temp_dir", &self.temp_dir)
+            .field("strict", &self.strict)
+            .field("profiles", &self.profiles)
+            .field("auto_disable_threshold", &self.auto_disable_threshold)
+            .field("purge_data_on_unload", &self.purge_data_on_unload)
+            .field("disable_error_policy", &self.disable_error_policy)
+            .field("leak_on_drop", &self.leak_on_drop)
+            .field("event_metrics", &self.event_metrics)
+            .field("extensions", &self.extensions)
+            .field("accept_extensionless", &self.accept_extensionless)
+            .field("follow_symlinks", &self.follow_symlinks)
+            .field("known_events", &self.known_events)
+            .field("auto_enable", &self.auto_enable)
+            .field("max_plugins", &self.max_plugins)
+            .field("disable_unhealthy", &self.disable_unhealthy)
+            .field("dispatch_mode", &self.dispatch_mode)
+            .field("event_dispatch_modes", &self.event_dispatch_modes)
+            .field("event_history_capacity", &self.event_history_capacity)
+            .field("safe_mode_threshold", &self.safe_mode_threshold)
+            .field("circuit_breaker", &self.circuit_breaker)
+            .field("circuits", &self.circuits)
+            .field("safe_mode", &self.safe_mode)
+            .field("lazy_init", &self.lazy_init)
+            .field("require_at_least", &self.require_at_least)
+            .field("frozen", &self.frozen)
+            .field("event_failures", &self.event_failures)
+            .field("cascade_disabled", &self.cascade_disabled)
+            .field("scheduled", &self.scheduled)
+            .field("host", &self.host)
+            .field("state_notifier", &self.state_notifier)
+            .finish_non_exhaustive()
+    }
 }
 #[allow(unused)]
-impl EventBus {
-    /// 創建新的事件總線
-    fn new() -> Self {
+impl PluginManager {
+    /// 創建新的插件管理器
+    /// - `plugin_dir`: 插件目錄路徑
+    pub fn new<P: AsRef<Path>>(plugin_dir: P) -> Self {
         Self {
-            subscribers: HashMap::new(),
+            plugins: HashMap::new(),
+            plugin_dir: plugin_dir.as_ref().to_path_buf(),
+            event_bus: EventBus::new(),
+            name_validator: Box::new(default_name_validator),
+            loader: Arc::new(LibloadingLoader),
+            temp_dir: std::env::temp_dir(),
+            strict: false,
+            profiles: HashMap::new(),
+            event_error_handler: None,
+            auto_disable_threshold: None,
+            event_failures: HashMap::new(),
+            cascade_disabled: HashMap::new(),
+            host: SharedHost::default(),
+            dispatching: false,
+            scheduled: Vec::new(),
+            next_schedule_seq: 0,
+            next_load_seq: 0,
+            dispatch_cache: Mutex::default(),
+            purge_data_on_unload: false,
+            disable_error_policy: DisableErrorPolicy::default(),
+            leak_on_drop: false,
+            event_metrics: EventMetrics::default(),
+            extensions: default_plugin_extensions(),
+            accept_extensionless: false,
+            follow_symlinks: true,
+            known_events: None,
+            handler_time_policy: None,
+            event_middleware: Vec::new(),
+            auto_enable: true,
+            max_plugins: None,
+            disable_unhealthy: false,
+            dispatch_mode: DispatchMode::default(),
+            event_dispatch_modes: HashMap::new(),
+            round_robin: HashMap::new(),
+            event_history_capacity: 0,
+            event_history: VecDeque::new(),
+            state_notifier: StateNotifier::default(),
+            safe_mode_threshold: None,
+            circuit_breaker: None,
+            circuits: HashMap::new(),
+            error_transitions: VecDeque::new(),
+            safe_mode: false,
+            lazy_init: false,
+            require_at_least: 0,
+            frozen: false,
+            #[cfg(feature = "remote")]
+            checksum_verifier: None,
+            #[cfg(feature = "remote")]
+            download_cache: HashMap::new(),
+        }
+    }
+    /// 設定事件處理錯誤的處理函數
+    /// - 插件的 `handle_event` 每次失敗時都會調用，可用於記錄、統計等集中處理
+    /// - `handler`: 處理函數，參數為插件名稱、事件與錯誤
+    pub fn set_event_error_handler(&mut self, handler: EventErrorHandler) {
+        self.event_error_handler = Some(handler);
+    }
+    /// 設定事件處理耗時的策略函數
+    /// - 每次插件的 `handle_event` 返回後，會以該插件的累計耗時調用，返回 `true` 時禁用插件
+    /// - 插件在處理期間不會被中斷，策略只能在處理結束後生效
+    /// - `policy`: 策略函數，參數為插件名稱與累計耗時
+    pub fn set_handler_time_policy(&mut self, policy: HandlerTimePolicy) {
+        self.handler_time_policy = Some(policy);
+    }
+    /// 加入事件中介函數
+    /// - 每個事件送達插件前，依加入順序調用所有中介函數，適合記錄、統計、加上關聯識別碼或權限檢查等共通邏輯
+    /// - 中介函數可直接修改事件 (並返回 [`MiddlewareAction::Modify`])，之後的中介函數與插件收到修改後的事件;
+    ///   返回 [`MiddlewareAction::Drop`] 時捨棄事件，不再調用之後的中介函數，也不會派送給任何插件
+    /// - 適用於 `broadcast_event`、`broadcast_batch`、`broadcast_to`、`request`，
+    ///   以及經由宿主句柄、回應事件與延遲事件派送的事件；`broadcast_event_dry_run` 不會調用中介函數
+    /// - 中介函數不能存取管理器，也不應 panic
+    /// - `middleware`: 中介函數
+    pub fn add_event_middleware(&mut self, middleware: EventMiddleware) {
+        self.event_middleware.push(middleware);
+    }
+    /// 移除所有事件中介函數
+    pub fn clear_event_middleware(&mut self) {
+        self.event_middleware.clear();
+    }
+    /// 依序調用事件中介函數
+    /// - `event`: 要派送的事件，中介函數可直接修改
+    /// - 返回值: 事件是否應繼續派送，被捨棄時計入 `events_dropped`
+    fn apply_middleware(&mut self, event: &mut Event) -> bool {
+        for middleware in &self.event_middleware {
+            if middleware(event) == MiddlewareAction::Drop {
+                self.event_metrics.events_dropped += 1;
+                return false;
+            }
+        }
+        true
+    }
+    /// 設定自動禁用的連續失敗次數
+    /// - 插件連續處理事件失敗達到此次數時會被自動禁用，處理成功時計數歸零
+    /// - `threshold`: 連續失敗次數，`None` 表示不自動禁用
+    pub fn set_auto_disable_threshold(&mut self, threshold: Option<u32>) {
+        self.auto_disable_threshold = threshold;
+    }
+    /// 設定卸載插件時是否刪除其資料目錄
+    /// - 預設保留，讓插件的資料在重新載入或下次啟動時仍可使用；重新載入與替換插件時不會刪除
+    /// - `purge`: 是否刪除
+    pub fn set_purge_data_on_unload(&mut self, purge: bool) {
+        self.purge_data_on_unload = purge;
+    }
+    /// 設定卸載已啟用的插件時，`on_disable` 失敗的處理方式
+    /// - 預設為 [`DisableErrorPolicy::ForceUnloadOnDisableError`]，記錄錯誤後繼續卸載，
+    ///   避免禁用總是失敗的插件永遠無法移除
+    /// - `policy`: 處理方式
+    pub fn set_disable_error_policy(&mut self, policy: DisableErrorPolicy) {
+        self.disable_error_policy = policy;
+    }
+    /// 設定釋放管理器時是否跳過卸載程序
+    /// - 啟用時 `Drop` 不會調用 `unload_all_plugins`，已載入插件的 `on_disable`、`on_unload`
+    ///   與動態庫的 `unload_plugin` 都不會執行
+    /// - 插件實例、動態庫與其佔用的記憶體會洩漏到行程結束，暫存檔案、解壓縮的資源
+    ///   與資料目錄也不會刪除
+    /// - 只適合即將結束的短暫行程 (例如測試)，長時間執行的程式中重複建立管理器會持續累積記憶體
+    /// - 明確調用的 `unload_plugin`、`clear` 等操作不受影響
+    /// - `leak`: 是否跳過卸載程序，預設為 `false`
+    pub fn set_leak_on_drop(&mut self, leak: bool) {
+        self.leak_on_drop = leak;
+    }
+    /// 設定接受的插件副檔名，取代 [`default_plugin_extensions`]
+    /// - 檔案名稱中任一以 `.` 分隔的後綴符合即可，因此 `so` 也會接受 `libfoo.so.1`
+    /// - 比對時不區分大小寫
+    /// - `extensions`: 不含 `.` 的副檔名列表，例如 `vec!["so".into(), "plug".into()]`
+    pub fn set_extensions(&mut self, extensions: Vec<String>) {
+        self.extensions = extensions;
+    }
+    /// 設定是否接受沒有副檔名的插件檔案
+    /// - 檔案仍需通過其他檢查 (一般檔案且具備權限)
+    /// - `accept`: 是否接受，預設為 `false`
+    pub fn set_accept_extensionless(&mut self, accept: bool) {
+        self.accept_extensionless = accept;
+    }
+    /// 設定是否跟隨符號連結的插件檔案
+    /// - 跟隨時以連結指向的目標檔案檢查副檔名與權限，因此指向非插件檔案的連結會被拒絕
+    /// - 不跟隨時所有符號連結都會以 [`PluginFileCheck::Symlink`] 拒絕
+    /// - `follow`: 是否跟隨，預設為 `true`
+    pub fn set_follow_symlinks(&mut self, follow: bool) {
+        self.follow_symlinks = follow;
+    }
+    /// 設定已知的事件名稱
+    /// - 之後載入、重新載入或替換的插件若訂閱了不在其中的事件，會發出警告，嚴格模式下拒絕載入
+    /// - 已載入的插件不會重新檢查
+    /// - `events`: 有效的事件名稱
+    pub fn set_known_events(&mut self, events: HashSet<String>) {
+        self.known_events = Some(events);
+    }
+    /// 設定每個插件的事件訂閱數量上限
+    /// - 之後載入、重新載入或替換的插件訂閱數量 (含萬用字元訂閱) 超出上限時拒絕載入，
+    ///   避免插件以大量訂閱耗盡宿主的資源
+    /// - 已載入的插件不會重新檢查
+    /// - `max`: 上限，`None` 表示不限制 (預設)
+    pub fn set_max_subscriptions_per_plugin(&mut self, max: Option<usize>) {
+        self.event_bus.set_max_subscriptions_per_plugin(max);
+    }
+    /// 設定所有插件的事件訂閱總數上限
+    /// - 之後載入、重新載入或替換的插件會使總數超出上限時拒絕載入
+    /// - 已載入的插件不會重新檢查
+    /// - `max`: 上限，`None` 表示不限制 (預設)
+    pub fn set_max_total_subscriptions(&mut self, max: Option<usize>) {
+        self.event_bus.set_max_total_subscriptions(max);
+    }
+    /// 設定載入後是否自動啟用插件
+    /// - 停用時新載入的插件維持 `Loaded` 狀態，需自行調用 `enable_plugin`；
+    ///   重新載入與替換插件仍會保留原本的狀態
+    /// - `auto_enable`: 是否自動啟用，預設為 `true`
+    pub fn set_auto_enable(&mut self, auto_enable: bool) {
+        self.auto_enable = auto_enable;
+    }
+    /// 設定是否延遲初始化插件
+    /// - 啟用時載入插件只會創建實例並註冊事件訂閱，`on_load` 與自動啟用的 `on_enable`
+    ///   延後到第一個事件經由 `broadcast_event` 或 `request` 送達插件時才調用，之後的事件不再初始化;
+    ///   從未收到事件的插件不會執行昂貴的初始化，代價是第一個事件的處理延遲包含初始化時間
+    /// - 延遲期間插件維持 `Loaded` 狀態；明確調用 `enable_plugin` 時會先調用 `on_load`
+    /// - 延遲的 `on_load` 失敗時插件進入 `Error` 狀態，可用 [`PluginManager::recover_plugin`] 重試
+    /// - 從未初始化的插件卸載時不調用 `on_unload`
+    /// - 只影響之後載入的插件；替換或重新載入已啟用的插件時會立即初始化
+    /// - `lazy`: 是否延遲初始化，預設為 `false`
+    pub fn set_lazy_init(&mut self, lazy: bool) {
+        self.lazy_init = lazy;
+    }
+    /// 設定 `load_all_plugins` 至少需要成功載入的插件數量
+    /// - 用於發現插件沒有被部署到插件目錄等設定錯誤；預設為 0，空目錄也視為成功
+    /// - `n`: 最少插件數量
+    pub fn set_require_at_least(&mut self, n: usize) {
+        self.require_at_least = n;
+    }
+    /// 設定可同時載入的插件數量上限
+    /// - 已達上限時新的插件會被拒絕載入，已載入的插件不受影響
+    /// - `max`: 數量上限，`None` 表示不限制
+    pub fn set_max_plugins(&mut self, max: Option<usize>) {
+        self.max_plugins = max;
+    }
+    /// 設定健康檢查失敗時是否禁用插件
+    /// - `disable`: 是否在 [`PluginManager::run_health_checks`] 失敗時禁用插件，預設為 `false`
+    pub fn set_disable_unhealthy(&mut self, disable: bool) {
+        self.disable_unhealthy = disable;
+    }
+    /// 設定嚴格模式
+    /// - `strict`: 是否啟用嚴格模式
+    pub fn set_strict(&mut self, strict: bool) {
+        self.strict = strict;
+    }
+    /// 設定插件目錄，之後的 `load_all_plugins` 會從新目錄載入
+    /// - 已載入的插件不受影響，如需重新開始請先調用 [`PluginManager::clear`]
+    /// - `dir`: 插件目錄路徑
+    pub fn set_plugin_dir<P: AsRef<Path>>(&mut self, dir: P) {
+        self.plugin_dir = dir.as_ref().to_path_buf();
+    }
+    /// 設定從記憶體載入插件時使用的暫存目錄
+    /// - 預設為系統暫存目錄，建議改為只有目前使用者可寫入的私有目錄
+    /// - `dir`: 暫存目錄路徑
+    pub fn set_temp_dir<P: AsRef<Path>>(&mut self, dir: P) {
+        self.temp_dir = dir.as_ref().to_path_buf();
+    }
+    /// 設定插件名稱驗證函數，取代預設的 [`default_name_validator`]
+    /// - `validator`: 驗證函數，返回 `false` 的名稱會被拒絕載入
+    pub fn set_name_validator(&mut self, validator: NameValidator) {
+        self.name_validator = validator;
+    }
+    /// 創建新的插件管理器，並在插件目錄不存在時自動創建
+    /// - `plugin_dir`: 插件目錄路徑
+    /// - 返回值: 插件管理器，或目錄創建失敗的錯誤
+    pub fn new_create<P: AsRef<Path>>(plugin_dir: P) -> Result<Self> {
+        let plugin_dir = plugin_dir.as_ref();
+        if !plugin_dir.exists() {
+            std::fs::create_dir_all(plugin_dir).map_err(|e| {
+                PluginError::LoadError(format!("Failed to create plugin directory: {}", e))
+            })?;
+        }
+        Ok(Self::new(plugin_dir))
+    }
+    /// 凍結管理器
+    /// - 凍結後載入、重新載入、替換、合併、卸載、啟用、禁用、恢復插件、從快照恢復、
+    ///   清除與更新插件設定都會返回錯誤 (訊息以 [`FROZEN_ERROR_PREFIX`] 開頭，可用 [`is_frozen_error`] 判斷)，
+    ///   用於在啟動完成後防止執行期間注入或移除插件
+    /// - 查詢、事件派送、排程與健康檢查仍可使用；管理器自身的保護機制 (連續失敗、耗時策略與
+    ///   健康檢查的自動禁用) 以及延遲初始化的啟用仍會生效
+    /// - 凍結無法解除；釋放管理器時仍會正常卸載所有插件
+    pub fn freeze(&mut self) {
+        self.frozen = true;
+        println!("Plugin manager frozen");
+    }
+    /// 檢查管理器是否已凍結
+    pub fn is_frozen(&self) -> bool {
+        self.frozen
+    }
+    /// 管理器已凍結時拒絕變更
+    /// - `make`: 對應操作的錯誤變體
+    /// - `action`: 被拒絕的操作，用於錯誤訊息
+    /// - 返回值: 未凍結時為 `Ok`
+    fn check_frozen(&self, make: fn(String) -> PluginError, action: &str) -> Result<()> {
+        if self.frozen {
+            return Err(make(format!("{}cannot {}", FROZEN_ERROR_PREFIX, action)));
+        }
+        Ok(())
+    }
+    /// 加載單個插件
+    /// - 動態庫匯出 `create_plugins` (`fn() -> Vec<Box<dyn Plugin>>`) 時優先使用，
+    ///   返回的每個插件都會以各自的 `name()` 註冊，擁有獨立的狀態與事件訂閱，
+    ///   所有插件共用同一個動態庫句柄，最後一個插件卸載時才卸載動態庫
+    /// - `path`: 插件檔案的路徑
+    /// - 返回值: 成功或失敗的結果，部分插件註冊或啟用失敗時其餘插件仍會註冊，並返回第一個錯誤
+    pub fn load_plugin(&mut self, path: &Path) -> Result<()> {
+        self.check_frozen(PluginError::LoadError, "load plugins")?;
+        let entries = self.open_plugins(path, HashMap::new())?;
+        self.register_library(entries)
+            .into_iter()
+            .collect::<Result<Vec<_>>>()?;
+        Ok(())
+    }
+    /// 以交易方式載入多個插件，全部成功或全部不載入
+    /// - 依序以 [`PluginManager::load_plugin`] 的方式載入每個檔案 (含自動啟用)，
+    ///   任一插件載入、註冊或啟用失敗時，卸載此交易中已載入的所有插件 (依賴者先卸載)，
+    ///   交易開始前已載入的插件不受影響
+    /// - 回滾會執行被卸載插件的禁用與卸載鉤子，插件在鉤子之外造成的副作用、
+    ///   統計計數與已派送的事件不會復原
+    /// - 全部載入成功後調用這些插件的 `on_all_loaded` 鉤子 (見 [`PluginManager::run_all_loaded`])；
+    ///   鉤子失敗只會讓該插件進入 `Error` 狀態，不會回滾交易
+    /// - `paths`: 插件檔案的路徑
+    /// - 返回值: 成功或失敗的結果，失敗時返回第一個錯誤
+    pub fn load_transaction(&mut self, paths: &[&Path]) -> Result<()> {
+        self.check_frozen(PluginError::LoadError, "load plugins")?;
+        let existing: HashSet<String> = self.plugins.keys().cloned().collect();
+        let first_seq = self.next_load_seq;
+        let mut result = Ok(());
+        for path in paths {
+            result = self
+                .open_plugins(path, HashMap::new())
+                .and_then(|entries| {
+                    self.register_library(entries)
+                        .into_iter()
+                        .collect::<Result<Vec<_>>>()
+                })
+                .map(|_| ())
+                .map_err(|e| {
+                    PluginError::LoadError(format!("Failed to load plugin from {:?}: {}", path, e))
+                });
+            if result.is_err() {
+                break;
+            }
+        }
+        let Err(e) = result else {
+            self.run_all_loaded(first_seq);
+            return Ok(());
+        };
+        // 啟用失敗的插件已經註冊，因此以交易前後的差異決定要回滾的插件
+        let added: Vec<String> = self
+            .unload_order()
+            .into_iter()
+            .filter(|name| !existing.contains(name))
+            .collect();
+        for name in added {
+            if let Err(rollback_error) = self.force_unload_plugin(&name) {
+                eprintln!("Failed to roll back plugin {}: {}", name, rollback_error);
+            }
+        }
+        eprintln!("Plugin load transaction rolled back: {}", e);
+        Err(e)
+    }
+    /// 以別名載入單個插件
+    /// - 插件會以 `alias` 而非 `plugin.name()` 註冊，事件訂閱也以別名作為插件識別
+    /// - 之後的 `enable_plugin`、`get_plugin`、`unload_plugin` 等操作都必須使用別名
+    /// - 插件內部的 `name()` 可能與註冊名稱不同，`get_all_plugins` 返回的是註冊名稱
+    /// - `path`: 插件檔案的路徑
+    /// - `alias`: 註冊名稱，需通過名稱驗證
+    /// - 返回值: 成功或失敗的結果
+    pub fn load_plugin_as(&mut self, path: &Path, alias: &str) -> Result<()> {
+        self.check_frozen(PluginError::LoadError, "load plugins")?;
+        if !(self.name_validator)(alias) {
+            return Err(PluginError::LoadError(format!(
+                "Invalid plugin alias: {:?}",
+                alias
+            )));
+        }
+        // 先檢查別名，避免為已被使用的別名開啟動態庫並調用 `on_load`
+        if self.plugins.contains_key(alias) {
+            return Err(PluginError::LoadError(format!(
+                "Plugin {} is already loaded",
+                alias
+            )));
+        }
+        // 資料目錄以別名區分，避免同一插件的多個別名共用資料
+        let config = HashMap::from([(
+            DATA_DIR_CONFIG_KEY.to_string(),
+            self.data_dir_for(alias).to_string_lossy().into_owned(),
+        )]);
+        let entry = self.open_plugin(path, config)?;
+        self.register_entry(alias.to_string(), entry)?;
+        Ok(())
+    }
+    /// 將已創建的插件條目註冊到管理器，並在自動啟用時啟用
+    /// - `name`: 註冊名稱
+    /// - `entry`: 狀態為 `Loaded` 的插件條目
+    /// - 名稱已被其他插件使用時釋放此條目並返回 `LoadError`，不會取代已註冊的插件
+    /// - 返回值: 註冊名稱，啟用失敗時插件仍保留在管理器中並返回錯誤
+    fn register_entry(&mut self, name: String, mut entry: PluginEntry) -> Result<String> {
+        if self.plugins.contains_key(&name) {
+            let _ = Self::finalize_entry(entry);
+            return Err(PluginError::LoadError(format!(
+                "Plugin {} is already loaded",
+                name
+            )));
+        }
+        if let Some(max) = self.max_plugins {
+            if self.plugins.len() >= max {
+                let _ = Self::finalize_entry(entry);
+                return Err(PluginError::LoadError(format!(
+                    "Cannot load plugin {}: limit of {} plugins reached",
+                    name, max
+                )));
+            }
+        }
+        if let Err(e) = self.check_subscriptions(&name, entry.plugin.as_ref()) {
+            let _ = Self::finalize_entry(entry);
+            return Err(e);
+        }
+        // 註冊事件訂閱
+        // 訂閱數量上限已由 `check_subscriptions` 檢查，不會失敗
+        self.invalidate_dispatch_order();
+        for event in entry.plugin.subscribed_events() {
+            let _ = self.event_bus.subscribe(&event, &name);
+        }
+        println!("Loaded plugin: {} v{}", name, entry.plugin.version());
+        self.host.bind_client(entry.host_client, &name);
+        entry.load_seq = self.take_load_seq();
+        self.plugins.insert(name.clone(), entry);
+        self.host.mark_loaded(&name);
+        self.state_notifier.notify(&name, &PluginState::Loaded);
+        // 延遲初始化時等到第一個事件送達才啟用
+        if self.auto_enable && !self.lazy_init {
+            self.enable_plugin(name.as_str())?;
+        }
+        Ok(name)
+    }
+    /// 依序註冊同一動態庫建立的所有插件條目
+    /// - 某個插件註冊失敗不影響其餘插件
+    /// - `entries`: 狀態為 `Loaded` 的插件條目
+    /// - 返回值: 每個插件條目的註冊結果，依條目順序排列
+    fn register_library(&mut self, entries: Vec<PluginEntry>) -> Vec<Result<String>> {
+        entries
+            .into_iter()
+            .map(|entry| {
+                let name = entry.plugin.name().to_string();
+                self.register_entry(name, entry)
+            })
+            .collect()
+    }
+    /// 將另一個管理器的插件移入此管理器
+    /// - 插件實例與動態庫直接轉移，不會重新載入、卸載或調用任何生命週期鉤子，
+    ///   並保留原本的狀態、設定、資料目錄與事件訂閱方式
+    /// - 插件匯出 `plugin_set_host` 時會重新傳入此管理器的宿主句柄
+    /// - 以下插件不會轉移，記錄在報告的 `failed` 中，並在 `other` 釋放時依 `other` 的設定卸載:
+    ///   註冊名稱已存在於此管理器 (保留此管理器的插件)、超出插件數量上限，
+    ///   或未通過已知事件檢查 (嚴格模式)
+    /// - `other` 中尚未派送的事件與延遲事件會被捨棄
+    /// - `other`: 要合併的管理器
+    /// - 返回值: 合併報告，`loaded` 為成功轉移的插件名稱
+    pub fn merge(&mut self, mut other: PluginManager) -> LoadReport {
+        let mut report = LoadReport::default();
+        for name in other.sorted_names() {
+            let entry = &other.plugins[&name];
+            let rejection = if self.frozen {
+                Some(format!("{}cannot merge plugins", FROZEN_ERROR_PREFIX))
+            } else if self.plugins.contains_key(&name) {
+                Some(format!("Plugin {} is already loaded", name))
+            } else if self
+                .max_plugins
+                .is_some_and(|max| self.plugins.len() >= max)
+            {
+                Some(format!(
+                    "Cannot merge plugin {}: limit of {} plugins reached",
+                    name,
+                    self.max_plugins.unwrap_or_default()
+                ))
+            } else {
+                self.check_subscriptions(&name, entry.plugin.as_ref())
+                    .err()
+                    .map(|e| e.to_string())
+            };
+            if let Some(error_msg) = rejection {
+                eprintln!("Failed to merge plugin {}: {}", name, error_msg);
+                report.failed.push((entry.path.clone(), error_msg));
+                continue;
+            }
+            let Some(mut entry) = other.plugins.remove(&name) else {
+                continue;
+            };
+            other.host.mark_unloaded(&name);
+            // 插件原本持有 `other` 的宿主句柄，改為此管理器的句柄，心跳記錄不會保留
+            entry.host_client = self.host.register_client(&name);
+            entry.load_seq = self.take_load_seq();
+            if let Some(set_host) = unsafe {
+                optional_symbol::<fn(Arc<dyn HostHandle>)>(&entry.library, b"plugin_set_host")
+            } {
+                set_host(self.host.handle_for(entry.host_client));
+            }
+            self.invalidate_dispatch_order();
+            for event in entry.plugin.subscribed_events() {
+                let _ = self.event_bus.subscribe(&event, &name);
+            }
+            let state = entry.state.clone();
+            self.plugins.insert(name.clone(), entry);
+            self.host.mark_loaded(&name);
+            self.state_notifier.notify(&name, &state);
+            println!("Merged plugin: {}", name);
+            report.loaded.push(name);
+        }
+        report
+    }
+    /// 檢查插件訂閱的事件是否都在已知事件名稱中，且未超出訂閱數量上限
+    /// - 超出 [`PluginManager::set_max_subscriptions_per_plugin`] 或
+    ///   [`PluginManager::set_max_total_subscriptions`] 的上限時一律返回錯誤
+    /// - 未設定已知事件名稱時不檢查；萬用字元訂閱只要匹配任一已知事件即視為有效
+    /// - 未知事件會發出警告，嚴格模式下返回錯誤
+    /// - `name`: 插件的註冊名稱
+    /// - `plugin`: 插件實例
+    /// - 返回值: 成功或失敗的結果
+    fn check_subscriptions(&self, name: &str, plugin: &dyn Plugin) -> Result<()> {
+        self.event_bus
+            .check_subscriptions(name, &plugin.subscribed_events())?;
+        let Some(known) = &self.known_events else {
+            return Ok(());
+        };
+        let mut unknown: Vec<String> = plugin
+            .subscribed_events()
+            .into_iter()
+            .filter(|event| match event.strip_suffix('*') {
+                Some(prefix) => !known.iter().any(|k| k.starts_with(prefix)),
+                None => !known.contains(event),
+            })
+            .collect();
+        if unknown.is_empty() {
+            return Ok(());
+        }
+        unknown.sort();
+        let error_msg = format!(
+            "Plugin {} subscribes to unknown events: {}",
+            name,
+            unknown.join(", ")
+        );
+        if self.strict {
+            return Err(PluginError::EventError(error_msg));
+        }
+        eprintln!("Warning: {}", error_msg);
+        Ok(())
+    }
+    /// 從記憶體中的位元組載入插件
+    /// - 位元組會寫入暫存目錄中的唯一檔案 (使用平台對應的副檔名)，再以一般方式載入
+    /// - 暫存檔案會在插件卸載時刪除，載入失敗時立即刪除
+    /// - 安全性: 寫入可執行的暫存檔案後再載入，若暫存目錄可被其他使用者寫入，
+    ///   檔案可能在寫入與載入之間被替換。檔案以 `create_new` 建立以避免覆寫既有檔案，
+    ///   在 Unix 上權限為 `0o700`，但仍建議透過 [`PluginManager::set_temp_dir`]
+    ///   指定私有目錄，且只載入來源可信的位元組
+    /// - `name_hint`: 用於暫存檔案名稱的提示，不影響插件註冊名稱
+    /// - `bytes`: 動態庫的內容
+    /// - 返回值: 成功或失敗的結果
+    pub fn load_plugin_from_bytes(&mut self, name_hint: &str, bytes: &[u8]) -> Result<()> {
+        self.check_frozen(PluginError::LoadError, "load plugins")?;
+        let temp_path = self.write_temp_plugin(name_hint, bytes)?;
+        let ret = self.load_plugin(&temp_path);
+        // 載入成功 (即使啟用失敗) 時由插件條目追蹤暫存檔案，否則立即刪除;
+        // 動態庫提供多個插件時每個條目都會記錄，由最後卸載的插件刪除
+        let mut tracked = false;
+        for entry in self
+            .plugins
+            .values_mut()
+            .filter(|entry| entry.path == temp_path)
+        {
+            entry.temp_file = Some(temp_path.clone());
+            tracked = true;
+        }
+        if !tracked {
+            let _ = std::fs::remove_file(&temp_path);
+        }
+        ret
+    }
+    /// 將插件位元組寫入暫存目錄中的唯一檔案
+    /// - `name_hint`: 檔案名稱提示
+    /// - `bytes`: 動態庫的內容
+    /// - 返回值: 暫存檔案的路徑
+    fn write_temp_plugin(&self, name_hint: &str, bytes: &[u8]) -> Result<PathBuf> {
+        std::fs::create_dir_all(&self.temp_dir).map_err(|e| {
+            PluginError::LoadError(format!("Failed to create temp directory: {}", e))
+        })?;
+        let temp_path = self.unique_temp_path(name_hint, std::env::consts::DLL_EXTENSION);
+
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        options.mode(0o700);
+        let written = options
+            .open(&temp_path)
+            .and_then(|mut file| file.write_all(bytes).and_then(|_| file.sync_all()));
+        if let Err(e) = written {
+            let _ = std::fs::remove_file(&temp_path);
+            return Err(PluginError::LoadError(format!(
+                "Failed to write temp plugin file {:?}: {}",
+                temp_path, e
+            )));
+        }
+        Ok(temp_path)
+    }
+    /// 計算插件的資料目錄路徑
+    /// - 位於 `plugin_dir/.data/<name>/`，名稱中英數字、`_` 與 `-` 以外的字元會替換為 `_`，
+    ///   因此同一名稱在重新載入或重新啟動後都會得到相同的路徑
+    /// - `name`: 插件的註冊名稱
+    fn data_dir_for(&self, name: &str) -> PathBuf {
+        let dir_name: String = name
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '_' || c == '-' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        self.plugin_dir.join(DATA_DIR_NAME).join(dir_name)
+    }
+    /// 在暫存目錄中產生唯一的路徑
+    /// - `name_hint`: 檔案名稱提示，只保留英數字、`_` 與 `-`
+    /// - `suffix`: 副檔名或後綴
+    /// - 返回值: 尚不存在的暫存路徑
+    fn unique_temp_path(&self, name_hint: &str, suffix: &str) -> PathBuf {
+        let hint: String = name_hint
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric() || *c == '_' || *c == '-')
+            .collect();
+        let file_name = format!(
+            "{}-{}-{}.{}",
+            if hint.is_empty() {
+                "plugin"
+            } else {
+                hint.as_str()
+            },
+            std::process::id(),
+            TEMP_FILE_COUNTER.fetch_add(1, Ordering::Relaxed),
+            suffix
+        );
+        self.temp_dir.join(file_name)
+    }
+    /// 開啟插件動態庫並創建插件實例
+    /// - 會驗證插件名稱並調用加載鉤子，但不會註冊到管理器中
+    /// - 動態庫提供多個插件時返回錯誤，需改用 [`PluginManager::load_plugin`] 載入
+    /// - `path`: 插件檔案的路徑
+    /// - `config`: 傳給插件的設定
+    /// - 返回值: 狀態為 `Loaded` 的插件條目
+    fn open_plugin(&self, path: &Path, config: HashMap<String, String>) -> Result<PluginEntry> {
+        self.instantiate_plugin(Self::open_library(&*self.loader, path)?, config)
+    }
+    /// 開啟插件動態庫並創建其提供的所有插件實例
+    /// - `path`: 插件檔案的路徑
+    /// - `config`: 傳給每個插件的設定
+    /// - 返回值: 狀態為 `Loaded` 的插件條目，依動態庫返回的順序排列
+    fn open_plugins(
+        &self,
+        path: &Path,
+        config: HashMap<String, String>,
+    ) -> Result<Vec<PluginEntry>> {
+        self.instantiate_plugins(Self::open_library(&*self.loader, path)?, config, None)
+    }
+    /// 開啟插件動態庫
+    /// - 不依賴管理器狀態，可在其他執行緒上執行
+    /// - `loader`: 載入後端
+    /// - `path`: 插件檔案的路徑
+    /// - 返回值: 已開啟的動態庫
+    fn open_library(loader: &dyn PluginLoader, path: &Path) -> Result<OpenedLibrary> {
+        // 在開啟前讀取修改時間與內容雜湊值，載入期間被更新的檔案會在下次檢查時視為過期
+        let modified = file_modified(path);
+        let content_hash = file_hash(path);
+        #[cfg(feature = "metrics")]
+        let library_open;
+        let library = timed!(library_open, loader.load(path))?;
+        Ok(OpenedLibrary {
+            library,
+            path: path.to_path_buf(),
+            modified,
+            content_hash,
+            #[cfg(feature = "metrics")]
+            library_open,
+        })
+    }
+    /// 從已開啟的動態庫創建插件實例
+    /// - 動態庫提供多個插件時返回錯誤，需改用 [`PluginManager::instantiate_plugins`]
+    /// - `opened`: 已開啟的動態庫
+    /// - `config`: 傳給插件的設定
+    /// - 返回值: 狀態為 `Loaded` 的插件條目
+    fn instantiate_plugin(
+        &self,
+        opened: OpenedLibrary,
+        config: HashMap<String, String>,
+    ) -> Result<PluginEntry> {
+        let path = opened.path.clone();
+        let entries = self.instantiate_plugins(opened, config, None)?;
+        Self::single_entry(&path, entries)
+    }
+    /// 取出動態庫提供的唯一插件條目
+    /// - `path`: 插件檔案的路徑，用於錯誤訊息
+    /// - `entries`: 同一動態庫建立的插件條目
+    /// - 返回值: 唯一的插件條目，條目不只一個時全部卸載並返回錯誤
+    fn single_entry(path: &Path, mut entries: Vec<PluginEntry>) -> Result<PluginEntry> {
+        if entries.len() != 1 {
+            let count = entries.len();
+            for entry in entries {
+                let _ = Self::finalize_entry(entry);
+            }
+            return Err(PluginError::LoadError(format!(
+                "Library {:?} provides {} plugins, load it with load_plugin",
+                path, count
+            )));
+        }
+        Ok(entries.remove(0))
+    }
+    /// 從已開啟的動態庫創建其提供的所有插件實例
+    /// - 實例以 [`PluginManager::create_instances`] 創建，所有條目共用同一個動態庫句柄
+    /// - 會驗證插件名稱並調用加載鉤子，但不會註冊到管理器中
+    /// - 插件可匯出可選的 `plugin_configure` 符號 (`fn(&HashMap<String, String>)`)，
+    ///   在加載鉤子之前接收設定；動態庫提供多個插件時會為每個插件各調用一次
+    /// - 任一插件建立失敗時，已建立的插件會立即卸載並返回錯誤
+    /// - `opened`: 已開啟的動態庫
+    /// - `config`: 傳給每個插件的設定
+    /// - `select`: 只建立 `name()` 與此名稱相同的插件，其餘實例在調用任何鉤子前釋放
+    /// - 返回值: 狀態為 `Loaded` 的插件條目，依動態庫返回的順序排列
+    fn instantiate_plugins(
+        &self,
+        opened: OpenedLibrary,
+        config: HashMap<String, String>,
+        select: Option<&str>,
+    ) -> Result<Vec<PluginEntry>> {
+        #[cfg(feature = "metrics")]
+        let create;
+        let OpenedLibrary {
+            library,
+            path,
+            modified,
+            content_hash,
+            #[cfg(feature = "metrics")]
+            library_open,
+        } = opened;
+        let lib = Arc::new(library);
+        // 插件實例必須在動態庫之前釋放，提前返回時 `plugins` 會先於 `lib` 釋放
+        let mut plugins = timed!(create, unsafe { Self::create_instances(&lib) })?;
+        if let Some(select) = select {
+            plugins.retain(|plugin| plugin.name() == select);
+            if plugins.is_empty() {
+                return Err(PluginError::LoadError(format!(
+                    "Library {:?} does not provide plugin {}",
+                    path, select
+                )));
+            }
+        }
+        let mut names = HashSet::new();
+        if let Some(plugin) = plugins.iter().find(|p| !names.insert(p.name().to_string())) {
+            return Err(PluginError::LoadError(format!(
+                "Library {:?} provides plugin {} more than once",
+                path,
+                plugin.name()
+            )));
+        }
+
+        let mut entries: Vec<PluginEntry> = Vec::with_capacity(plugins.len());
+        for plugin in plugins {
+            match self.setup_entry(plugin, &lib, &path, modified, content_hash, config.clone()) {
+                #[allow(unused_mut)]
+                Ok(mut entry) => {
+                    #[cfg(feature = "metrics")]
+                    {
+                        entry.timings.library_open = library_open;
+                        entry.timings.create = create;
+                    }
+                    entries.push(entry);
+                }
+                Err(e) => {
+                    for entry in entries {
+                        let _ = Self::finalize_entry(entry);
+                    }
+                    return Err(e);
+                }
+            }
+        }
+        Ok(entries)
+    }
+    /// 為單個插件實例建立資料目錄、傳遞宿主句柄與設定、調用加載鉤子，並讀取可選的符號
+    /// - `plugin`: 插件實例
+    /// - `lib`: 建立此實例的動態庫
+    /// - `path`: 插件檔案的路徑
+    /// - `modified`: 開啟前讀取的檔案修改時間
+    /// - `content_hash`: 開啟前計算的檔案內容雜湊值
+    /// - `config`: 傳給插件的設定
+    /// - 返回值: 狀態為 `Loaded` 的插件條目
+    fn setup_entry(
+        &self,
+        plugin: BoxedPlugin,
+        lib: &Arc<LoadedLibrary>,
+        path: &Path,
+        modified: Option<SystemTime>,
+        content_hash: Option<u64>,
+        mut config: HashMap<String, String>,
+    ) -> Result<PluginEntry> {
+        #[cfg(feature = "metrics")]
+        let mut timings = LoadTimings::default();
+        unsafe {
+            let name = plugin.name().to_string();
+            // 驗證插件名稱，無效時插件實例會在返回時釋放
+            if !(self.name_validator)(&name) {
+                return Err(PluginError::LoadError(format!(
+                    "Invalid plugin name: {:?}",
+                    name
+                )));
+            }
+            // 建立資料目錄，設定中未指定時依插件名稱決定
+            let data_dir = match config.get(DATA_DIR_CONFIG_KEY) {
+                Some(dir) => PathBuf::from(dir),
+                None => {
+                    let dir = self.data_dir_for(&name);
+                    config.insert(
+                        DATA_DIR_CONFIG_KEY.to_string(),
+                        dir.to_string_lossy().into_owned(),
+                    );
+                    dir
+                }
+            };
+            std::fs::create_dir_all(&data_dir).map_err(|e| {
+                PluginError::LoadError(format!(
+                    "Failed to create data directory {:?}: {}",
+                    data_dir, e
+                ))
+            })?;
+            // 傳遞宿主句柄 (可選的 `plugin_set_host` 符號)
+            let host_client = self.host.register_client(&name);
+            if let Some(set_host) =
+                optional_symbol::<fn(Arc<dyn HostHandle>)>(lib, b"plugin_set_host")
+            {
+                set_host(self.host.handle_for(host_client));
+            }
+            // 傳遞設定
+            if let Some(configure) =
+                optional_symbol::<fn(&HashMap<String, String>)>(lib, b"plugin_configure")
+            {
+                configure(&config);
+            }
+            // 調用加載鉤子
+            // 延遲初始化時 `on_load` 留到插件第一次被啟用時才調用
+            if !self.lazy_init {
+                timed!(timings.on_load, plugin.on_load()).map_err(|e| in_plugin(&name, e))?;
+            }
+            // 以下符號接收插件實例，多插件動態庫的每個插件可以有不同的值
+            // 讀取插件宣告的能力 (可選的 `plugin_capabilities` 符號)
+            let capabilities =
+                optional_symbol::<fn(&dyn Plugin) -> Vec<String>>(lib, b"plugin_capabilities")
+                    .map(|f| f(plugin.as_ref()))
+                    .unwrap_or_default();
+            // 讀取插件的優先級 (可選的 `plugin_priority` 符號，預設為 0)
+            let priority = optional_symbol::<fn(&dyn Plugin) -> i32>(lib, b"plugin_priority")
+                .map(|f| f(plugin.as_ref()))
+                .unwrap_or(0);
+            // 讀取插件的依賴 (可選的 `plugin_dependencies` 符號)
+            let dependencies =
+                optional_symbol::<fn(&dyn Plugin) -> Vec<String>>(lib, b"plugin_dependencies")
+                    .map(|f| f(plugin.as_ref()))
+                    .unwrap_or_default();
+            // 讀取插件會發布的事件 (可選的 `plugin_published_events` 符號)
+            let published_events =
+                optional_symbol::<fn(&dyn Plugin) -> Vec<String>>(lib, b"plugin_published_events")
+                    .map(|f| f(plugin.as_ref()))
+                    .unwrap_or_default();
+            // 讀取向下轉型函數 (可選的 `plugin_as_any` 符號)
+            let as_any = optional_symbol::<fn(&dyn Plugin) -> &dyn Any>(lib, b"plugin_as_any");
+            // 讀取健康檢查函數 (可選的 `plugin_health_check` 符號)
+            let health_check =
+                optional_symbol::<fn(&dyn Plugin) -> Result<()>>(lib, b"plugin_health_check");
+            // 讀取清理函數，缺少時卸載不會執行動態庫的清理
+            let native_unload = optional_symbol::<fn()>(lib, b"unload_plugin");
+            if native_unload.is_none() {
+                eprintln!(
+                    "Warning: plugin {} does not export unload_plugin, native cleanup will be skipped",
+                    name
+                );
+            }
+            // 讀取設定變更鉤子 (可選的 `plugin_config_change` 符號)
+            let config_change = optional_symbol::<ConfigChangeHook>(lib, b"plugin_config_change");
+            // 讀取狀態遷移鉤子 (可選的 `plugin_export_state` 與 `plugin_on_migrate` 符號)
+            let export_state = optional_symbol::<ExportStateHook>(lib, b"plugin_export_state");
+            let on_migrate = optional_symbol::<MigrateHook>(lib, b"plugin_on_migrate");
+            // 讀取批次載入完成後的鉤子 (可選的 `plugin_on_all_loaded` 符號)
+            let on_all_loaded =
+                optional_symbol::<fn(&mut dyn Plugin) -> Result<()>>(lib, b"plugin_on_all_loaded");
+            // 讀取資源用量回報函數 (可選的 `plugin_resource_usage` 符號)
+            let resource_usage =
+                optional_symbol::<fn(&dyn Plugin) -> ResourceUsage>(lib, b"plugin_resource_usage");
+            Ok(PluginEntry {
+                plugin,
+                library: Arc::clone(lib),
+                path: path.to_path_buf(),
+                temp_file: None,
+                extracted_dir: None,
+                config,
+                data_dir,
+                modified,
+                content_hash,
+                host_client,
+                load_seq: 0,
+                pending_on_load: self.lazy_init,
+                in_flight: Cell::new(0),
+                handler_time: Duration::ZERO,
+                capabilities,
+                priority,
+                dependencies,
+                published_events,
+                as_any,
+                health_check,
+                resource_usage,
+                config_change,
+                export_state,
+                on_migrate,
+                on_all_loaded,
+                native_unload,
+                state: PluginState::Loaded,
+                #[cfg(feature = "metrics")]
+                timings,
+            })
+        }
+    }
+    /// 檢查 ABI 版本並調用動態庫的建構函數，創建動態庫提供的所有插件實例
+    /// - 動態庫匯出 `create_plugins` (`fn() -> Vec<Box<dyn Plugin>>`) 時優先使用，返回空列表時視為失敗;
+    ///   否則以 [`PluginManager::create_instance`] 創建單個實例
+    /// - 啟用 `send` 功能時改為讀取 `create_plugins_send` (`fn() -> Vec<Box<dyn Plugin + Send>>`)
+    /// - 創建後以 [`PluginManager::check_instances`] 檢查每個實例的中繼資料
+    /// - 不調用任何生命週期鉤子，返回的實例必須在動態庫釋放前釋放
+    /// - `lib`: 已開啟的動態庫
+    /// - 返回值: 插件實例
+    unsafe fn create_instances(lib: &LoadedLibrary) -> Result<Vec<BoxedPlugin>> {
+        let plugins = match optional_symbol::<fn() -> Vec<BoxedPlugin>>(
+            lib,
+            CREATE_PLUGINS_SYMBOL.as_bytes(),
+        ) {
+            Some(create_plugins) => {
+                Self::check_abi_version(lib)?;
+                let plugins = create_plugins();
+                if plugins.is_empty() {
+                    return Err(PluginError::LoadError(format!(
+                        "{} returned no plugins",
+                        CREATE_PLUGINS_SYMBOL
+                    )));
+                }
+                plugins
+            }
+            None => vec![Self::create_instance(lib)?],
+        };
+        Self::check_instances(plugins)
+    }
+    /// 檢查剛創建的插件實例是否可以正常使用
+    /// - 簽名相符但 ABI 不相容 (例如不同的編譯器版本或配置器) 的動態庫可能返回損壞的實例，
+    ///   此檢查無法完全驗證實例，只能在註冊前攔截其中一部分情況
+    /// - 在 `catch_unwind` 中讀取 `name()`、`version()` 與 `description()`，
+    ///   要求它們不為空、是有效的 UTF-8、不超過長度上限且不包含控制字元 (描述可包含換行與 tab)
+    /// - 未通過檢查的實例不會被釋放 (其解構函數可能同樣損壞)，而是直接洩漏，
+    ///   其餘實例正常釋放，動態庫隨後由呼叫者卸載
+    /// - `plugins`: 動態庫返回的插件實例
+    /// - 返回值: 全部通過時返回原本的實例，否則返回第一個不相容插件的錯誤
+    fn check_instances(plugins: Vec<BoxedPlugin>) -> Result<Vec<BoxedPlugin>> {
+        let mut results: Vec<Result<()>> = plugins
+            .iter()
+            .map(|plugin| check_instance(plugin.as_ref()))
+            .collect();
+        let Some(first_error) = results.iter().position(Result::is_err) else {
+            return Ok(plugins);
+        };
+        for (plugin, result) in plugins.into_iter().zip(&results) {
+            if result.is_err() {
+                std::mem::forget(plugin);
+            }
+        }
+        Err(results.swap_remove(first_error).unwrap_err())
+    }
+    /// 檢查動態庫的 ABI 版本
+    /// - 版本由 `export_plugin!` 匯出 (`plugin_abi_version`)，手寫匯出的插件可省略，省略時不檢查
+    /// - 插件可另外匯出 `plugin_abi_min` (`fn() -> u32`) 宣告能配合的最舊版本，預設與 `plugin_abi_version` 相同;
+    ///   插件的範圍與載入器支援的範圍
+    ///   ([`MIN_SUPPORTED_ABI_VERSION`](crate::MIN_SUPPORTED_ABI_VERSION) 至
+    ///   [`PLUGIN_ABI_VERSION`](crate::PLUGIN_ABI_VERSION)) 有交集時才接受
+    /// - `lib`: 已開啟的動態庫
+    /// - 返回值: 範圍沒有交集或插件的範圍無效時返回不相容插件的錯誤
+    unsafe fn check_abi_version(lib: &LoadedLibrary) -> Result<()> {
+        let Some(abi_version) = optional_symbol::<fn() -> u32>(lib, b"plugin_abi_version") else {
+            return Ok(());
+        };
+        let version = abi_version();
+        let min = optional_symbol::<fn() -> u32>(lib, b"plugin_abi_min").map_or(version, |f| f());
+        let (host_min, host_max) = (crate::MIN_SUPPORTED_ABI_VERSION, crate::PLUGIN_ABI_VERSION);
+        if min > version {
+            return Err(incompatible_plugin_error(format!(
+                "ABI range {}..={} is invalid (minimum is above the version)",
+                min, version
+            )));
+        }
+        if version < host_min {
+            return Err(incompatible_plugin_error(format!(
+                "ABI version {} is older than the supported range {}..={}",
+                version, host_min, host_max
+            )));
+        }
+        if min > host_max {
+            return Err(incompatible_plugin_error(format!(
+                "ABI version {} or newer is required, host supports {}..={}",
+                min, host_min, host_max
+            )));
+        }
+        Ok(())
+    }
+    /// 檢查 ABI 版本並調用動態庫的建構函數
+    /// - 插件匯出 `try_create_plugin` (`fn() -> Result<Box<dyn Plugin>, String>`) 時優先使用，
+    ///   返回的錯誤會轉為 `LoadError`；否則使用 `create_plugin` (`fn() -> Box<dyn Plugin>`)
+    /// - 啟用 `send` 功能時改為讀取 `try_create_plugin_send` 與 `create_plugin_send`
+    ///   (返回 `Box<dyn Plugin + Send>`)，兩者都不存在時返回不相容插件的錯誤
+    /// - 不調用任何生命週期鉤子，返回的實例必須在動態庫釋放前釋放
+    /// - `lib`: 已開啟的動態庫
+    /// - 返回值: 插件實例
+    unsafe fn create_instance(lib: &LoadedLibrary) -> Result<BoxedPlugin> {
+        Self::check_abi_version(lib)?;
+        // 創建插件實例，優先使用可回報失敗的 `try_create_plugin`
+        match optional_symbol::<fn() -> std::result::Result<BoxedPlugin, String>>(
+            lib,
+            TRY_CREATE_PLUGIN_SYMBOL.as_bytes(),
+        ) {
+            Some(try_create_plugin) => try_create_plugin()
+                .map_err(|e| PluginError::LoadError(format!("Failed to create plugin: {}", e))),
+            None => {
+                // 獲取創建插件函數
+                let create_plugin =
+                    optional_symbol::<fn() -> BoxedPlugin>(lib, CREATE_PLUGIN_SYMBOL.as_bytes())
+                        .ok_or_else(|| {
+                            incompatible_plugin_error(format!(
+                                "Failed to get {} symbol: symbol not found",
+                                CREATE_PLUGIN_SYMBOL
+                            ))
+                        })?;
+                Ok(create_plugin())
+            }
+        }
+    }
+    /// 重新載入插件
+    /// - 從插件原本的檔案路徑重新載入，並保留原本的啟用/禁用狀態
+    /// - 舊實例匯出 `plugin_export_state` 且新實例匯出 `plugin_on_migrate` 時，
+    ///   會在新實例啟用前把舊實例的狀態交給新實例 (見 [`PluginManager::migrate_state`])
+    /// - 新的插件實例會先完整建立 (含加載與啟用鉤子)，之後才在同一次 `&mut self`
+    ///   操作中一併替換舊條目與事件訂閱，因此 `broadcast_event` 不會看到重新載入到一半的插件;
+    ///   重新載入前後送出的事件只會由舊實例或新實例其中之一處理，不會遺失
+    /// - 新插件載入或啟用失敗時，舊插件維持不變
+    /// - `name`: 插件名稱
+    /// - 返回值: 成功或失敗的結果
+    pub fn reload_plugin(&mut self, name: &str) -> Result<()> {
+        self.replace_plugin(name, None, true)?;
+        println!("Reloaded plugin: {}", name);
+        Ok(())
+    }
+    /// 重新載入所有插件
+    /// - 依名稱順序逐一以 [`PluginManager::reload_plugin`] 重新載入，保留原本的啟用/禁用狀態;
+    ///   重新載入失敗的插件維持舊實例，不影響其他插件
+    /// - 返回值: 載入報告，`loaded` 為成功重新載入的插件名稱
+    pub fn reload_all_plugins(&mut self) -> LoadReport {
+        let mut report = LoadReport::default();
+        for name in self.sorted_names() {
+            let path = self.plugins[&name].path.clone();
+            match self.reload_plugin(&name) {
+                Ok(()) => report.loaded.push(name),
+                Err(e) => {
+                    eprintln!("Failed to reload plugin {}: {}", name, e);
+                    report.failed.push((path, e.to_string()));
+                }
+            }
+        }
+        report
+    }
+    /// 獲取檔案已在載入後更新的插件
+    /// - 先比較插件檔案目前的修改時間與載入時記錄的修改時間，修改時間較新時才讀取整個檔案，
+    ///   比較內容雜湊值；內容未改變 (例如建置系統以相同內容重寫檔案) 的插件不視為過期
+    /// - 從記憶體或封存檔載入的插件使用管理器建立的暫存檔案，不會被視為過期
+    /// - 返回值: 過期插件的名稱，依名稱排序
+    pub fn stale_plugins(&self) -> Vec<String> {
+        self.sorted_names()
+            .into_iter()
+            .filter(|name| self.check_stale(&self.plugins[name]) == Staleness::Changed)
+            .collect()
+    }
+    /// 檢查插件檔案在載入後是否更新
+    /// - `entry`: 插件條目
+    /// - 返回值: 檢查結果
+    fn check_stale(&self, entry: &PluginEntry) -> Staleness {
+        if entry.temp_file.is_some() {
+            return Staleness::Unchanged;
+        }
+        let current = match (entry.modified, file_modified(&entry.path)) {
+            (Some(loaded), Some(current)) if current > loaded => current,
+            _ => return Staleness::Unchanged,
+        };
+        match (entry.content_hash, file_hash(&entry.path)) {
+            (Some(loaded), Some(hash)) if loaded == hash => Staleness::Touched(current),
+            _ => Staleness::Changed,
+        }
+    }
+    /// 重新載入所有檔案已更新的插件
+    /// - 以 [`PluginManager::stale_plugins`] 的規則找出過期插件，並逐一以
+    ///   [`PluginManager::reload_plugin`] 重新載入，保留原本的啟用/禁用狀態；
+    ///   重新載入失敗的插件維持舊實例
+    /// - 修改時間較新但內容未改變的插件不會重新載入，只更新記錄的修改時間，
+    ///   之後的檢查不需要再讀取檔案
+    /// - 適合由宿主自行定期調用，作為檔案監看之外的輪詢方式
+    /// - 返回值: 載入報告，`loaded` 為成功重新載入的插件名稱
+    pub fn refresh_stale(&mut self) -> LoadReport {
+        let mut report = LoadReport::default();
+        let mut stale = Vec::new();
+        for name in self.sorted_names() {
+            match self.check_stale(&self.plugins[&name]) {
+                Staleness::Unchanged => {}
+                Staleness::Touched(modified) => {
+                    if let Some(entry) = self.plugins.get_mut(&name) {
+                        entry.modified = Some(modified);
+                    }
+                }
+                Staleness::Changed => stale.push(name),
+            }
+        }
+        for name in stale {
+            let path = self.plugins[&name].path.clone();
+            match self.reload_plugin(&name) {
+                Ok(()) => report.loaded.push(name),
+                Err(e) => {
+                    eprintln!("Failed to refresh plugin {}: {}", name, e);
+                    report.failed.push((path, e.to_string()));
+                }
+            }
+        }
+        report
+    }
+    /// 以另一個插件檔案替換已載入的插件
+    /// - 替換方式與 [`PluginManager::reload_plugin`] 相同: 新插件完整建立後才一次性替換，
+    ///   並沿用舊插件的註冊名稱、設定、事件訂閱方式與啟用/禁用狀態
+    /// - 新插件的 `name()` 必須與舊插件相同，不同時可改用 [`PluginManager::force_swap_plugin`]
+    /// - 與重新載入相同，會在新實例啟用前遷移舊實例的狀態
+    /// - 新插件載入或啟用失敗時，舊插件維持不變
+    /// - `name`: 插件的註冊名稱
+    /// - `new_path`: 新插件檔案的路徑
+    /// - 返回值: 成功或失敗的結果
+    pub fn swap_plugin(&mut self, name: &str, new_path: &Path) -> Result<()> {
+        self.replace_plugin(name, Some(new_path), true)?;
+        println!("Swapped plugin: {} -> {:?}", name, new_path);
+        Ok(())
+    }
+    /// 以另一個插件檔案替換已載入的插件，不檢查新插件的 `name()`
+    /// - 替換後插件仍以原本的註冊名稱存取，行為與以別名載入的插件相同
+    /// - `name`: 插件的註冊名稱
+    /// - `new_path`: 新插件檔案的路徑
+    /// - 返回值: 成功或失敗的結果
+    pub fn force_swap_plugin(&mut self, name: &str, new_path: &Path) -> Result<()> {
+        self.replace_plugin(name, Some(new_path), false)?;
+        println!("Swapped plugin: {} -> {:?}", name, new_path);
+        Ok(())
+    }
+    /// 替換已載入插件的實際實作
+    /// - `name`: 插件的註冊名稱
+    /// - `new_path`: 新插件檔案的路徑，`None` 表示從原本的路徑重新載入
+    /// - `check_name`: 是否要求新插件的 `name()` 與舊插件相同
+    /// - 返回值: 成功或失敗的結果
+    fn replace_plugin(
+        &mut self,
+        name: &str,
+        new_path: Option<&Path>,
+        check_name: bool,
+    ) -> Result<()> {
+        self.check_frozen(PluginError::LoadError, "replace plugins")?;
+        let (old_path, old_state, plugin_name, config, load_seq) = match self.plugins.get(name) {
+            Some(entry) => (
+                entry.path.clone(),
+                entry.state.clone(),
+                entry.plugin.name().to_string(),
+                entry.config.clone(),
+                entry.load_seq,
+            ),
+            None => {
+                return Err(PluginError::LoadError(format!(
+                    "Plugin {} is not loaded",
+                    name
+                )))
+            }
+        };
+        let path = new_path.map_or(old_path.clone(), Path::to_path_buf);
+
+        // 1. 完整建立新的插件實例
+        // 檢查名稱時只建立同名的插件，因此也能替換由多插件動態庫提供的插件
+        let select = check_name.then_some(plugin_name.as_str());
+        let opened = Self::open_library(&*self.loader, &path)?;
+        let entries = self.instantiate_plugins(opened, config, select)?;
+        let mut new_entry = Self::single_entry(&path, entries)?;
+        new_entry.load_seq = load_seq;
+        if check_name && new_entry.plugin.name() != plugin_name {
+            let error_msg = format!(
+                "Replacement plugin reports name {} instead of {}",
+                new_entry.plugin.name(),
+                plugin_name
+            );
+            let _ = Self::finalize_entry(new_entry);
+            return Err(PluginError::LoadError(error_msg));
+        }
+        if let Err(e) = self.check_subscriptions(name, new_entry.plugin.as_ref()) {
+            let _ = Self::finalize_entry(new_entry);
+            return Err(e);
+        }
+        if let Err(e) = Self::migrate_state(&self.plugins[name], &mut new_entry) {
+            let _ = Self::finalize_entry(new_entry);
+            return Err(in_plugin(name, e));
+        }
+        new_entry.state = match old_state {
+            PluginState::Enabled => {
+                if let Err(e) = new_entry.run_enable_hooks() {
+                    let _ = Self::finalize_entry(new_entry);
+                    return Err(in_plugin(name, e));
+                }
+                PluginState::Enabled
+            }
+            PluginState::Disabled => PluginState::Disabled,
+            _ => PluginState::Loaded,
+        };
+        if new_entry.state != old_state {
+            self.state_notifier.notify(name, &new_entry.state);
+        }
+
+        // 2. 一次性替換事件訂閱與插件條目
+        self.host.bind_client(new_entry.host_client, name);
+        let new_events = new_entry.plugin.subscribed_events();
+        let Some(mut old_entry) = self.plugins.insert(name.to_string(), new_entry) else {
+            unreachable!("plugin {} was checked above", name);
+        };
+        // 由管理器建立的檔案改由新條目管理，避免釋放舊插件時被刪除;
+        // 設定可能指向解壓縮的資源，因此資源目錄一律轉移，暫存檔案只在從同一路徑載入時轉移
+        if let Some(entry) = self.plugins.get_mut(name) {
+            if path == old_path {
+                entry.temp_file = old_entry.temp_file.take();
+            }
+            entry.extracted_dir = old_entry.extracted_dir.take();
+        }
+        self.invalidate_dispatch_order();
+        for event in old_entry.plugin.subscribed_events() {
+            self.event_bus.unsubscribe(&event, name);
+        }
+        for event in new_events {
+            let _ = self.event_bus.subscribe(&event, name);
+        }
+
+        // 3. 釋放舊插件
+        if old_entry.state == PluginState::Enabled {
+            if let Err(e) = old_entry.plugin.on_disable() {
+                eprintln!("Error disabling old instance of plugin {}: {}", name, e);
+            }
+        }
+        self.host.forget_client(old_entry.host_client);
+        if let Err(e) = Self::finalize_entry(old_entry) {
+            eprintln!("Error unloading old instance of plugin {}: {}", name, e);
+        }
+        Ok(())
+    }
+    /// 把舊實例的狀態遷移到新實例
+    /// - 插件透過匯出可選的 `plugin_export_state` 符號 (`fn(&dyn Plugin) -> Option<MigrationBlob>`)
+    ///   匯出狀態，並透過 `plugin_on_migrate` 符號
+    ///   (`fn(&mut dyn Plugin, &str, MigrationBlob) -> Result<()>`) 接收舊實例的版本與狀態
+    /// - 新實例沒有遷移鉤子時不會要求舊實例匯出狀態；舊實例沒有匯出鉤子或返回 `None` 時不調用遷移鉤子
+    /// - 延遲初始化時遷移鉤子會在 `on_load` 之前調用
+    /// - `old_entry`: 舊的插件條目
+    /// - `new_entry`: 尚未啟用的新插件條目
+    /// - 返回值: 成功或失敗的結果，遷移鉤子返回的錯誤會原樣返回
+    fn migrate_state(old_entry: &PluginEntry, new_entry: &mut PluginEntry) -> Result<()> {
+        let Some(on_migrate) = new_entry.on_migrate else {
+            return Ok(());
+        };
+        let Some(state) = old_entry
+            .export_state
+            .and_then(|export_state| export_state(old_entry.plugin.as_ref()))
+        else {
+            return Ok(());
+        };
+        let from_version = old_entry.plugin.version();
+        on_migrate(new_entry.plugin.as_mut(), from_version, state)?;
+        println!(
+            "Migrated state of plugin {} from version {} to {}",
+            new_entry.plugin.name(),
+            from_version,
+            new_entry.plugin.version()
+        );
+        Ok(())
+    }
+    /// 啟用插件
+    /// - `on_enable` 失敗時插件進入 `Error` 狀態，可用 [`PluginManager::recover_plugin`] 重試
+    /// - `name`: 插件名稱
+    /// - 返回值: 成功或失敗的結果
+    pub fn enable_plugin(&mut self, name: &str) -> Result<()> {
+        self.check_frozen(PluginError::EnableError, "enable plugins")?;
+        self.enable_entry(name)
+    }
+    /// 啟用插件，並重新啟用先前由 [`PluginManager::disable_plugin_cascade`] 一併禁用的依賴者
+    /// - 依賴者依禁用的相反順序啟用，被依賴者先於依賴者啟用；
+    ///   已卸載或已不在禁用狀態 (例如已另外啟用) 的依賴者會被略過
+    /// - 沒有連鎖禁用記錄時與 [`PluginManager::enable_plugin`] 相同
+    /// - 任一插件啟用失敗時立即返回錯誤，尚未啟用的依賴者仍保留記錄，可再次調用重試
+    /// - `name`: 插件名稱
+    /// - 返回值: 依啟用順序排列的插件名稱 (包含 `name` 本身)，失敗時返回錯誤
+    pub fn enable_plugin_cascade(&mut self, name: &str) -> Result<Vec<String>> {
+        self.check_frozen(PluginError::EnableError, "enable plugins")?;
+        self.enable_entry(name)?;
+        let mut enabled = vec![name.to_string()];
+        let mut dependents = self.cascade_disabled.remove(name).unwrap_or_default();
+        while let Some(other) = dependents.pop() {
+            let disabled = self
+                .plugins
+                .get(&other)
+                .is_some_and(|entry| entry.state == PluginState::Disabled);
+            if !disabled {
+                continue;
+            }
+            if let Err(e) = self.enable_entry(&other) {
+                if !dependents.is_empty() {
+                    self.cascade_disabled.insert(name.to_string(), dependents);
+                }
+                return Err(e);
+            }
+            enabled.push(other);
+        }
+        Ok(enabled)
+    }
+    /// 啟用插件的實際實作，不檢查凍結狀態
+    /// - `name`: 插件名稱
+    /// - 返回值: 成功或失敗的結果
+    fn enable_entry(&mut self, name: &str) -> Result<()> {
+        if self.safe_mode {
+            return Err(PluginError::EnableError(format!(
+                "Cannot enable plugin {}: safe mode is active",
+                name
+            )));
+        }
+        let Some(entry) = self.plugins.get_mut(name) else {
+            return Err(PluginError::EnableError(format!(
+                "Plugin {} is not loaded",
+                name
+            )));
+        };
+        if entry.state == PluginState::Enabled {
+            return Ok(());
+        }
+        if entry.state == PluginState::Loaded || entry.state == PluginState::Disabled {
+            if let Err(e) = entry.run_enable_hooks() {
+                entry.state = PluginState::Error(e.to_string());
+                self.state_notifier.notify(name, &entry.state);
+                self.record_error_transition();
+                return Err(in_plugin(name, e));
+            }
+            entry.state = PluginState::Enabled;
+            self.state_notifier.notify(name, &entry.state);
+            println!("Enabled plugin: {}", name);
+            return Ok(());
+        }
+        Err(in_plugin(
+            name,
+            PluginError::EnableError("Can't enable plugin".into()),
+        ))
+    }
+    /// 記錄插件進入錯誤狀態，短時間內達到門檻時進入安全模式
+    fn record_error_transition(&mut self) {
+        let Some((count, window)) = self.safe_mode_threshold else {
+            return;
+        };
+        let now = Instant::now();
+        self.error_transitions.push_back(now);
+        while self
+            .error_transitions
+            .front()
+            .is_some_and(|&at| now.duration_since(at) > window)
+        {
+            self.error_transitions.pop_front();
+        }
+        if !self.safe_mode && self.error_transitions.len() >= count {
+            eprintln!(
+                "{} plugins entered error state within {:?}, entering safe mode",
+                self.error_transitions.len(),
+                window
+            );
+            self.safe_mode = true;
+            // 只禁用仍在啟用中的插件，依賴者先於其依賴禁用
+            for name in self.unload_order() {
+                if self.plugins[&name].state != PluginState::Enabled {
+                    continue;
+                }
+                if let Err(e) = self.disable_entry(&name) {
+                    eprintln!("Error disabling plugin {} in safe mode: {}", name, e);
+                }
+            }
+        }
+    }
+    /// 設定進入安全模式的門檻
+    /// - 在 `window` 時間內有 `count` 次插件進入 `Error` 狀態時，視為系統性問題 (例如共用依賴失效)，
+    ///   管理器會進入安全模式並禁用所有已啟用的插件 (依賴者先於其依賴禁用)，
+    ///   其他狀態的插件 (含進入錯誤狀態的插件) 維持原狀
+    /// - 安全模式下 `enable_plugin` (含自動啟用與 `recover_plugin`) 一律返回錯誤，
+    ///   直到調用 [`PluginManager::clear_safe_mode`]
+    /// - `threshold`: (次數, 時間範圍)，`None` 表示不啟用安全模式，預設為 `None`
+    pub fn set_safe_mode_threshold(&mut self, threshold: Option<(usize, Duration)>) {
+        self.safe_mode_threshold = threshold;
+    }
+    /// 設定事件派送的斷路器
+    /// - 插件對同一事件連續 `failures` 次處理失敗時斷開，`cooldown` 時間內不再將此事件派送給該插件;
+    ///   冷卻期過後允許一次試驗派送，成功時關閉，失敗時重新斷開並再次冷卻
+    /// - 每個 (插件, 事件) 獨立計算，其他事件照常派送；插件卸載時清除其所有記錄
+    /// - 更改設定不會清除既有的失敗記錄
+    /// - `breaker`: (連續失敗次數, 冷卻時間)，`None` 表示不啟用斷路器，預設為 `None`
+    pub fn set_circuit_breaker(&mut self, breaker: Option<(u32, Duration)>) {
+        self.circuit_breaker = breaker;
+        if breaker.is_none() {
+            self.circuits.clear();
+        }
+    }
+    /// 查詢插件對指定事件的斷路器狀態
+    /// - `plugin`: 插件名稱
+    /// - `event`: 事件名稱
+    /// - 返回值: 斷路器狀態，未啟用斷路器或沒有失敗記錄時為 `Closed`
+    pub fn circuit_state(&self, plugin: &str, event: &str) -> CircuitState {
+        let Some((_, cooldown)) = self.circuit_breaker else {
+            return CircuitState::Closed;
+        };
+        if self.circuits.is_empty() {
+            return CircuitState::Closed;
+        }
+        let opened_at = self
+            .circuits
+            .get(&(plugin.to_string(), event.to_string()))
+            .and_then(|breaker| breaker.opened_at);
+        match opened_at {
+            None => CircuitState::Closed,
+            Some(at) if at.elapsed() < cooldown => CircuitState::Open,
+            Some(_) => CircuitState::HalfOpen,
+        }
+    }
+    /// 檢查斷路器是否允許派送
+    /// - `name`: 插件名稱
+    /// - `event`: 事件名稱
+    /// - 返回值: 斷路器未斷開或已進入半開狀態時為 `true`
+    fn circuit_allows(&self, name: &str, event: &str) -> bool {
+        self.circuit_state(name, event) != CircuitState::Open
+    }
+    /// 記錄處理結果並更新斷路器
+    /// - 成功時清除記錄；失敗達到門檻 (含半開狀態的試驗失敗) 時斷開並重新開始冷卻
+    /// - `name`: 插件名稱
+    /// - `event`: 事件名稱
+    /// - `success`: 是否處理成功
+    fn record_circuit(&mut self, name: &str, event: &str, success: bool) {
+        let Some((threshold, cooldown)) = self.circuit_breaker else {
+            return;
+        };
+        if success && self.circuits.is_empty() {
+            return;
+        }
+        let key = (name.to_string(), event.to_string());
+        if success {
+            self.circuits.remove(&key);
+            return;
+        }
+        let breaker = self.circuits.entry(key).or_default();
+        breaker.failures = breaker.failures.saturating_add(1);
+        if breaker.failures >= threshold {
+            breaker.opened_at = Some(Instant::now());
+            eprintln!(
+                "Plugin {} failed event {} {} consecutive times, skipping it for {:?}",
+                name, event, breaker.failures, cooldown
+            );
+        }
+    }
+    /// 檢查管理器是否處於安全模式
+    pub fn is_safe_mode(&self) -> bool {
+        self.safe_mode
+    }
+    /// 離開安全模式
+    /// - 清除錯誤狀態的記錄，之後可再次啟用插件；已禁用的插件不會自動重新啟用
+    pub fn clear_safe_mode(&mut self) {
+        self.safe_mode = false;
+        self.error_transitions.clear();
+    }
+    /// 啟用插件，失敗時以指數退避重試
+    /// - 第 n 次失敗後等待 `base_delay * 2^(n-1)` 再重試，重試期間插件維持原本的狀態;
+    ///   全部嘗試都失敗時與 `enable_plugin` 相同進入 `Error` 狀態
+    /// - 不重新載入動態庫，只重新調用 `on_enable`，適合依賴外部服務 (資料庫、socket) 而暫時失敗的插件
+    /// - 等待期間會阻塞目前的執行緒
+    /// - `name`: 插件名稱
+    /// - `attempts`: 最多嘗試的次數，0 視為 1
+    /// - `base_delay`: 第一次重試前的等待時間
+    /// - 返回值: 成功或失敗的結果，失敗時為最後一次的錯誤
+    pub fn enable_plugin_with_retry(
+        &mut self,
+        name: &str,
+        attempts: u32,
+        base_delay: Duration,
+    ) -> Result<()> {
+        self.check_frozen(PluginError::EnableError, "enable plugins")?;
+        for attempt in 1..attempts {
+            if self.safe_mode {
+                break;
+            }
+            let Some(entry) = self.plugins.get_mut(name) else {
+                break;
+            };
+            if entry.state != PluginState::Loaded && entry.state != PluginState::Disabled {
+                break;
+            }
+            match entry.run_enable_hooks() {
+                Ok(()) => {
+                    entry.state = PluginState::Enabled;
+                    self.state_notifier.notify(name, &entry.state);
+                    println!("Enabled plugin: {} (attempt {})", name, attempt);
+                    return Ok(());
+                }
+                Err(e) => {
+                    let delay = base_delay
+                        .checked_mul(2u32.saturating_pow(attempt - 1))
+                        .unwrap_or(Duration::MAX);
+                    eprintln!(
+                        "Failed to enable plugin {} (attempt {}/{}): {}, retrying in {:?}",
+                        name, attempt, attempts, e, delay
+                    );
+                    std::thread::sleep(delay);
+                }
+            }
+        }
+        // 最後一次嘗試，失敗時進入 `Error` 狀態；不可重試的情況也由此返回對應的錯誤
+        self.enable_plugin(name)
+    }
+    /// 從錯誤狀態恢復插件
+    /// - 不重新載入動態庫，只將狀態重設為 `Loaded` 後再次調用 `on_enable`，
+    ///   適合重試暫時性的啟用失敗 (例如依賴的服務稍後才可用)
+    /// - 恢復失敗時插件維持 `Error` 狀態，錯誤訊息更新為本次的錯誤
+    /// - `name`: 插件名稱
+    /// - 返回值: 成功或失敗的結果，插件不在 `Error` 狀態時返回錯誤
+    pub fn recover_plugin(&mut self, name: &str) -> Result<()> {
+        self.check_frozen(PluginError::EnableError, "recover plugins")?;
+        let Some(entry) = self.plugins.get_mut(name) else {
+            return Err(PluginError::EnableError(format!(
+                "Plugin {} is not loaded",
+                name
+            )));
+        };
+        if !matches!(entry.state, PluginState::Error(_)) {
+            return Err(PluginError::EnableError(format!(
+                "Plugin {} is not in error state",
+                name
+            )));
+        }
+        entry.state = PluginState::Loaded;
+        self.state_notifier.notify(name, &entry.state);
+        self.enable_entry(name)?;
+        println!("Recovered plugin: {}", name);
+        Ok(())
+    }
+    /// 禁用插件
+    /// - 仍有已啟用的插件依賴此插件時會發出警告並列出這些插件，嚴格模式下改為返回錯誤;
+    ///   需要一併禁用依賴者時可改用 [`PluginManager::disable_plugin_cascade`]
+    /// - `name`: 插件名稱
+    /// - 返回值: 成功或失敗的結果
+    pub fn disable_plugin(&mut self, name: &str) -> Result<()> {
+        self.check_frozen(PluginError::DisableError, "disable plugins")?;
+        let dependents: Vec<String> = self
+            .direct_dependents(name)
+            .into_iter()
+            .filter(|other| self.plugins[other].state == PluginState::Enabled)
+            .collect();
+        if !dependents.is_empty()
+            && self
+                .plugins
+                .get(name)
+                .is_some_and(|entry| entry.state == PluginState::Enabled)
+        {
+            let error_msg = format!(
+                "Plugin {} is still required by enabled plugins: {}",
+                name,
+                dependents.join(", ")
+            );
+            if self.strict {
+                return Err(PluginError::DisableError(error_msg));
+            }
+            eprintln!("Warning: {}", error_msg);
+        }
+        self.disable_entry(name)
+    }
+    /// 禁用插件及所有直接或間接依賴它的已啟用插件
+    /// - 依賴者會先於被依賴者禁用 (與 [`PluginManager::unload_all_plugins`] 的卸載順序相同)
+    /// - 一併禁用的依賴者會被記錄，之後可用 [`PluginManager::enable_plugin_cascade`] 重新啟用
+    /// - 任一插件禁用失敗時立即返回錯誤，先前已禁用的插件維持禁用
+    /// - `name`: 插件名稱
+    /// - 返回值: 依禁用順序排列的插件名稱 (包含 `name` 本身)，失敗時返回錯誤
+    pub fn disable_plugin_cascade(&mut self, name: &str) -> Result<Vec<String>> {
+        self.check_frozen(PluginError::DisableError, "disable plugins")?;
+        if !self.plugins.contains_key(name) {
+            return Err(PluginError::DisableError(format!(
+                "Plugin {} is not loaded",
+                name
+            )));
+        }
+        let mut dependents = HashSet::new();
+        let mut pending = vec![name.to_string()];
+        while let Some(current) = pending.pop() {
+            for other in self.direct_dependents(&current) {
+                if other != name && dependents.insert(other.clone()) {
+                    pending.push(other);
+                }
+            }
+        }
+        let order: Vec<String> = self
+            .unload_order()
+            .into_iter()
+            .filter(|other| {
+                dependents.contains(other) && self.plugins[other].state == PluginState::Enabled
+            })
+            .collect();
+        let mut disabled = Vec::with_capacity(order.len() + 1);
+        let mut result = Ok(());
+        for other in order {
+            result = self.disable_entry(&other);
+            if result.is_err() {
+                break;
+            }
+            disabled.push(other);
+        }
+        // 即使中途失敗，已禁用的依賴者也要記錄，之後才能重新啟用
+        if !disabled.is_empty() {
+            self.cascade_disabled
+                .entry(name.to_string())
+                .or_default()
+                .extend(disabled.iter().cloned());
+        }
+        result?;
+        self.disable_entry(name)?;
+        disabled.push(name.to_string());
+        Ok(disabled)
+    }
+    /// 禁用插件的實際實作，不檢查凍結狀態
+    /// - `name`: 插件名稱
+    /// - 返回值: 成功或失敗的結果
+    fn disable_entry(&mut self, name: &str) -> Result<()> {
+        let Some(entry) = self.plugins.get_mut(name) else {
+            return Err(PluginError::DisableError(format!(
+                "Plugin {} is not loaded",
+                name
+            )));
+        };
+        if entry.state == PluginState::Disabled {
+            return Ok(());
+        }
+        if entry.state == PluginState::Enabled {
+            entry.plugin.on_disable().map_err(|e| in_plugin(name, e))?;
+            entry.state = PluginState::Disabled;
+            self.state_notifier.notify(name, &entry.state);
+            println!("Disabled plugin: {}", name);
+            return Ok(());
+        }
+        Err(in_plugin(
+            name,
+            PluginError::DisableError("Can't disable plugin".into()),
+        ))
+    }
+    /// 卸載插件
+    /// - 仍有其他已載入的插件依賴此插件時會拒絕卸載，可改用 [`PluginManager::force_unload_plugin`]
+    /// - 插件不存在時返回錯誤，與 `enable_plugin`、`disable_plugin` 一致，
+    ///   可藉此分辨實際卸載與名稱錯誤的情況
+    /// - 已啟用的插件會先禁用，`on_disable` 失敗時依 [`PluginManager::set_disable_error_policy`]
+    ///   的設定繼續卸載 (預設) 或返回錯誤
+    /// - 管理器會記錄 [`PluginManager::with_library`] 與事件處理期間的借用;
+    ///   安全的程式碼中借用檢查器已保證卸載時沒有借用，若經由 unsafe 程式碼在借用期間卸載，
+    ///   debug 組建會 panic。從符號複製出的指針不受此檢查保護
+    /// - `name`: 插件名稱
+    /// - 返回值: 卸載結果，包含是否執行了動態庫的 `unload_plugin` 清理函數
+    pub fn unload_plugin(&mut self, name: &str) -> Result<UnloadOutcome> {
+        self.unload_plugin_inner(name, false)
+    }
+    /// 強制卸載插件，忽略其他插件對它的依賴
+    /// - 依賴此插件的插件會保持載入，可能因此無法正常運作
+    /// - `name`: 插件名稱
+    /// - 返回值: 卸載結果，包含是否執行了動態庫的 `unload_plugin` 清理函數
+    pub fn force_unload_plugin(&mut self, name: &str) -> Result<UnloadOutcome> {
+        self.unload_plugin_inner(name, true)
+    }
+    /// 卸載插件的實際實作
+    /// - `name`: 插件名稱
+    /// - `force`: 是否忽略其他插件對它的依賴
+    fn unload_plugin_inner(&mut self, name: &str, force: bool) -> Result<UnloadOutcome> {
+        self.check_frozen(PluginError::LoadError, "unload plugins")?;
+        if !self.plugins.contains_key(name) {
+            return Err(PluginError::LoadError(format!(
+                "Plugin {} is not loaded",
+                name
+            )));
+        }
+        if !force {
+            let blockers = self.direct_dependents(name);
+            if !blockers.is_empty() {
+                return Err(PluginError::LoadError(format!(
+                    "Plugin {} is still required by: {}",
+                    name,
+                    blockers.join(", ")
+                )));
+            }
+        }
+
+        let entry = &self.plugins[name];
+        // 1. 創建一個事件訂閱的副本
+        let events = entry.plugin.subscribed_events();
+        let enabled = entry.state == PluginState::Enabled;
+
+        // 2. 執行禁用邏輯 (僅對已啟用的插件)
+        if enabled {
+            if let Err(e) = self.disable_entry(name) {
+                if self.disable_error_policy == DisableErrorPolicy::StrictDisable {
+                    return Err(e);
+                }
+                eprintln!(
+                    "Failed to disable plugin {} before unloading, unloading anyway: {}",
+                    name, e
+                );
+            }
+        }
+
+        // 3. 取消訂閱所有事件
+        self.invalidate_dispatch_order();
+        for event in events {
+            self.event_bus.unsubscribe(&event, name);
+        }
+
+        // 4. 獲取插件實例並執行卸載操作
+        self.event_failures.remove(name);
+        self.circuits.retain(|(plugin, _), _| plugin != name);
+        for weights in self.round_robin.values_mut() {
+            weights.remove(name);
+        }
+        self.cascade_disabled.remove(name);
+        self.host.clear_logs(name);
+        self.cancel_scheduled_for(name);
+        self.host.mark_unloaded(name);
+        let entry = self.plugins.remove(name).expect("plugin is loaded");
+        self.host.forget_client(entry.host_client);
+        self.state_notifier.notify(name, &PluginState::Unloaded);
+        let data_dir = entry.data_dir.clone();
+        let ret = Self::finalize_entry(entry);
+        if self.purge_data_on_unload {
+            if let Err(e) = std::fs::remove_dir_all(&data_dir) {
+                eprintln!(
+                    "Failed to remove plugin data directory {:?}: {}",
+                    data_dir, e
+                );
+            }
+        }
+        let outcome = ret?;
+        println!("Unloaded plugin: {}", name);
+        Ok(outcome)
+    }
+    /// 獲取插件直接依賴的插件
+    /// - 依插件宣告的 `plugin_dependencies` 計算，只包含直接依賴，不展開間接依賴;
+    ///   宣告的依賴不一定已載入
+    /// - `name`: 插件名稱
+    /// - 返回值: 依賴的插件名稱，依名稱排序且不重複，插件不存在時為空
+    pub fn dependencies_of(&self, name: &str) -> Vec<String> {
+        let Some(entry) = self.plugins.get(name) else {
+            return Vec::new();
+        };
+        let mut dependencies: Vec<String> = entry
+            .dependencies
+            .iter()
+            .filter(|dependency| *dependency != name)
+            .cloned()
+            .collect();
+        dependencies.sort();
+        dependencies.dedup();
+        dependencies
+    }
+    /// 獲取直接依賴指定插件的已載入插件
+    /// - 與 [`PluginManager::dependencies_of`] 方向相反，只包含直接依賴者;
+    ///   仍有依賴者時 [`PluginManager::unload_plugin`] 會拒絕卸載
+    /// - `name`: 插件名稱
+    /// - 返回值: 依賴此插件的插件名稱，依名稱排序且不重複，沒有依賴者或插件不存在時為空
+    pub fn dependents_of(&self, name: &str) -> Vec<String> {
+        self.direct_dependents(name)
+    }
+    /// 獲取直接依賴指定插件的已載入插件
+    /// - `name`: 插件名稱
+    /// - 返回值: 依賴此插件的插件名稱，依名稱排序
+    fn direct_dependents(&self, name: &str) -> Vec<String> {
+        let mut dependents: Vec<String> = self
+            .plugins
+            .iter()
+            .filter(|(other, entry)| *other != name && entry.dependencies.iter().any(|d| d == name))
+            .map(|(other, _)| other.clone())
+            .collect();
+        dependents.sort();
+        dependents
+    }
+    /// 計算啟用順序 (拓撲排序)
+    /// - 被依賴者會排在依賴者之前，同一層依名稱排序
+    /// - 有循環依賴的插件會依名稱排在最後
+    /// - 返回值: 插件名稱的啟用順序
+    fn enable_order(&self) -> Vec<String> {
+        let mut remaining = self.sorted_names();
+        let mut order = Vec::with_capacity(remaining.len());
+        while !remaining.is_empty() {
+            // 不依賴其他剩餘插件的插件可以先啟用
+            let (ready, blocked): (Vec<String>, Vec<String>) =
+                remaining.iter().cloned().partition(|name| {
+                    !self.plugins[name]
+                        .dependencies
+                        .iter()
+                        .any(|dependency| dependency != name && remaining.contains(dependency))
+                });
+            if ready.is_empty() {
+                order.extend(blocked);
+                break;
+            }
+            order.extend(ready);
+            remaining = blocked;
+        }
+        order
+    }
+    /// 計算卸載順序 (反向拓撲排序)
+    /// - 依賴者會排在被依賴者之前，同一層依名稱排序
+    /// - 有循環依賴的插件會依名稱排在最後
+    /// - 返回值: 插件名稱的卸載順序
+    fn unload_order(&self) -> Vec<String> {
+        let mut remaining = self.sorted_names();
+        let mut order = Vec::with_capacity(remaining.len());
+        while !remaining.is_empty() {
+            // 沒有被其他剩餘插件依賴的插件可以先卸載
+            let (ready, blocked): (Vec<String>, Vec<String>) =
+                remaining.iter().cloned().partition(|name| {
+                    !remaining.iter().any(|other| {
+                        other != name && self.plugins[other].dependencies.contains(name)
+                    })
+                });
+            if ready.is_empty() {
+                order.extend(blocked);
+                break;
+            }
+            order.extend(ready);
+            remaining = blocked;
+        }
+        order
+    }
+    /// 對已移出管理器的插件條目執行卸載鉤子與標準卸載程序
+    /// - 動態庫仍被同一動態庫的其他插件使用時只調用卸載鉤子並釋放實例，
+    ///   動態庫的清理函數、動態庫本身與暫存檔案在最後一個插件卸載時才釋放
+    /// - `entry`: 要釋放的插件條目
+    /// - 返回值: 卸載結果，卸載鉤子失敗時仍會執行標準卸載程序並返回錯誤
+    fn finalize_entry(mut entry: PluginEntry) -> Result<UnloadOutcome> {
+        // 安全的程式碼無法在借用期間取得 `&mut self`，此處只會捕捉經由 unsafe 或原始指針的誤用
+        debug_assert_eq!(
+            entry.in_flight.get(),
+            0,
+            "plugin {} was unloaded while still borrowed",
+            entry.plugin.name()
+        );
+        // 調用卸載鉤子
+        // 延遲初始化且從未啟用的插件沒有調用過 `on_load`，也不調用 `on_unload`
+        let ret = if entry.pending_on_load {
+            Ok(())
+        } else {
+            entry.plugin.on_unload()
+        };
+
+        // 動態庫仍有其他插件使用時，清理函數與檔案刪除留給最後一個插件
+        if Arc::strong_count(&entry.library) > 1 {
+            drop(entry);
+            return ret.map(|_| UnloadOutcome::default());
+        }
+        // 執行標準卸載程序 (缺少 `unload_plugin` 時已在載入時警告)
+        let ran_native_cleanup = match entry.native_unload {
+            Some(unload_plugin) => {
+                unload_plugin();
+                true
+            }
+            None => false,
+        };
+
+        // 釋放動態庫後再刪除暫存檔案與解壓縮的資源
+        let temp_file = entry.temp_file.take();
+        let extracted_dir = entry.extracted_dir.take();
+        drop(entry);
+        if let Some(temp_file) = temp_file {
+            if let Err(e) = std::fs::remove_file(&temp_file) {
+                eprintln!("Failed to remove temp plugin file {:?}: {}", temp_file, e);
+            }
+        }
+        if let Some(extracted_dir) = extracted_dir {
+            if let Err(e) = std::fs::remove_dir_all(&extracted_dir) {
+                eprintln!(
+                    "Failed to remove extracted plugin files {:?}: {}",
+                    extracted_dir, e
+                );
+            }
+        }
+        ret.map(|_| UnloadOutcome { ran_native_cleanup })
+    }
+    /// 卸載所有符合條件的插件
+    /// - `pred`: 判斷條件，參數為插件名稱與當前狀態
+    /// - 返回值: 成功或失敗的結果，失敗時包含所有卸載錯誤
+    pub fn unload_where<F: Fn(&str, &PluginState) -> bool>(&mut self, pred: F) -> Result<()> {
+        // 先依卸載順序收集符合條件的名稱，避免在修改插件集合時產生借用衝突
+        let names: Vec<String> = self
+            .unload_order()
+            .into_iter()
+            .filter(|name| pred(name, &self.plugins[name].state))
+            .collect();
+
+        let results = names
+            .into_iter()
+            .map(|name| {
+                let ret = self.unload_plugin(&name).map(|_| ());
+                (name, ret)
+            })
+            .collect();
+        Self::aggregate_results("unload", results, PluginError::LoadError)
+    }
+
+    /// 發送事件
+    /// - 事件會依插件優先級 (由高到低，同優先級依註冊順序，再依名稱) 送達所有已啟用的訂閱者
+    ///   ([`DispatchMode::RoundRobin`] 的事件只送達輪流選出的一個訂閱者)
+    /// - 事件先放入待處理佇列再派送；處理器返回的回應事件與插件透過
+    ///   [`HostHandle::broadcast`] 發送的事件同樣排入佇列，在目前事件派送完成後才處理，
+    ///   因此不會遞歸派送，所有待處理事件都會在返回前送達
+    /// - 佇列中的事件依 `Event.priority` 由高到低派送，相同優先級依加入順序派送;
+    ///   `Event.priority` 不影響單一事件的訂閱者順序
+    /// - 派送進行中再次發送 (重入) 的事件只會排入佇列，由外層的派送迴圈處理
+    /// - 單一插件處理失敗只會記錄錯誤，不會中斷其他插件的處理
+    /// - 事件總線中指向不存在插件的訂閱者會發出警告，嚴格模式下返回錯誤，
+    ///   此時尚未派送的事件會保留在佇列中
+    /// - `event`: 要發送的事件
+    /// - 返回值: 成功或失敗的結果
+    pub fn broadcast_event(&mut self, event: Event) -> Result<()> {
+        self.host.broadcast(event);
+        self.process_host_events()
+    }
+    /// 派送所有待處理事件
+    /// - 插件在事件處理以外 (例如 `on_enable` 或其他執行緒) 透過 [`HostHandle::broadcast`]
+    ///   發送的事件會等到下次 `broadcast_event` 或調用此函數時才派送
+    /// - 已有派送進行中時直接返回，事件由進行中的派送迴圈處理
+    /// - 返回值: 成功或失敗的結果
+    pub fn process_host_events(&mut self) -> Result<()> {
+        if self.dispatching {
+            return Ok(());
+        }
+        self.dispatching = true;
+        let mut ret = Ok(());
+        while let Some(event) = self.host.pop_pending() {
+            if let Err(e) = self.dispatch_event(event) {
+                ret = Err(e);
+                break;
+            }
+        }
+        self.dispatching = false;
+        ret
+    }
+    /// 依派送模式將單一事件派送給已啟用的訂閱者
+    /// - 先經過事件中介函數，被捨棄的事件不會派送
+    /// - 回應事件會排入待處理佇列
+    /// - `event`: 要派送的事件
+    /// - 返回值: 成功或失敗的結果
+    fn dispatch_event(&mut self, mut event: Event) -> Result<()> {
+        if !self.apply_middleware(&mut event) {
+            return Ok(());
+        }
+        let event = &event;
+        self.event_metrics.record_event(&event.name);
+        let mut subscribers = self.dispatch_order(&event.name);
+        if self.dispatch_mode_for(&event.name) == DispatchMode::RoundRobin {
+            subscribers = self.pick_round_robin(&event.name, &subscribers);
+        }
+        // 只有啟用事件歷史時才記錄派送結果
+        let outcome = self.deliver_all(event, &subscribers, self.event_history_capacity > 0)?;
+        if let Some(outcome) = outcome {
+            self.record_history(event, outcome);
+        }
+        Ok(())
+    }
+    /// 將單一事件依序交給多個訂閱者處理，回應事件會排入待處理佇列
+    /// - `event`: 要派送的事件
+    /// - `subscribers`: 依派送順序排列的訂閱者名稱
+    /// - `collect`: 是否收集派送結果
+    /// - 返回值: `collect` 為 `true` 時返回派送結果
+    fn deliver_all(
+        &mut self,
+        event: &Event,
+        subscribers: &[Arc<str>],
+        collect: bool,
+    ) -> Result<Option<BroadcastOutcome>> {
+        let mut outcome = collect.then(BroadcastOutcome::default);
+        // 依序發送事件
+        for name in subscribers {
+            let delivery = self.deliver(name, event)?;
+            if let Delivery::Handled(Some(response_event)) = &delivery {
+                self.host.broadcast(response_event.clone());
+            }
+            if let Some(outcome) = &mut outcome {
+                let name = name.to_string();
+                match delivery {
+                    Delivery::Skipped => outcome.skipped.push(name),
+                    Delivery::Handled(_) => outcome.delivered.push(name),
+                    Delivery::Failed(e) => outcome.failed.push((name, e)),
+                }
+            }
+        }
+        Ok(outcome)
+    }
+    /// 批次發送事件
+    /// - 每個不同的事件名稱只查詢一次訂閱者與派送模式，適合大量發送事件的來源
+    /// - 事件依輸入順序逐一派送，不經過待處理佇列的優先級排序；送達方式與
+    ///   `broadcast_event` 相同，回應事件排入佇列並在整批派送完成後處理
+    /// - 批次期間由於啟用/禁用而改變的插件狀態仍會生效，但訂閱者列表以第一次查詢的結果為準
+    /// - 被事件中介函數捨棄的事件，派送結果為空
+    /// - `events`: 要發送的事件
+    /// - 返回值: 每個輸入事件的派送結果，順序與輸入相同；事件總線指向不存在的插件且為嚴格模式時返回錯誤
+    pub fn broadcast_batch(&mut self, events: Vec<Event>) -> Result<Vec<BroadcastOutcome>> {
+        let mut routes: HashMap<String, (DispatchOrder, DispatchMode)> = HashMap::new();
+        let mut outcomes = Vec::with_capacity(events.len());
+        for mut event in events {
+            if !self.apply_middleware(&mut event) {
+                outcomes.push(BroadcastOutcome::default());
+                continue;
+            }
+            let event = &event;
+            self.event_metrics.record_event(&event.name);
+            let (mut subscribers, mode) = match routes.get(&event.name) {
+                Some(route) => route.clone(),
+                None => {
+                    let route = (
+                        self.dispatch_order(&event.name),
+                        self.dispatch_mode_for(&event.name),
+                    );
+                    routes.insert(event.name.clone(), route.clone());
+                    route
+                }
+            };
+            if mode == DispatchMode::RoundRobin {
+                subscribers = self.pick_round_robin(&event.name, &subscribers);
+            }
+            let outcome = self
+                .deliver_all(event, &subscribers, true)?
+                .unwrap_or_default();
+            if self.event_history_capacity > 0 {
+                self.record_history(event, outcome.clone());
+            }
+            outcomes.push(outcome);
+        }
+        self.process_host_events()?;
+        Ok(outcomes)
+    }
+    /// 記錄事件歷史，超出容量時移除最舊的項目
+    /// - `event`: 派送的事件
+    /// - `outcome`: 派送結果
+    fn record_history(&mut self, event: &Event, outcome: BroadcastOutcome) {
+        while self.event_history.len() >= self.event_history_capacity {
+            self.event_history.pop_front();
+        }
+        self.event_history.push_back((event.clone(), outcome));
+    }
+    /// 設定事件歷史的容量
+    /// - 容量大於 0 時，每個派送的事件 (含回應事件與宿主句柄發送的事件) 與其派送結果會被記錄，
+    ///   超出容量時捨棄最舊的項目；`request` 與 `broadcast_to` 不會記錄
+    /// - 縮小容量時會立即捨棄多出的舊項目
+    /// - `capacity`: 最多保留的事件數量，預設為 0 (不記錄)
+    pub fn set_event_history_capacity(&mut self, capacity: usize) {
+        self.event_history_capacity = capacity;
+        while self.event_history.len() > capacity {
+            self.event_history.pop_front();
+        }
+    }
+    /// 獲取事件歷史
+    /// - 返回值: 最近派送的事件及其派送結果，由舊到新排列
+    pub fn event_history(&self) -> Vec<(Event, BroadcastOutcome)> {
+        self.event_history.iter().cloned().collect()
+    }
+    /// 設定預設的事件派送模式
+    /// - `mode`: 沒有個別設定的事件所使用的派送模式，預設為 [`DispatchMode::Broadcast`]
+    pub fn set_dispatch_mode(&mut self, mode: DispatchMode) {
+        self.dispatch_mode = mode;
+    }
+    /// 設定單一事件的派送模式，優先於預設的派送模式
+    /// - `event`: 事件名稱
+    /// - `mode`: 派送模式，`None` 表示改回使用預設的派送模式
+    pub fn set_event_dispatch_mode(&mut self, event: &str, mode: Option<DispatchMode>) {
+        match mode {
+            Some(mode) => {
+                self.event_dispatch_modes.insert(event.to_string(), mode);
+            }
+            None => {
+                self.event_dispatch_modes.remove(event);
+                self.round_robin.remove(event);
+            }
+        }
+    }
+    /// 獲取事件的派送模式
+    /// - `event`: 事件名稱
+    fn dispatch_mode_for(&self, event: &str) -> DispatchMode {
+        self.event_dispatch_modes
+            .get(event)
+            .copied()
+            .unwrap_or(self.dispatch_mode)
+    }
+    /// 以平滑加權輪詢從訂閱者中選出一個處理者
+    /// - 權重為插件的優先級 (小於 1 時視為 1)，長期來看每個插件收到的事件數與權重成正比，
+    ///   且同一插件不會在權重允許的範圍外連續被選中
+    /// - 只考慮事件會送達的插件 (見 [`PluginManager::is_deliverable`])；輪詢狀態依事件名稱分開記錄，
+    ///   已不是候選者的插件會被移除
+    /// - `event`: 事件名稱
+    /// - `subscribers`: 依派送順序排列的訂閱者
+    /// - 返回值: 被選中的訂閱者，沒有已啟用的訂閱者時為空
+    fn pick_round_robin(&mut self, event: &str, subscribers: &[Arc<str>]) -> DispatchOrder {
+        let candidates = self.round_robin_candidates(subscribers);
+        if candidates.is_empty() {
+            return Arc::new([]);
+        }
+        let weights = self.round_robin.entry(event.to_string()).or_default();
+        Self::round_robin_step(weights, &candidates)
+            .into_iter()
+            .collect()
+    }
+    /// 篩選輪詢派送的候選者
+    /// - `subscribers`: 依派送順序排列的訂閱者
+    /// - 返回值: 會被派送的訂閱者及其權重
+    fn round_robin_candidates(&self, subscribers: &[Arc<str>]) -> Vec<(Arc<str>, i64)> {
+        subscribers
+            .iter()
+            .filter_map(|name| {
+                let entry = self.plugins.get(&**name)?;
+                self.is_deliverable(entry)
+                    .then(|| (Arc::clone(name), i64::from(entry.priority.max(1))))
+            })
+            .collect()
+    }
+    /// 執行一次平滑加權輪詢
+    /// - `weights`: 每個插件目前的權重，會被更新
+    /// - `candidates`: 候選者及其權重
+    /// - 返回值: 被選中的候選者，沒有候選者時返回 `None`
+    fn round_robin_step(
+        weights: &mut HashMap<String, i64>,
+        candidates: &[(Arc<str>, i64)],
+    ) -> Option<Arc<str>> {
+        weights.retain(|name, _| {
+            candidates
+                .iter()
+                .any(|(candidate, _)| **candidate == **name)
+        });
+        let total: i64 = candidates.iter().map(|(_, weight)| weight).sum();
+        let mut selected: Option<(&Arc<str>, i64)> = None;
+        for (name, weight) in candidates {
+            let current = match weights.get_mut(&**name) {
+                Some(current) => current,
+                None => weights.entry(name.to_string()).or_insert(0),
+            };
+            *current += weight;
+            // 同分時保留派送順序中較前面的插件
+            if selected.is_none_or(|(_, best)| *current > best) {
+                selected = Some((name, *current));
+            }
+        }
+        let (selected, _) = selected?;
+        if let Some(current) = weights.get_mut(&**selected) {
+            *current -= total;
+        }
+        Some(selected.clone())
+    }
+    /// 檢查事件是否會送達插件
+    /// - 已啟用的插件，以及延遲初始化且會在第一個事件時自動啟用的插件
+    /// - `entry`: 插件條目
+    fn is_deliverable(&self, entry: &PluginEntry) -> bool {
+        entry.state == PluginState::Enabled
+            || (entry.pending_on_load && entry.state == PluginState::Loaded && self.auto_enable)
+    }
+    /// 預覽事件會送達的插件，不調用任何 `handle_event`
+    /// - 與 `broadcast_event` 使用相同的派送順序、派送模式與狀態篩選，
+    ///   輪詢派送時返回下一次會被選中的插件，但不會推進輪詢狀態
+    /// - 不調用事件中介函數，因此不反映中介函數的修改或捨棄
+    /// - 只預覽此事件本身，不包含處理後產生的回應事件；
+    ///   指向不存在插件的訂閱不會列出 (實際發送時會發出警告或在嚴格模式下返回錯誤)
+    /// - `event`: 要預覽的事件
+    /// - 返回值: 依送達順序排列的插件名稱
+    pub fn broadcast_event_dry_run(&self, event: &Event) -> Vec<String> {
+        let subscribers = self.dispatch_order(&event.name);
+        if self.dispatch_mode_for(&event.name) == DispatchMode::RoundRobin {
+            let candidates = self.round_robin_candidates(&subscribers);
+            let mut weights = self
+                .round_robin
+                .get(&event.name)
+                .cloned()
+                .unwrap_or_default();
+            return Self::round_robin_step(&mut weights, &candidates)
+                .into_iter()
+                .map(|name| name.to_string())
+                .collect();
+        }
+        subscribers
+            .iter()
+            .filter(|name| {
+                self.plugins
+                    .get(&***name)
+                    .is_some_and(|entry| self.is_deliverable(entry))
+            })
+            .map(|name| name.to_string())
+            .collect()
+    }
+    /// 發送請求事件並取得單一回應
+    /// - 事件依與 `broadcast_event` 相同的優先級順序逐一交給已啟用的訂閱者，
+    ///   第一個返回回應的插件即為處理者，之後的訂閱者不會收到此事件
+    /// - 多個插件都可能回應時，由派送順序最前者 (見 `broadcast_event`) 回應；沒有插件回應時返回 `Ok(None)`
+    /// - 回應事件直接返回給呼叫者，不會再發送給其他插件
+    /// - 處理失敗的插件與 `broadcast_event` 相同會記錄錯誤並計入連續失敗次數，之後繼續詢問下一個訂閱者
+    /// - 被事件中介函數捨棄的請求不會送達任何插件，返回 `Ok(None)`
+    /// - `event`: 請求事件
+    /// - 返回值: 第一個回應事件，或嚴格模式下的錯誤
+    pub fn request(&mut self, mut event: Event) -> Result<Option<Event>> {
+        if !self.apply_middleware(&mut event) {
+            return Ok(None);
+        }
+        self.event_metrics.record_event(&event.name);
+        let mut reply = None;
+        for name in self.dispatch_order(&event.name).iter() {
+            if let Delivery::Handled(Some(response_event)) = self.deliver(name, &event)? {
+                reply = Some(response_event);
+                break;
+            }
+        }
+        // 插件在處理請求時透過宿主句柄發送的事件
+        self.process_host_events()?;
+        Ok(reply)
+    }
+    /// 將事件交給單一訂閱者處理
+    /// - 跳過不存在或未啟用的插件；處理失敗時記錄錯誤，不中斷派送
+    /// - `name`: 訂閱者名稱
+    /// - `event`: 要處理的事件
+    /// - 返回值: 處理結果，訂閱者不存在且為嚴格模式時返回錯誤
+    fn deliver(&mut self, name: &str, event: &Event) -> Result<Delivery> {
+        let Some(entry) = self.plugins.get(name) else {
+            let error_msg = format!(
+                "EventBus references unknown plugin {} for event {}",
+                name, event.name
+            );
+            if self.strict {
+                return Err(PluginError::EventError(error_msg));
+            }
+            eprintln!("Warning: {}", error_msg);
+            return Ok(Delivery::Skipped);
+        };
+        // 延遲初始化的插件在第一個事件送達時才初始化並啟用
+        if entry.state != PluginState::Enabled && self.is_deliverable(entry) {
+            if let Err(e) = self.enable_entry(name) {
+                return Ok(Delivery::Failed(e.to_string()));
+            }
+        }
+        let Some(entry) = self.plugins.get(name) else {
+            return Ok(Delivery::Skipped);
+        };
+        if entry.state != PluginState::Enabled || !self.circuit_allows(name, &event.name) {
+            return Ok(Delivery::Skipped);
+        }
+        // 處理失敗已由 `call_handler` 記錄
+        Ok(match self.call_handler(name, event) {
+            Ok(response) => Delivery::Handled(response),
+            Err(e) => Delivery::Failed(e.to_string()),
+        })
+    }
+    /// 調用已啟用插件的 `handle_event`
+    /// - 更新統計計數與處理耗時，失敗時記錄錯誤並計入連續失敗次數
+    /// - `name`: 插件名稱，需為已載入的插件
+    /// - `event`: 要處理的事件
+    /// - 返回值: 插件的回應事件或處理錯誤
+    fn call_handler(&mut self, name: &str, event: &Event) -> Result<Option<Event>> {
+        let Some(entry) = self.plugins.get(name) else {
+            return Ok(None);
+        };
+        // 處理事件並檢查是否有回應事件
+        self.event_metrics.handler_calls += 1;
+        let started = Instant::now();
+        let ret = {
+            let _guard = entry.borrow_guard();
+            entry.plugin.handle_event(event)
+        };
+        self.record_handler_time(name, started.elapsed());
+        self.record_circuit(name, &event.name, ret.is_ok());
+        match ret {
+            Ok(response) => {
+                self.event_failures.remove(name);
+                Ok(response)
+            }
+            Err(e) => {
+                self.event_metrics.handler_errors += 1;
+                self.handle_event_error(name, event, &e);
+                Err(in_plugin(name, e))
+            }
+        }
+    }
+    /// 將事件只發送給指定的插件，不經過一般的訂閱路由
+    /// - 目標依與 `broadcast_event` 相同的優先級順序處理，重複的名稱只發送一次
+    /// - 未載入或未啟用的目標會被略過；`require_subscription` 為 `true` 時，
+    ///   未訂閱此事件 (含萬用字元訂閱) 的目標也會被略過
+    /// - 回應事件與 `broadcast_event` 相同會排入佇列，依一般訂閱路由發送給所有訂閱者
+    /// - 事件會先經過事件中介函數 (修改的是副本)，被捨棄時不發送給任何目標，返回空的結果
+    /// - `event`: 要發送的事件
+    /// - `targets`: 目標插件名稱
+    /// - `require_subscription`: 是否只發送給有訂閱此事件的目標
+    /// - 返回值: 每個目標的處理結果，回應事件派送失敗時返回錯誤
+    pub fn broadcast_to(
+        &mut self,
+        event: &Event,
+        targets: &[&str],
+        require_subscription: bool,
+    ) -> Result<BroadcastOutcome> {
+        let mut event = event.clone();
+        if !self.apply_middleware(&mut event) {
+            return Ok(BroadcastOutcome::default());
+        }
+        let event = &event;
+        self.event_metrics.record_event(&event.name);
+        let subscribers = self.event_bus.get_subscribers_interned(&event.name);
+        let mut targets: Vec<String> = targets.iter().map(|name| name.to_string()).collect();
+        targets.sort();
+        targets.dedup();
+        targets.sort_by(|a, b| self.delivery_cmp(a, b));
+
+        let mut outcome = BroadcastOutcome::default();
+        for name in targets {
+            let enabled = self
+                .plugins
+                .get(&name)
+                .is_some_and(|entry| entry.state == PluginState::Enabled);
+            if !enabled
+                || (require_subscription && !subscribers.iter().any(|s| **s == *name))
+                || !self.circuit_allows(&name, &event.name)
+            {
+                outcome.skipped.push(name);
+                continue;
+            }
+            match self.call_handler(&name, event) {
+                Ok(response) => {
+                    if let Some(response_event) = response {
+                        self.host.broadcast(response_event);
+                    }
+                    outcome.delivered.push(name);
+                }
+                Err(e) => outcome.failed.push((name, e.to_string())),
+            }
+        }
+        self.process_host_events()?;
+        Ok(outcome)
+    }
+    /// 累計插件處理事件的耗時，並在超出預算時禁用插件
+    /// - `name`: 插件名稱
+    /// - `elapsed`: 本次 `handle_event` 的耗時
+    fn record_handler_time(&mut self, name: &str, elapsed: Duration) {
+        let Some(entry) = self.plugins.get_mut(name) else {
+            return;
+        };
+        entry.handler_time += elapsed;
+        let total = entry.handler_time;
+        let over_budget = self
+            .handler_time_policy
+            .as_ref()
+            .is_some_and(|policy| policy(name, total));
+        if over_budget {
+            eprintln!(
+                "Plugin {} exceeded its handler time budget ({:?}), disabling",
+                name, total
+            );
+            if let Err(e) = self.disable_entry(name) {
+                eprintln!("Error disabling plugin {}: {}", name, e);
+            }
+        }
+    }
+    /// 獲取插件處理事件的累計耗時
+    /// - 以牆上時間計算 `handle_event` 的執行時間，包含插件等待 I/O 或鎖的時間，並非精確的 CPU 時間
+    /// - 重新載入或替換插件後從零開始計算
+    /// - `name`: 插件名稱
+    /// - 返回值: 累計耗時，插件不存在時返回 `None`
+    pub fn plugin_cpu_time(&self, name: &str) -> Option<Duration> {
+        self.plugins.get(name).map(|entry| entry.handler_time)
+    }
+    /// 獲取插件最近一次透過 [`HostHandle::heartbeat`] 回報的時間
+    /// - 重新載入或替換插件後，新實例需重新回報
+    /// - `name`: 插件名稱
+    /// - 返回值: 回報時間，插件不存在或從未回報時返回 `None`
+    pub fn plugin_last_heartbeat(&self, name: &str) -> Option<Instant> {
+        self.plugins
+            .get(name)
+            .and_then(|entry| self.host.last_heartbeat(entry.host_client))
+    }
+    /// 獲取心跳已逾時的插件
+    /// - 只檢查曾經回報過心跳的插件，從未回報的插件視為不使用心跳機制
+    /// - `max_age`: 距離最近一次回報允許的最長時間
+    /// - 返回值: 逾時插件的名稱，依名稱排序
+    pub fn stale_heartbeats(&self, max_age: Duration) -> Vec<String> {
+        let now = Instant::now();
+        self.sorted_names()
+            .into_iter()
+            .filter(|name| {
+                self.plugin_last_heartbeat(name)
+                    .is_some_and(|last| now.duration_since(last) > max_age)
+            })
+            .collect()
+    }
+    /// 設定每個插件保留的日誌數量上限
+    /// - 插件以 [`HostHandle::log`] 記錄的日誌一律透過 `log` crate 輸出，
+    ///   上限大於 0 時也會保留最近的日誌，供 [`PluginManager::plugin_logs`] 讀取
+    /// - 預設為 0 (不保留)；縮小上限時會捨棄較舊的日誌
+    /// - `capacity`: 每個插件最多保留的日誌數量
+    pub fn set_plugin_log_capacity(&mut self, capacity: usize) {
+        self.host.set_log_capacity(capacity);
+    }
+    /// 獲取插件保留的日誌
+    /// - 日誌依插件的註冊名稱保留，重新載入或替換後仍會保留，插件卸載時清除
+    /// - `name`: 插件名稱
+    /// - 返回值: 依記錄順序排列的日誌，插件不存在或沒有保留的日誌時為空
+    pub fn plugin_logs(&self, name: &str) -> Vec<LogRecord> {
+        self.host.logs(name)
+    }
+    /// 建立插件之間的具名通道
+    /// - 插件以 [`HostHandle::sender`] 與 [`HostHandle::receiver`] 取得端點，
+    ///   通道的擁有與關閉規則見 [`HostHandle`] 的說明
+    /// - `name`: 通道名稱
+    /// - 返回值: 成功或失敗的結果，同名通道已存在時返回錯誤
+    pub fn create_channel(&mut self, name: &str) -> Result<()> {
+        if !self.host.create_channel(name) {
+            return Err(PluginError::EventError(format!(
+                "Channel {} already exists",
+                name
+            )));
+        }
+        Ok(())
+    }
+    /// 關閉具名通道
+    /// - 只從登記中移除，插件已取得的端點不受影響
+    /// - `name`: 通道名稱
+    /// - 返回值: 通道是否存在
+    pub fn close_channel(&mut self, name: &str) -> bool {
+        self.host.close_channel(name)
+    }
+    /// 獲取所有具名通道的名稱
+    /// - 返回值: 通道名稱，依名稱排序
+    pub fn channel_names(&self) -> Vec<String> {
+        let mut names = self.host.channel_names();
+        names.sort();
+        names
+    }
+    /// 獲取事件處理的統計計數
+    /// - 返回值: 自建立管理器或上次重設以來的計數
+    pub fn event_metrics(&self) -> EventMetrics {
+        self.event_metrics.clone()
+    }
+    /// 將事件處理的統計計數歸零
+    pub fn reset_event_metrics(&mut self) {
+        self.event_metrics = EventMetrics::default();
+    }
+    /// 排程延遲事件
+    /// - 事件會在經過 `after` 之後，由下一次 [`PluginManager::pump_scheduled`] 發送一次
+    /// - `event`: 要發送的事件
+    /// - `after`: 延遲時間
+    pub fn schedule_event(&mut self, event: Event, after: Duration) {
+        self.push_scheduled(None, event, after);
+    }
+    /// 代替插件排程延遲事件
+    /// - 與 [`PluginManager::schedule_event`] 相同，但事件屬於 `owner`，
+    ///   插件在事件觸發前卸載時事件會被取消
+    /// - `owner`: 排程此事件的插件名稱
+    /// - `event`: 要發送的事件
+    /// - `after`: 延遲時間
+    pub fn schedule_event_for(&mut self, owner: &str, event: Event, after: Duration) {
+        self.push_scheduled(Some(owner.to_string()), event, after);
+    }
+    /// 加入延遲事件
+    fn push_scheduled(&mut self, owner: Option<String>, event: Event, after: Duration) {
+        let seq = self.next_schedule_seq;
+        self.next_schedule_seq += 1;
+        self.scheduled.push(ScheduledEvent {
+            fire_at: Instant::now() + after,
+            seq,
+            owner,
+            event,
+        });
+    }
+    /// 取消插件排程的所有延遲事件
+    /// - `owner`: 插件名稱
+    fn cancel_scheduled_for(&mut self, owner: &str) {
+        self.scheduled
+            .retain(|scheduled| scheduled.owner.as_deref() != Some(owner));
+    }
+    /// 發送所有已到觸發時間的延遲事件
+    /// - 到期的事件會一併排入待處理佇列，依 `Event.priority` 由高到低發送，
+    ///   相同優先級依觸發時間 (相同時依排程順序) 發送，每個事件只發送一次
+    /// - 管理器不會自行計時，宿主需定期調用此函數，例如在主迴圈中傳入 `Instant::now()`
+    /// - 單一事件發送失敗不會中斷其他事件的發送
+    /// - `now`: 目前時間
+    /// - 返回值: 成功或失敗的結果，失敗時返回第一個發送錯誤
+    pub fn pump_scheduled(&mut self, now: Instant) -> Result<()> {
+        let (mut due, pending): (Vec<ScheduledEvent>, Vec<ScheduledEvent>) =
+            std::mem::take(&mut self.scheduled)
+                .into_iter()
+                .partition(|scheduled| scheduled.fire_at <= now);
+        self.scheduled = pending;
+        due.sort_by(|a, b| a.fire_at.cmp(&b.fire_at).then_with(|| a.seq.cmp(&b.seq)));
+
+        for scheduled in due {
+            self.host.broadcast(scheduled.event);
+        }
+        // 派送失敗時佇列中仍可能留有事件，繼續派送直到佇列清空
+        let mut ret = Ok(());
+        while let Err(e) = self.process_host_events() {
+            if ret.is_ok() {
+                ret = Err(e);
+            }
         }
+        ret
     }
-    #[allow(clippy::unwrap_or_default)]
-    /// 訂閱事件
-    /// - `event`: 要訂閱的事件名稱
-    /// - `plugin`: 訂閱此事件的插件名稱
-    fn subscribe(&mut self, event: &str, plugin: &str) {
-        self.subscribers
-            .entry(event.to_string())
-            .or_insert_with(HashSet::new)
-            .insert(plugin.to_string());
-    }
-    /// 取消訂閱事件
-    /// - `event`: 要取消的事件名稱
-    /// - `plugin`: 要取消訂閱的插件名稱
-    fn unsubscribe(&mut self, event: &str, plugin: &str) {
-        if let Some(subscribers) = self.subscribers.get_mut(event) {
-            subscribers.remove(plugin);
+    /// 計算事件的派送順序
+    /// - 插件透過匯出可選的 `plugin_priority` 符號 (`fn(&dyn Plugin) -> i32`) 宣告優先級，預設為 0
+    /// - 順序見 [`PluginManager::delivery_cmp`]
+    /// - 排序的結果會依事件名稱快取，訂閱與插件都沒有變更時重複派送同一事件不會配置記憶體
+    /// - `event`: 事件名稱
+    /// - 返回值: 依派送順序排列的訂閱者名稱
+    fn dispatch_order(&self, event: &str) -> DispatchOrder {
+        let subscribers = self.event_bus.get_subscribers_interned(event);
+        let mut cache = self
+            .dispatch_cache
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        if let Some(cached) = cache.get(event) {
+            if Arc::ptr_eq(&cached.subscribers, &subscribers) {
+                return Arc::clone(&cached.order);
+            }
+        }
+        let mut order = subscribers.to_vec();
+        order.sort_by(|a, b| self.delivery_cmp(a, b));
+        let order: DispatchOrder = order.into();
+        if cache.len() >= SUBSCRIBER_CACHE_CAPACITY {
+            cache.clear();
         }
+        cache.insert(
+            event.to_string(),
+            CachedDispatchOrder {
+                subscribers,
+                order: Arc::clone(&order),
+            },
+        );
+        order
     }
-    /// 獲取某事件的所有訂閱者
-    /// - `event`: 事件名稱
-    /// - 返回值: 訂閱此事件的插件名稱列表
-    fn get_subscribers(&self, event: &str) -> Vec<String> {
-        self.subscribers
-            .get(event)
-            .map(|s| s.iter().cloned().collect())
-            .unwrap_or_default()
+    /// 清空派送順序的快取
+    /// - 插件的優先級與註冊順序只在載入、合併、替換與卸載時改變，這些操作都會調用此函數
+    fn invalidate_dispatch_order(&mut self) {
+        self.dispatch_cache
+            .get_mut()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
     }
-}
-
-/// 插件管理器，用於管理插件的加載、啟用、禁用和事件通知
-#[derive(Debug)]
-pub struct PluginManager {
-    /// 插件的集合，鍵為插件名稱
-    plugins: HashMap<String, PluginEntry>,
-    /// 插件目錄的路徑
-    plugin_dir: PathBuf,
-    /// 事件總線
-    event_bus: EventBus,
-}
-#[allow(unused)]
-impl PluginManager {
-    /// 創建新的插件管理器
-    /// - `plugin_dir`: 插件目錄路徑
-    pub fn new<P: AsRef<Path>>(plugin_dir: P) -> Self {
-        Self {
-            plugins: HashMap::new(),
-            plugin_dir: plugin_dir.as_ref().to_path_buf(),
-            event_bus: EventBus::new(),
+    /// 比較兩個插件的派送順序，為全序關係
+    /// 1. 優先級由高到低
+    /// 2. 同優先級時依註冊順序，先載入的插件先處理；重新載入或替換的插件維持原本的位置，
+    ///    從其他管理器合併的插件視為在合併時註冊
+    /// 3. 仍相同時 (例如指向不存在插件的訂閱) 依註冊名稱排序
+    /// - `a`, `b`: 插件的註冊名稱
+    fn delivery_cmp(&self, a: &str, b: &str) -> std::cmp::Ordering {
+        let key = |name: &str| {
+            self.plugins
+                .get(name)
+                .map_or((0, u64::MAX), |entry| (entry.priority, entry.load_seq))
+        };
+        let ((priority_a, seq_a), (priority_b, seq_b)) = (key(a), key(b));
+        priority_b
+            .cmp(&priority_a)
+            .then(seq_a.cmp(&seq_b))
+            .then_with(|| a.cmp(b))
+    }
+    /// 分配下一個註冊流水號
+    fn take_load_seq(&mut self) -> u64 {
+        let seq = self.next_load_seq;
+        self.next_load_seq += 1;
+        seq
+    }
+    /// 調用一批插件的 `on_all_loaded` 鉤子
+    /// - 插件透過匯出可選的 `plugin_on_all_loaded` 符號 (`fn(&mut dyn Plugin) -> Result<()>`)
+    ///   在整批插件都載入並啟用後執行跨插件的設定 (例如取得其他插件的參照)
+    /// - 依註冊順序調用批次中每個狀態為 `Loaded` 或 `Enabled` 的插件；
+    ///   延遲初始化且尚未調用 `on_load` 的插件不調用
+    /// - 鉤子失敗的插件會先禁用 (已啟用時) 再進入 `Error` 狀態，不影響其他插件
+    /// - `first_seq`: 批次開始前的註冊流水號，之後註冊的插件屬於此批次
+    /// - 返回值: 鉤子失敗的插件名稱及錯誤
+    fn run_all_loaded(&mut self, first_seq: u64) -> Vec<(String, PluginError)> {
+        let mut batch: Vec<(u64, String)> = self
+            .plugins
+            .iter()
+            .filter(|(_, entry)| entry.load_seq >= first_seq)
+            .map(|(name, entry)| (entry.load_seq, name.clone()))
+            .collect();
+        batch.sort();
+        let mut errors = Vec::new();
+        for (_, name) in batch {
+            let Some(entry) = self.plugins.get_mut(&name) else {
+                continue;
+            };
+            let Some(on_all_loaded) = entry.on_all_loaded else {
+                continue;
+            };
+            if entry.pending_on_load
+                || !matches!(entry.state, PluginState::Loaded | PluginState::Enabled)
+            {
+                continue;
+            }
+            let Err(e) = on_all_loaded(entry.plugin.as_mut()) else {
+                continue;
+            };
+            eprintln!("Error in on_all_loaded of plugin {}: {}", name, e);
+            if entry.state == PluginState::Enabled {
+                if let Err(disable_error) = entry.plugin.on_disable() {
+                    eprintln!("Error disabling plugin {}: {}", name, disable_error);
+                }
+            }
+            entry.state = PluginState::Error(e.to_string());
+            self.state_notifier.notify(&name, &entry.state);
+            self.record_error_transition();
+            let e = in_plugin(&name, e);
+            errors.push((name, e));
         }
+        errors
     }
-    /// 加載單個插件
-    /// - `path`: 插件檔案的路徑
-    /// - 返回值: 成功或失敗的結果
-    pub fn load_plugin(&mut self, path: &Path) -> Result<()> {
-        unsafe {
-            let lib = Library::new(path)
-                .map_err(|e| PluginError::LoadError(format!("Failed to load library: {}", e)))?;
-
-            // 獲取創建插件函數
-            let create_plugin: libloading::Symbol<fn() -> Box<dyn Plugin>> =
-                lib.get(b"create_plugin").map_err(|e| {
-                    PluginError::LoadError(format!("Failed to get create_plugin symbol: {}", e))
-                })?;
-
-            // 創建插件實例
-            let plugin = create_plugin();
-            let name = plugin.name().to_string();
-            // 調用加載鉤子
-
-            plugin.on_load()?;
-            // 註冊事件訂閱
-            for event in plugin.subscribed_events() {
-                self.event_bus.subscribe(&event, &name);
-            }
-            println!("Loaded plugin: {} v{}", name, plugin.version());
-            self.plugins.insert(
-                name.clone(),
-                PluginEntry {
-                    plugin,
-                    library: lib,
-                    state: PluginState::Loaded,
-                },
-            );
-            self.enable_plugin(name.as_str())?;
-            Ok(())
+    /// 調用一批插件的 `on_all_loaded` 鉤子，並把失敗記錄到載入報告
+    /// - `first_seq`: 批次開始前的註冊流水號
+    /// - `report`: 載入報告，失敗的插件以其檔案路徑記錄在 `failed`
+    fn report_all_loaded(&mut self, first_seq: u64, report: &mut LoadReport) {
+        for (name, e) in self.run_all_loaded(first_seq) {
+            let path = self.plugins[&name].path.clone();
+            report.failed.push((path, e.to_string()));
         }
     }
-    /// 啟用插件
+    /// 處理插件的事件處理錯誤
+    /// - 調用錯誤處理函數，並在連續失敗達到門檻時禁用插件
     /// - `name`: 插件名稱
-    /// - 返回值: 成功或失敗的結果
-    pub fn enable_plugin(&mut self, name: &str) -> Result<()> {
-        let ret = self.plugins.get_mut(name);
-        if let Some(entry) = ret {
-            if entry.state == PluginState::Enabled {
-                return Ok(());
-            }
-            if entry.state == PluginState::Loaded {
-                entry.plugin.on_enable()?;
-                entry.state = PluginState::Enabled;
-                println!("Enabled plugin: {}", name);
-                return Ok(());
+    /// - `event`: 處理失敗的事件
+    /// - `error`: 處理錯誤
+    fn handle_event_error(&mut self, name: &str, event: &Event, error: &PluginError) {
+        match &self.event_error_handler {
+            Some(handler) => handler(name, event, error),
+            None => eprintln!("Error handling event in plugin {}: {}", name, error),
+        }
+
+        let failures = self.event_failures.entry(name.to_string()).or_insert(0);
+        *failures += 1;
+        let failures = *failures;
+        if let Some(threshold) = self.auto_disable_threshold {
+            if failures >= threshold {
+                self.event_failures.remove(name);
+                eprintln!(
+                    "Plugin {} failed {} consecutive events, disabling",
+                    name, failures
+                );
+                if let Err(e) = self.disable_entry(name) {
+                    eprintln!("Error disabling plugin {}: {}", name, e);
+                }
             }
         }
-        Err(PluginError::EnableError("Can't enable plugin".into()))
     }
-    /// 禁用插件
+    /// 獲取插件回報的資源用量
+    /// - 插件透過匯出可選的 `plugin_resource_usage` 符號 (`fn(&dyn Plugin) -> ResourceUsage`)
+    ///   回報用量，參數為 `create_plugin` 創建的實例；未匯出時視為全部為 0
     /// - `name`: 插件名稱
-    /// - 返回值: 成功或失敗的結果
-    pub fn disable_plugin(&mut self, name: &str) -> Result<()> {
-        let ret = self.plugins.get_mut(name);
-        if let Some(entry) = ret {
-            if entry.state == PluginState::Disabled {
-                return Ok(());
+    /// - 返回值: 資源用量，插件不存在時返回 `None`
+    pub fn plugin_resource_usage(&self, name: &str) -> Option<ResourceUsage> {
+        let entry = self.plugins.get(name)?;
+        Some(
+            entry
+                .resource_usage
+                .map(|usage| usage(entry.plugin.as_ref()))
+                .unwrap_or_default(),
+        )
+    }
+    /// 加總所有已啟用插件回報的資源用量
+    /// - 未啟用的插件不計入
+    /// - 返回值: 資源用量的總和，超出範圍時維持在最大值
+    pub fn total_resource_usage(&self) -> ResourceUsage {
+        self.plugins
+            .iter()
+            .filter(|(_, entry)| entry.state == PluginState::Enabled)
+            .filter_map(|(name, _)| self.plugin_resource_usage(name))
+            .fold(ResourceUsage::default(), |total, usage| ResourceUsage {
+                approx_bytes: total.approx_bytes.saturating_add(usage.approx_bytes),
+                open_handles: total.open_handles.saturating_add(usage.open_handles),
+            })
+    }
+    /// 對所有已啟用的插件執行健康檢查
+    /// - 插件透過匯出可選的 `plugin_health_check` 符號 (`fn(&dyn Plugin) -> Result<()>`)
+    ///   提供檢查，參數為 `create_plugin` 創建的實例；未匯出時視為健康
+    /// - 啟用 [`PluginManager::set_disable_unhealthy`] 時，檢查失敗的插件會被禁用
+    /// - 管理器不會自行排程，宿主需定期調用此函數
+    /// - 返回值: 每個已啟用插件的名稱及檢查結果，依名稱排序
+    pub fn run_health_checks(&mut self) -> Vec<(String, Result<()>)> {
+        let mut results = Vec::new();
+        for name in self.sorted_names() {
+            let entry = &self.plugins[&name];
+            if entry.state != PluginState::Enabled {
+                continue;
             }
-            if entry.state == PluginState::Enabled {
-                entry.plugin.on_disable()?;
-                entry.state = PluginState::Disabled;
-                println!("Disabled plugin: {}", name);
-                return Ok(());
+            let ret = match entry.health_check {
+                Some(health_check) => {
+                    health_check(entry.plugin.as_ref()).map_err(|e| in_plugin(&name, e))
+                }
+                None => Ok(()),
+            };
+            if let Err(e) = &ret {
+                eprintln!("Plugin {} failed health check: {}", name, e);
+                if self.disable_unhealthy {
+                    if let Err(e) = self.disable_entry(&name) {
+                        eprintln!("Error disabling plugin {}: {}", name, e);
+                    }
+                }
             }
+            results.push((name, ret));
         }
-        Err(PluginError::DisableError("Can't disable plugin".into()))
+        results
+    }
+    /// 檢查事件總線與插件集合是否一致
+    /// - 返回值: 所有指向不存在插件的訂閱描述，一致時為空
+    pub fn validate_consistency(&self) -> Vec<String> {
+        let mut problems: Vec<String> = self
+            .event_bus
+            .iter()
+            .flat_map(|(event, names)| {
+                names
+                    .iter()
+                    .map(|name| &**name)
+                    .filter(|name| !self.plugins.contains_key(*name))
+                    .map(move |name| {
+                        format!("Event {} is subscribed by unknown plugin {}", event, name)
+                    })
+            })
+            .collect();
+        problems.sort();
+        problems
     }
-    /// 卸載插件
-    /// - `name`: 插件名稱
-    /// - 返回值: 成功或失敗的結果
-    pub fn unload_plugin(&mut self, name: &str) -> Result<()> {
-        // 先檢查插件是否存在
-        if let Some(entry) = self.plugins.get(name) {
-            // 1. 創建一個事件訂閱的副本
-            let events = entry.plugin.subscribed_events();
 
-            // 2. 執行禁用邏輯
-            self.disable_plugin(name)?;
+    /// 載入所有插件
+    /// - 設定 [`PluginManager::set_require_at_least`] 時，成功載入的插件少於要求數量也視為失敗
+    /// - 全部檔案載入後，調用此次載入的插件的 `on_all_loaded` 鉤子 (見 [`PluginManager::run_all_loaded`])，
+    ///   鉤子的錯誤會一併列入返回的錯誤
+    /// - 返回值: 成功或失敗的結果
+    pub fn load_all_plugins(&mut self) -> Result<()> {
+        self.check_frozen(PluginError::LoadError, "load plugins")?;
+        let (paths, mut errors) = self.scan_plugin_dir()?;
+        let first_seq = self.next_load_seq;
 
-            // 3. 取消訂閱所有事件
-            for event in events {
-                self.event_bus.unsubscribe(&event, name);
+        // 處理每個插件檔案
+        let mut loaded = 0;
+        for path in paths {
+            // 嘗試載入插件
+            match self.load_plugin(&path) {
+                Ok(()) => loaded += 1,
+                Err(e) => {
+                    let error_msg = format!("Failed to load plugin from {:?}: {}", path, e);
+                    errors.push(error_msg.clone());
+                    eprintln!("{}", error_msg);
+                }
             }
+        }
+        if loaded < self.require_at_least {
+            let error_msg = format!(
+                "Only {} plugins loaded from {:?}, at least {} required",
+                loaded, self.plugin_dir, self.require_at_least
+            );
+            errors.push(error_msg.clone());
+            eprintln!("{}", error_msg);
+        }
+        for (name, e) in self.run_all_loaded(first_seq) {
+            errors.push(format!("Plugin {} failed in on_all_loaded: {}", name, e));
+        }
+
+        // 如果有任何錯誤,收集並回傳
+        if !errors.is_empty() {
+            return Err(PluginError::LoadError(format!(
+                "Failed to load some plugins:\n{}",
+                errors.join("\n")
+            )));
+        }
+
+        Ok(())
+    }
+    /// 載入所有插件，並可在載入途中取消
+    /// - 每個插件檔案載入前都會檢查 `cancel`，設為 `true` 後不再載入剩餘的檔案
+    /// - 取消前已載入的插件會保持載入與啟用
+    /// - 停止後 (含取消) 調用已載入插件的 `on_all_loaded` 鉤子，失敗的插件仍列在 `loaded`，
+    ///   並在 `failed` 中記錄錯誤
+    /// - `cancel`: 取消訊號，可由其他執行緒透過 `Arc<AtomicBool>` 設定
+    /// - 返回值: 載入報告，`cancelled` 表示是否提前停止，只有插件目錄無法讀取時才返回錯誤
+    pub fn load_all_plugins_cancellable(&mut self, cancel: &AtomicBool) -> Result<LoadReport> {
+        self.check_frozen(PluginError::LoadError, "load plugins")?;
+        let (paths, errors) = self.scan_plugin_dir()?;
+        let first_seq = self.next_load_seq;
+        let mut report = LoadReport::default();
+        for error_msg in errors {
+            report.failed.push((self.plugin_dir.clone(), error_msg));
+        }
 
-            // 4. 獲取插件實例並執行卸載操作
-            if let Some(mut entry) = self.plugins.remove(name) {
-                // 調用卸載鉤子
-                entry.plugin.on_unload()?;
+        for path in paths {
+            if cancel.load(Ordering::Relaxed) {
+                report.cancelled = true;
+                println!("Plugin loading cancelled");
+                break;
+            }
+            let results = match self.open_plugins(&path, HashMap::new()) {
+                Ok(entries) => self.register_library(entries),
+                Err(e) => vec![Err(e)],
+            };
+            for ret in results {
+                match ret {
+                    Ok(name) => report.loaded.push(name),
+                    Err(e) => {
+                        eprintln!("Failed to load plugin from {:?}: {}", path, e);
+                        report.failed.push((path.clone(), e.to_string()));
+                    }
+                }
+            }
+        }
+        self.report_all_loaded(first_seq, &mut report);
+        Ok(report)
+    }
+    /// 記錄插件的註冊順序
+    /// - 可保存下來，在下次啟動時以 [`PluginManager::load_in_order`] 依相同順序載入
+    /// - 重新載入或替換的插件維持原本的位置
+    /// - 返回值: 依註冊順序排列的插件名稱
+    pub fn record_load_order(&self) -> Vec<String> {
+        let mut order: Vec<(u64, &String)> = self
+            .plugins
+            .iter()
+            .map(|(name, entry)| (entry.load_seq, name))
+            .collect();
+        order.sort();
+        order.into_iter().map(|(_, name)| name.clone()).collect()
+    }
+    /// 依指定的順序載入插件目錄中的插件，不受目錄掃描順序影響
+    /// - 先以 [`PluginManager::validate_plugin_dir`] 的方式暫時開啟每個候選檔案，
+    ///   找出提供各插件名稱的檔案，再依 `order` 的順序逐一載入
+    /// - 沒有對應檔案或已載入的名稱會被略過；提供多個插件的動態庫在第一次出現時一次載入所有插件
+    /// - 多個檔案提供相同名稱時使用路徑排序較前的檔案
+    /// - 全部載入後調用 `on_all_loaded` 鉤子，處理方式與 [`PluginManager::load_all_plugins_cancellable`] 相同
+    /// - `order`: 插件名稱的載入順序，通常來自 [`PluginManager::record_load_order`]
+    /// - 返回值: 載入報告，插件目錄無法讀取或管理器已凍結時記錄在 `failed`
+    pub fn load_in_order(&mut self, order: &[String]) -> LoadReport {
+        let mut report = LoadReport::default();
+        let paths = self
+            .check_frozen(PluginError::LoadError, "load plugins")
+            .and_then(|()| self.scan_plugin_dir());
+        let (mut paths, errors) = match paths {
+            Ok(scanned) => scanned,
+            Err(e) => {
+                report.failed.push((self.plugin_dir.clone(), e.to_string()));
+                return report;
+            }
+        };
+        for error_msg in errors {
+            report.failed.push((self.plugin_dir.clone(), error_msg));
+        }
+        let mut files: HashMap<String, PathBuf> = HashMap::new();
+        paths.sort();
+        for path in paths {
+            match self.probe_plugin_names(&path) {
+                Ok(names) => {
+                    for name in names {
+                        files.entry(name).or_insert_with(|| path.clone());
+                    }
+                }
+                Err(e) => report.failed.push((path, e.to_string())),
+            }
+        }
 
-                // 執行標準卸載程序
-                unsafe {
-                    if let Ok(unload_plugin) = entry.library.get::<fn()>(b"unload_plugin") {
-                        unload_plugin();
+        let first_seq = self.next_load_seq;
+        for name in order {
+            if self.plugins.contains_key(name) {
+                continue;
+            }
+            let Some(path) = files.get(name) else {
+                eprintln!(
+                    "Skipping plugin {}: no matching file in {:?}",
+                    name, self.plugin_dir
+                );
+                continue;
+            };
+            let results = match self.open_plugins(path, HashMap::new()) {
+                Ok(entries) => self.register_library(entries),
+                Err(e) => vec![Err(e)],
+            };
+            for ret in results {
+                match ret {
+                    Ok(name) => report.loaded.push(name),
+                    Err(e) => {
+                        eprintln!("Failed to load plugin from {:?}: {}", path, e);
+                        report.failed.push((path.clone(), e.to_string()));
                     }
                 }
-                println!("Unloaded plugin: {}", name);
             }
         }
-        Ok(())
+        self.report_all_loaded(first_seq, &mut report);
+        report
     }
+    /// 以非同步方式載入所有插件
+    /// - 目錄掃描與檔案驗證在目前的任務上同步執行 (僅檔案系統中繼資料，成本低)
+    /// - 開啟動態庫 (預設的 `Library::new`，含動態庫的靜態初始化) 屬於阻塞的 FFI 操作，
+    ///   會透過 `spawn_blocking` 在阻塞執行緒上並行執行
+    /// - 由於 `dyn Plugin` 不保證 `Send`，`create_plugin`、加載與啟用鉤子以及註冊
+    ///   會在所有動態庫開啟後，於目前的任務上依掃描順序執行
+    /// - 需要 Tokio 執行環境，且返回的 Future 不是 `Send`
+    /// - 全部註冊後調用 `on_all_loaded` 鉤子，處理方式與 [`PluginManager::load_all_plugins_cancellable`] 相同
+    /// - 返回值: 載入報告，只有插件目錄無法讀取時才返回錯誤
+    #[cfg(feature = "async")]
+    pub async fn load_all_plugins_async(&mut self) -> Result<LoadReport> {
+        self.check_frozen(PluginError::LoadError, "load plugins")?;
+        let (paths, errors) = self.scan_plugin_dir()?;
+        let first_seq = self.next_load_seq;
+        let mut report = LoadReport::default();
+        for error_msg in errors {
+            report.failed.push((self.plugin_dir.clone(), error_msg));
+        }
 
-    /// 發送事件
-    /// - `event`: 要發送的事件
-    /// - 返回值: 成功或失敗的結果
-    // pub fn broadcast_event(&self, event: Event) -> Result<()> {
-    //     let subscribers = self.event_bus.get_subscribers(&event.name);
-    //     // 根據優先級排序
-    //     let mut subscribers: Vec<_> = subscribers
-    //         .iter()
-    //         .filter_map(|name| {
-    //             self.plugins.get(name).and_then(|entry| {
-    //                 if entry.state == PluginState::Enabled {
-    //                     Some((name, entry))
-    //                 } else {
-    //                     None
-    //                 }
-    //             })
-    //         })
-    //         .collect();
-    //     subscribers.sort_by_key(|(_, entry)| {
-    //         entry.plugin.subscribed_events().len() // 簡單用訂閱數量作為優先級
-    //     });
-    //     // 依序發送事件
-    //     for (name, entry) in subscribers {
-    //         if let Err(e) = entry.plugin.handle_event(&event) {
-    //             println!("Error handling event in plugin {}: {}", name, e);
-    //         }
-    //     }
-    //     Ok(())
-    // }
-    // pub fn broadcast_event(&self, event: Event) -> Result<()> {
-    //     let subscribers = self.event_bus.get_subscribers(&event.name);
-
-    //     for name in subscribers {
-    //         if let Some(entry) = self.plugins.get(&name) {
-    //             if entry.state == PluginState::Enabled {
-    //                 // 處理事件並檢查是否有回應事件
-    //                 if let Some(response_event) = entry.plugin.handle_event(&event)? {
-    //                     // 遞歸發送回應事件
-    //                     self.broadcast_event(response_event)?;
-    //                 }
-    //             }
-    //         }
-    //     }
-
-    //     Ok(())
-    // }
+        // 並行開啟所有動態庫
+        let tasks: Vec<_> = paths
+            .into_iter()
+            .map(|path| {
+                let task_path = path.clone();
+                let loader = Arc::clone(&self.loader);
+                let task =
+                    tokio::task::spawn_blocking(move || Self::open_library(&*loader, &task_path));
+                (path, task)
+            })
+            .collect();
 
-    /// 載入所有插件
-    /// - 返回值: 成功或失敗的結果
-    pub fn load_all_plugins(&mut self) -> Result<()> {
+        // 依序創建並註冊插件
+        for (path, task) in tasks {
+            let entries = match task.await {
+                Ok(opened) => {
+                    opened.and_then(|opened| self.instantiate_plugins(opened, HashMap::new(), None))
+                }
+                Err(e) => Err(PluginError::LoadError(format!(
+                    "Plugin loader task failed: {}",
+                    e
+                ))),
+            };
+            let results = match entries {
+                Ok(entries) => self.register_library(entries),
+                Err(e) => vec![Err(e)],
+            };
+            for ret in results {
+                match ret {
+                    Ok(name) => report.loaded.push(name),
+                    Err(e) => {
+                        eprintln!("Failed to load plugin from {:?}: {}", path, e);
+                        report.failed.push((path.clone(), e.to_string()));
+                    }
+                }
+            }
+        }
+        self.report_all_loaded(first_seq, &mut report);
+        Ok(report)
+    }
+    /// 掃描插件目錄中的有效插件檔案
+    /// - 返回值: 有效的插件檔案路徑，以及讀取目錄項目時的錯誤訊息
+    fn scan_plugin_dir(&self) -> Result<(Vec<PathBuf>, Vec<String>)> {
+        let mut paths = Vec::new();
         let mut errors = Vec::new();
 
         // 驗證插件目錄存在且可讀取
@@ -276,22 +3869,15 @@ impl PluginManager {
             }
         };
 
-        // 處理每個插件檔案
         for entry in dir_entries {
             match entry {
                 Ok(entry) => {
                     let path = entry.path();
 
                     // 驗證是否為有效的插件檔案
-                    if !self.is_valid_plugin_file(&path) {
-                        continue;
-                    }
-
-                    // 嘗試載入插件
-                    if let Err(e) = self.load_plugin(&path) {
-                        let error_msg = format!("Failed to load plugin from {:?}: {}", path, e);
-                        errors.push(error_msg.clone());
-                        eprintln!("{}", error_msg);
+                    match self.check_plugin_file(&path) {
+                        PluginFileCheck::Valid => paths.push(path),
+                        reason => log::debug!("Skipping {:?}: {:?}", path, reason),
                     }
                 }
                 Err(e) => {
@@ -302,74 +3888,637 @@ impl PluginManager {
             }
         }
 
-        // 如果有任何錯誤,收集並回傳
-        if !errors.is_empty() {
-            return Err(PluginError::LoadError(format!(
-                "Failed to load some plugins:\n{}",
-                errors.join("\n")
-            )));
-        }
-
-        Ok(())
+        Ok((paths, errors))
     }
 
-    fn is_valid_plugin_file(&self, path: &Path) -> bool {
-        // 基本副檔名檢查
-        let is_valid_extension = path.extension().map_or(false, |ext| match ext.to_str() {
-            #[cfg(target_os = "windows")]
-            Some("dll") => true,
-            #[cfg(target_os = "linux")]
-            Some("so") => true,
-            #[cfg(target_os = "macos")]
-            Some("dylib") => true,
-            _ => false,
-        });
+    /// 檢查檔案是否為有效的插件檔案
+    /// - 符號連結在跟隨時以解析後的目標檔案檢查副檔名與權限，不跟隨時一律拒絕
+    /// - `path`: 檔案路徑
+    /// - 返回值: 檢查結果，無效時說明原因
+    pub fn check_plugin_file(&self, path: &Path) -> PluginFileCheck {
+        let is_symlink = path
+            .symlink_metadata()
+            .is_ok_and(|metadata| metadata.file_type().is_symlink());
+        if !is_symlink {
+            return self.check_plugin_target(path);
+        }
+        if !self.follow_symlinks {
+            return PluginFileCheck::Symlink;
+        }
+        match std::fs::canonicalize(path) {
+            Ok(target) => self.check_plugin_target(&target),
+            // 指向不存在檔案的符號連結
+            Err(_) => PluginFileCheck::NotAFile,
+        }
+    }
+    /// 檢查已解析符號連結的檔案是否為有效的插件檔案
+    /// - `path`: 檔案路徑
+    /// - 返回值: 檢查結果，無效時說明原因
+    fn check_plugin_target(&self, path: &Path) -> PluginFileCheck {
+        // 基本副檔名檢查，任一後綴符合即可 (例如 `libfoo.so.1`)
+        let file_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy())
+            .unwrap_or_default();
+        let is_valid_extension = file_name.split('.').skip(1).any(|suffix| {
+            self.extensions
+                .iter()
+                .any(|ext| ext.eq_ignore_ascii_case(suffix))
+        }) || (self.accept_extensionless && path.extension().is_none());
 
         if !is_valid_extension {
-            return false;
+            return PluginFileCheck::WrongExtension;
         }
 
-        // 確保檔案存在且可讀取
-        if !path.exists() || !path.is_file() {
-            return false;
+        // 確保檔案存在且為一般檔案
+        if !path.exists() {
+            return PluginFileCheck::NotAFile;
+        }
+        let Ok(metadata) = path.metadata() else {
+            return PluginFileCheck::Unreadable;
+        };
+        if metadata.is_dir() {
+            return PluginFileCheck::Directory;
+        }
+        if !metadata.is_file() {
+            return PluginFileCheck::SpecialFile;
         }
 
         // 檢查檔案權限
-        if let Ok(metadata) = path.metadata() {
-            #[cfg(unix)]
-            return metadata.permissions().mode() & 0o111 != 0;
-            #[cfg(not(unix))]
-            return metadata.permissions().readonly() == false;
+        #[cfg(all(unix, not(target_os = "macos")))]
+        let permitted = metadata.permissions().mode() & 0o111 != 0;
+        // macOS 的可載入套件常沒有執行權限，但 dlopen 只需要讀取權限
+        #[cfg(target_os = "macos")]
+        let permitted = metadata.permissions().mode() & 0o444 != 0;
+        #[cfg(not(unix))]
+        let permitted = !metadata.permissions().readonly();
+        if !permitted {
+            return PluginFileCheck::NotExecutable;
         }
 
-        false
+        PluginFileCheck::Valid
+    }
+    fn is_valid_plugin_file(&self, path: &Path) -> bool {
+        self.check_plugin_file(path) == PluginFileCheck::Valid
     }
     /// 獲取插件
     /// - `name`: 插件名稱
     /// - 返回值: 插件實例
     pub fn get_plugin(&self, name: &str) -> Option<&dyn Plugin> {
-        self.plugins.get(name).map(|entry| entry.plugin.as_ref())
+        self.plugins
+            .get(name)
+            .map(|entry| -> &dyn Plugin { entry.plugin.as_ref() })
+    }
+    /// 獲取所有插件的註冊名稱
+    /// - 返回值: 插件名稱，依名稱排序，與 [`PluginManager::plugin_at`] 的索引順序相同
+    pub fn plugin_names(&self) -> Vec<String> {
+        self.sorted_names()
+    }
+    /// 依索引獲取插件
+    /// - 索引對應 [`PluginManager::plugin_names`] 的順序，載入或卸載插件之前同一索引指向同一插件
+    /// - `index`: 插件索引
+    /// - 返回值: (註冊名稱, 插件實例)，索引超出範圍時返回 `None`
+    pub fn plugin_at(&self, index: usize) -> Option<(&str, &dyn Plugin)> {
+        let mut names: Vec<&String> = self.plugins.keys().collect();
+        names.sort();
+        let name = *names.get(index)?;
+        Some((name.as_str(), self.plugins[name].plugin.as_ref()))
+    }
+    /// 獲取插件的索引
+    /// - `name`: 插件名稱
+    /// - 返回值: 插件在 [`PluginManager::plugin_names`] 中的索引，插件不存在時返回 `None`
+    pub fn plugin_index(&self, name: &str) -> Option<usize> {
+        if !self.plugins.contains_key(name) {
+            return None;
+        }
+        Some(
+            self.plugins
+                .keys()
+                .filter(|other| other.as_str() < name)
+                .count(),
+        )
+    }
+    /// 在限定範圍內存取插件的動態庫，用於取得插件額外匯出的符號
+    /// - 只適用於以 `libloading` 開啟的插件 (預設的 [`LibloadingLoader`])
+    /// - 安全性: 呼叫者需自行以 `unsafe` 調用 `Library::get`，並確保符號型別與插件實際匯出的一致，
+    ///   型別不符是未定義行為
+    /// - 取得的 `Symbol` 借用動態庫，只能在 `f` 內使用；`T` 不能借用動態庫，但從符號複製出的
+    ///   函數指針或資料指針不受此限制，呼叫者必須確保它們不會在插件卸載或重新載入後使用
+    /// - `f` 執行期間不可存取管理器，插件不會被卸載
+    /// - `name`: 插件名稱
+    /// - `f`: 存取動態庫的函數
+    /// - 返回值: `f` 的返回值，插件不存在或不是以 `libloading` 開啟時返回 `None`
+    pub fn with_library<T, F: FnOnce(&Library) -> T>(&self, name: &str, f: F) -> Option<T> {
+        let entry = self.plugins.get(name)?;
+        let library = entry.library.as_library()?;
+        let _guard = entry.borrow_guard();
+        Some(f(library))
+    }
+    /// 獲取插件的狀態
+    /// - `name`: 插件名稱
+    /// - 返回值: 插件目前的狀態，插件不存在時返回 `None`
+    pub fn state_of(&self, name: &str) -> Option<&PluginState> {
+        self.plugins.get(name).map(|entry| &entry.state)
+    }
+    /// 檢查插件的動態庫是否匯出 `unload_plugin` 清理函數
+    /// - 在載入時檢查，未匯出的插件卸載時不會執行動態庫的清理
+    /// - `name`: 插件名稱
+    /// - 返回值: 是否匯出，插件不存在時返回 `None`
+    pub fn plugin_has_native_cleanup(&self, name: &str) -> Option<bool> {
+        self.plugins
+            .get(name)
+            .map(|entry| entry.native_unload.is_some())
+    }
+    /// 更新已載入插件的設定
+    /// - 插件透過匯出可選的 `plugin_config_change` 符號
+    ///   (`fn(&mut dyn Plugin, &HashMap<String, String>) -> Result<()>`) 在執行期間接收新設定，
+    ///   不需要卸載或重新載入；未匯出時只保存設定，於下次重新載入時透過 `plugin_configure` 傳入
+    /// - 新設定未包含 [`DATA_DIR_CONFIG_KEY`] 時沿用插件目前的資料目錄
+    /// - 鉤子返回錯誤時保留原本的設定
+    /// - `name`: 插件名稱
+    /// - `config`: 新的設定
+    /// - 返回值: 成功或失敗的結果，插件不存在時返回 `ConfigError`
+    pub fn update_plugin_config(
+        &mut self,
+        name: &str,
+        mut config: HashMap<String, String>,
+    ) -> Result<()> {
+        self.check_frozen(PluginError::ConfigError, "update plugin config")?;
+        let Some(entry) = self.plugins.get_mut(name) else {
+            return Err(PluginError::ConfigError(format!(
+                "Plugin {} is not loaded",
+                name
+            )));
+        };
+        config
+            .entry(DATA_DIR_CONFIG_KEY.to_string())
+            .or_insert_with(|| entry.data_dir.to_string_lossy().into_owned());
+        if let Some(config_change) = entry.config_change {
+            config_change(entry.plugin.as_mut(), &config).map_err(|e| in_plugin(name, e))?;
+        }
+        entry.config = config;
+        println!("Updated config of plugin: {}", name);
+        Ok(())
+    }
+    /// 獲取插件的資料目錄
+    /// - 路徑也會透過設定的 [`DATA_DIR_CONFIG_KEY`] 傳給插件
+    /// - `name`: 插件名稱
+    /// - 返回值: 資料目錄路徑，插件不存在時返回 `None`
+    pub fn plugin_data_dir(&self, name: &str) -> Option<PathBuf> {
+        self.plugins.get(name).map(|entry| entry.data_dir.clone())
+    }
+    /// 獲取所有插件的狀態
+    /// - 返回值: 插件名稱到狀態的複本，反映調用當下的狀態
+    pub fn states(&self) -> HashMap<String, PluginState> {
+        self.plugins
+            .iter()
+            .map(|(name, entry)| (name.clone(), entry.state.clone()))
+            .collect()
+    }
+    /// 獲取插件並向下轉型為具體型別
+    /// - 插件需匯出 `plugin_as_any` 符號 (`fn(&dyn Plugin) -> &dyn Any`)，
+    ///   返回由 `create_plugin` 創建的實例本身；未匯出時一律返回 `None`
+    /// - 注意: 型別比對依賴 `TypeId`，只有宿主與插件使用完全相同的型別
+    ///   (同一個 crate 與版本，並以相同的編譯器編譯) 時才會成功，否則返回 `None`
+    /// - `name`: 插件名稱
+    /// - 返回值: 具體型別的插件實例，插件不存在或型別不符時返回 `None`
+    pub fn get_plugin_typed<T: Plugin + 'static>(&self, name: &str) -> Option<&T> {
+        let entry = self.plugins.get(name)?;
+        let as_any = entry.as_any?;
+        as_any(entry.plugin.as_ref()).downcast_ref::<T>()
     }
     /// 獲取所有插件
-    /// - 返回值: 插件列表
+    /// - 返回值: 插件列表，名稱為註冊名稱 (以別名載入時為別名)
     pub fn get_all_plugins(&self) -> Vec<(&str, &str, &str)> {
         self.plugins
-            .values()
-            .map(|entry| {
+            .iter()
+            .map(|(name, entry)| {
                 (
-                    entry.plugin.name(),
+                    name.as_str(),
                     entry.plugin.version(),
                     entry.plugin.description(),
                 )
             })
             .collect()
     }
+    /// 獲取所有插件的摘要
+    /// - 一次取得列表所需的名稱、版本、描述、狀態與檔案路徑，不需再逐一查詢
+    /// - 返回值: 插件摘要，依註冊名稱排序
+    pub fn list_plugins(&self) -> Vec<PluginSummary> {
+        self.sorted_names()
+            .iter()
+            .map(|name| Self::summary_of(name, &self.plugins[name]))
+            .collect()
+    }
+    /// 建立插件的摘要
+    /// - `name`: 插件的註冊名稱
+    /// - `entry`: 插件條目
+    fn summary_of(name: &str, entry: &PluginEntry) -> PluginSummary {
+        PluginSummary {
+            name: name.to_string(),
+            version: entry.plugin.version().to_string(),
+            description: entry.plugin.description().to_string(),
+            state: entry.state.clone(),
+            path: entry.path.clone(),
+        }
+    }
+    /// 分頁獲取插件摘要
+    /// - 插件依註冊名稱排序，只要插件集合不變，相同的參數會得到相同的結果
+    /// - `offset`: 略過的插件數量，超出範圍時返回空的頁面
+    /// - `limit`: 此頁最多包含的插件數量
+    /// - `filter`: 篩選字串，對名稱與描述做不區分大小寫的子字串比對，`None` 表示不篩選
+    /// - 返回值: 此頁的插件摘要與符合篩選條件的總數
+    pub fn get_plugins_page(
+        &self,
+        offset: usize,
+        limit: usize,
+        filter: Option<&str>,
+    ) -> PluginPage {
+        let filter = filter.map(str::to_lowercase);
+        let matches: Vec<String> = self
+            .sorted_names()
+            .into_iter()
+            .filter(|name| match &filter {
+                Some(filter) => {
+                    name.to_lowercase().contains(filter.as_str())
+                        || self.plugins[name]
+                            .plugin
+                            .description()
+                            .to_lowercase()
+                            .contains(filter.as_str())
+                }
+                None => true,
+            })
+            .collect();
+        let items = matches
+            .iter()
+            .skip(offset)
+            .take(limit)
+            .map(|name| Self::summary_of(name, &self.plugins[name]))
+            .collect();
+        PluginPage {
+            items,
+            total: matches.len(),
+        }
+    }
+    /// 獲取插件載入各階段的耗時
+    /// - `name`: 插件名稱
+    /// - 返回值: 插件的載入耗時，插件不存在時返回 `None`
+    #[cfg(feature = "metrics")]
+    pub fn get_load_timings(&self, name: &str) -> Option<LoadTimings> {
+        self.plugins.get(name).map(|entry| entry.timings)
+    }
+    /// 獲取載入總耗時最長的插件
+    /// - `n`: 最多返回的插件數量
+    /// - 返回值: (插件名稱, 載入總耗時) 列表，依耗時由長到短排序
+    #[cfg(feature = "metrics")]
+    pub fn slowest_plugins(&self, n: usize) -> Vec<(String, Duration)> {
+        let mut timings: Vec<(String, Duration)> = self
+            .plugins
+            .iter()
+            .map(|(name, entry)| (name.clone(), entry.timings.total()))
+            .collect();
+        timings.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        timings.truncate(n);
+        timings
+    }
+    /// 獲取傳給插件的設定
+    /// - `name`: 插件名稱
+    /// - 返回值: 插件的設定，插件不存在時返回 `None`
+    pub fn plugin_config(&self, name: &str) -> Option<&HashMap<String, String>> {
+        self.plugins.get(name).map(|entry| &entry.config)
+    }
+    /// 獲取支援指定能力的插件
+    /// - 插件透過匯出可選的 `plugin_capabilities` 符號 (`fn(&dyn Plugin) -> Vec<String>`) 宣告能力，
+    ///   未匯出時視為沒有任何能力
+    /// - `cap`: 能力名稱，例如 `"can_export_pdf"`
+    /// - 返回值: 支援此能力的插件名稱列表，依名稱排序
+    pub fn plugins_with_capability(&self, cap: &str) -> Vec<&str> {
+        let mut names: Vec<&str> = self
+            .plugins
+            .iter()
+            .filter(|(_, entry)| entry.capabilities.iter().any(|c| c == cap))
+            .map(|(name, _)| name.as_str())
+            .collect();
+        names.sort();
+        names
+    }
+    /// 獲取插件目前在事件總線中的所有訂閱
+    /// - `name`: 插件名稱
+    /// - 返回值: 此插件訂閱的事件名稱列表，依名稱排序，插件不存在時為空
+    pub fn plugin_subscriptions(&self, name: &str) -> Vec<String> {
+        self.event_bus.get_subscriptions(name)
+    }
+    /// 獲取每個事件的訂閱者數量
+    /// - 只計算仍在插件集合中的訂閱者，忽略事件總線中殘留的名稱
+    /// - 返回值: (事件名稱, 訂閱者數量) 列表，依數量由多到少排序
+    pub fn event_subscriber_counts(&self) -> Vec<(String, usize)> {
+        let mut counts: Vec<(String, usize)> = self
+            .event_bus
+            .iter()
+            .map(|(event, names)| {
+                let count = names
+                    .iter()
+                    .map(|name| &**name)
+                    .filter(|name| self.plugins.contains_key(*name))
+                    .count();
+                (event.to_string(), count)
+            })
+            .filter(|(_, count)| *count > 0)
+            .collect();
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        counts
+    }
+    /// 嘗試啟用所有插件，不會因單一失敗而中斷
+    /// - 依依賴順序啟用，被依賴者會先於依賴者啟用，`on_enable` 可假設依賴已啟用
+    /// - 依賴啟用失敗 (或因此被略過) 的插件不會嘗試啟用，其結果為說明原因的 `EnableError`
+    /// - 返回值: 每個插件名稱及其啟用結果，依啟用順序排列
+    pub fn try_enable_all(&mut self) -> Vec<(String, Result<()>)> {
+        let mut failed: HashSet<String> = HashSet::new();
+        let mut results = Vec::with_capacity(self.plugins.len());
+        for name in self.enable_order() {
+            let failed_dependency = self.plugins[&name]
+                .dependencies
+                .iter()
+                .find(|dependency| failed.contains(*dependency))
+                .cloned();
+            let ret = match failed_dependency {
+                Some(dependency) => Err(in_plugin(
+                    &name,
+                    PluginError::EnableError(format!(
+                        "Skipped because dependency {} failed to enable",
+                        dependency
+                    )),
+                )),
+                None => self.enable_plugin(&name),
+            };
+            if ret.is_err() {
+                failed.insert(name.clone());
+            }
+            results.push((name, ret));
+        }
+        results
+    }
+    /// 嘗試禁用所有插件，不會因單一失敗而中斷
+    /// - 依賴其他插件的插件先於其依賴禁用，因此嚴格模式下不會因依賴者仍在啟用而失敗;
+    ///   沒有依賴關係的插件依名稱排序
+    /// - 返回值: 每個插件名稱及其禁用結果，依禁用順序排列
+    pub fn try_disable_all(&mut self) -> Vec<(String, Result<()>)> {
+        self.unload_order()
+            .into_iter()
+            .map(|name| {
+                let ret = self.disable_plugin(&name);
+                (name, ret)
+            })
+            .collect()
+    }
+    /// 嘗試卸載所有插件，不會因單一失敗而中斷
+    /// - 依反向依賴順序卸載，依賴者會先於被依賴者卸載
+    /// - 返回值: 每個插件名稱及其卸載結果，依卸載順序排列
+    pub fn try_unload_all(&mut self) -> Vec<(String, Result<()>)> {
+        self.unload_order()
+            .into_iter()
+            .map(|name| {
+                let ret = self.force_unload_plugin(&name).map(|_| ());
+                (name, ret)
+            })
+            .collect()
+    }
+    /// 啟用所有插件
+    /// - 依 [`PluginManager::try_enable_all`] 的依賴順序啟用
+    /// - 返回值: 成功或失敗的結果，失敗時包含所有啟用錯誤
+    pub fn enable_all_plugins(&mut self) -> Result<()> {
+        let results = self.try_enable_all();
+        Self::aggregate_results("enable", results, PluginError::EnableError)
+    }
+    /// 禁用所有插件
+    /// - 返回值: 成功或失敗的結果，失敗時包含所有禁用錯誤
+    pub fn disable_all_plugins(&mut self) -> Result<()> {
+        let results = self.try_disable_all();
+        Self::aggregate_results("disable", results, PluginError::DisableError)
+    }
+    /// 依名稱排序的插件名稱列表
+    fn sorted_names(&self) -> Vec<String> {
+        let mut names: Vec<_> = self.plugins.keys().cloned().collect();
+        names.sort();
+        names
+    }
+    /// 將多個插件的操作結果合併為單一結果
+    /// - `action`: 操作名稱，用於錯誤訊息
+    /// - `results`: 每個插件的操作結果
+    /// - `make_error`: 用於建立合併錯誤的錯誤類型
+    fn aggregate_results(
+        action: &str,
+        results: Vec<(String, Result<()>)>,
+        make_error: fn(String) -> PluginError,
+    ) -> Result<()> {
+        let errors: Vec<String> = results
+            .into_iter()
+            .filter_map(|(name, ret)| ret.err().map(|e| (name, e)))
+            .map(|(name, e)| {
+                let error_msg = format!("Failed to {} plugin {}: {}", action, name, e);
+                eprintln!("{}", error_msg);
+                error_msg
+            })
+            .collect();
+
+        if !errors.is_empty() {
+            return Err(make_error(format!(
+                "Failed to {} some plugins:\n{}",
+                action,
+                errors.join("\n")
+            )));
+        }
+
+        Ok(())
+    }
+    /// 建立管理器狀態快照
+    /// - 返回值: 所有已載入插件的名稱、路徑與狀態，依名稱排序
+    pub fn snapshot(&self) -> ManagerSnapshot {
+        let mut plugins: Vec<PluginSnapshot> = self
+            .plugins
+            .iter()
+            .map(|(name, entry)| PluginSnapshot {
+                name: name.clone(),
+                path: entry.path.clone(),
+                state: entry.state.clone(),
+            })
+            .collect();
+        plugins.sort_by(|a, b| a.name.cmp(&b.name));
+        let profiles = self
+            .profiles
+            .iter()
+            .map(|(name, plugins)| (name.clone(), plugins.clone()))
+            .collect();
+        ManagerSnapshot { plugins, profiles }
+    }
+    /// 從快照恢復管理器狀態
+    /// - 先在不自動啟用的情況下載入快照中的每個插件，再套用其記錄的狀態，
+    ///   因此記錄為非啟用狀態的插件不會先被啟用
+    /// - 已載入的同名插件不會重新載入，只套用狀態
+    /// - `Enabled` 依依賴順序啟用；`Disabled` 會禁用插件，剛載入的插件直接標記為禁用;
+    ///   `Error` 會禁用已啟用的插件並恢復記錄的錯誤訊息 (不計入安全模式的門檻);
+    ///   `Loaded` 會禁用已啟用的插件，`Unloaded` 不做任何事
+    /// - 管理器已凍結時不做任何變更，快照中的每個插件都記錄為失敗
+    /// - `snapshot`: 要恢復的快照
+    /// - 返回值: 載入報告，依快照的順序排列，檔案已移動、載入失敗或狀態無法套用的插件會記錄為失敗
+    pub fn restore(&mut self, snapshot: &ManagerSnapshot) -> LoadReport {
+        if let Err(e) = self.check_frozen(PluginError::LoadError, "restore snapshots") {
+            let failed = snapshot
+                .plugins
+                .iter()
+                .map(|item| (item.path.clone(), e.to_string()))
+                .collect();
+            return LoadReport {
+                failed,
+                ..LoadReport::default()
+            };
+        }
+        for (name, plugins) in &snapshot.profiles {
+            self.define_profile(name, plugins.clone());
+        }
+        let mut failed: HashMap<String, String> = HashMap::new();
+        let auto_enable = std::mem::replace(&mut self.auto_enable, false);
+        for item in &snapshot.plugins {
+            if self.plugins.contains_key(&item.name) {
+                continue;
+            }
+            let ret = if item.path.is_file() {
+                self.load_plugin(&item.path).map_err(|e| e.to_string())
+            } else {
+                Err(format!("Plugin file for {} not found", item.name))
+            };
+            let ret = ret.and_then(|_| match self.plugins.contains_key(&item.name) {
+                true => Ok(()),
+                false => Err(format!(
+                    "Plugin at {:?} did not register as {}",
+                    item.path, item.name
+                )),
+            });
+            if let Err(e) = ret {
+                failed.insert(item.name.clone(), e);
+            }
+        }
+        self.auto_enable = auto_enable;
+
+        // 先套用非啟用的狀態 (依賴者優先)，再依依賴順序啟用
+        let recorded: HashMap<&str, &PluginState> = snapshot
+            .plugins
+            .iter()
+            .filter(|item| !failed.contains_key(&item.name))
+            .map(|item| (item.name.as_str(), &item.state))
+            .collect();
+        for name in self.unload_order() {
+            let Some(&state) = recorded.get(name.as_str()) else {
+                continue;
+            };
+            if *state == PluginState::Enabled {
+                continue;
+            }
+            if let Err(e) = self.restore_state(&name, state) {
+                failed.insert(name, e.to_string());
+            }
+        }
+        for name in self.enable_order() {
+            if recorded.get(name.as_str()) != Some(&&PluginState::Enabled) {
+                continue;
+            }
+            if let Err(e) = self.enable_plugin(&name) {
+                failed.insert(name, e.to_string());
+            }
+        }
+
+        let mut report = LoadReport::default();
+        for item in &snapshot.plugins {
+            match failed.remove(&item.name) {
+                Some(e) => report.failed.push((item.path.clone(), e)),
+                None => report.loaded.push(item.name.clone()),
+            }
+        }
+        report
+    }
+    /// 套用快照記錄的非啟用狀態
+    /// - `name`: 插件名稱
+    /// - `state`: 快照記錄的狀態
+    /// - 返回值: 成功或失敗的結果
+    fn restore_state(&mut self, name: &str, state: &PluginState) -> Result<()> {
+        if *state == PluginState::Unloaded {
+            return Ok(());
+        }
+        if self.plugins[name].state == PluginState::Enabled {
+            self.disable_plugin(name)?;
+        }
+        let entry = self.plugins.get_mut(name).expect("plugin is loaded");
+        let restored = match state {
+            PluginState::Disabled if entry.state == PluginState::Loaded => PluginState::Disabled,
+            PluginState::Error(msg) => PluginState::Error(msg.clone()),
+            _ => return Ok(()),
+        };
+        entry.state = restored;
+        self.state_notifier.notify(name, &entry.state);
+        Ok(())
+    }
+    /// 定義插件設定檔，已存在的同名設定檔會被取代
+    /// - `name`: 設定檔名稱，例如 `"minimal"` 或 `"full"`
+    /// - `plugins`: 設定檔包含的插件名稱
+    pub fn define_profile(&mut self, name: &str, plugins: Vec<String>) {
+        self.profiles.insert(name.to_string(), plugins);
+    }
+    /// 啟用插件設定檔
+    /// - 啟用設定檔中的所有插件，並禁用其他所有插件
+    /// - 設定檔中未載入的插件會被回報，但不會中斷其他插件的啟用
+    /// - `name`: 設定檔名稱
+    /// - 返回值: 成功或失敗的結果，失敗時包含所有缺少的插件與啟用/禁用錯誤
+    pub fn activate_profile(&mut self, name: &str) -> Result<()> {
+        let Some(profile) = self.profiles.get(name).cloned() else {
+            return Err(PluginError::ConfigError(format!(
+                "Profile {} is not defined",
+                name
+            )));
+        };
+
+        let mut results = Vec::new();
+        // 先禁用設定檔以外的插件
+        for plugin in self.sorted_names() {
+            if !profile.contains(&plugin)
+                && self.plugins.get(&plugin).map(|entry| &entry.state)
+                    == Some(&PluginState::Enabled)
+            {
+                let ret = self.disable_plugin(&plugin);
+                results.push((plugin, ret));
+            }
+        }
+        // 再啟用設定檔中的插件
+        for plugin in profile {
+            let ret = if self.plugins.contains_key(&plugin) {
+                self.enable_plugin(&plugin)
+            } else {
+                Err(PluginError::EnableError(format!(
+                    "Plugin {} in profile {} is not loaded",
+                    plugin, name
+                )))
+            };
+            results.push((plugin, ret));
+        }
+        Self::aggregate_results("activate", results, PluginError::EnableError)
+    }
+    /// 卸載所有插件並將管理器重設為空的狀態
+    /// - 插件目錄與其他設定 (含訂閱數量上限) 保持不變
+    /// - 返回值: 成功或失敗的結果，失敗時包含所有卸載錯誤；管理器已凍結時不做任何變更
+    pub fn clear(&mut self) -> Result<()> {
+        self.check_frozen(PluginError::LoadError, "clear the manager")?;
+        let results = self.try_unload_all();
+        self.event_bus.clear();
+        self.event_failures.clear();
+        self.scheduled.clear();
+        Self::aggregate_results("unload", results, PluginError::LoadError)
+    }
     /// 卸載所有插件
+    /// - 依反向依賴順序卸載，依賴者會先於被依賴者卸載
     /// - 返回值: 成功或失敗的結果
     pub fn unload_all_plugins(&mut self) -> Result<()> {
-        let names: Vec<_> = self.plugins.keys().cloned().collect();
-        for name in names {
-            if let Err(e) = self.unload_plugin(&name) {
+        for name in self.unload_order() {
+            if let Err(e) = self.force_unload_plugin(&name) {
                 eprintln!("Error unloading plugin {}: {}", name, e);
             }
         }
@@ -379,6 +4528,15 @@ impl PluginManager {
 /// 插件管理器的析構函數，用於在管理器被刪除時卸載所有插件
 impl Drop for PluginManager {
     fn drop(&mut self) {
+        if self.leak_on_drop {
+            // 不調用任何卸載鉤子，插件實例與動態庫永遠不會釋放
+            for (_, entry) in self.plugins.drain() {
+                std::mem::forget(entry);
+            }
+            return;
+        }
+        // 凍結只限制執行期間的變更，釋放時仍需正常卸載
+        self.frozen = false;
         if let Err(e) = self.unload_all_plugins() {
             eprintln!("Error unloading plugins during drop: {}", e);
         }
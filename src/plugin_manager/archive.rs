@@ -0,0 +1,305 @@
+//! 插件封存檔 (`.plugin`) 的載入
+//!
+//! 封存檔是一個 zip 檔案，包含資訊清單 [`ARCHIVE_MANIFEST`]、各平台的動態庫與資源檔案。
+use super::PluginManager;
+use chm_core_define::{PluginError, Result};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use zip::ZipArchive;
+
+/// 封存檔中資訊清單的檔案名稱
+pub const ARCHIVE_MANIFEST: &str = "manifest.json";
+/// 傳給插件的設定中，資源目錄路徑的鍵
+pub const ASSETS_DIR_CONFIG_KEY: &str = "assets_dir";
+
+/// 插件封存檔的資訊清單
+#[derive(Debug, Clone, Deserialize)]
+pub struct ArchiveManifest {
+    /// 插件名稱，用於暫存檔案與資源目錄的命名
+    pub name: String,
+    /// 各平台的動態庫在封存檔中的路徑，鍵為 `{os}-{arch}`，例如 `linux-x86_64`
+    pub libraries: HashMap<String, String>,
+    /// 資源檔案在封存檔中的目錄，未設定時不解壓縮資源
+    #[serde(default)]
+    pub assets: Option<String>,
+}
+impl ArchiveManifest {
+    /// 目前平台的識別字串，格式為 `{os}-{arch}`
+    pub fn current_target() -> String {
+        format!("{}-{}", std::env::consts::OS, std::env::consts::ARCH)
+    }
+}
+
+#[allow(unused)]
+impl PluginManager {
+    /// 載入插件封存檔
+    /// - 讀取資訊清單，找出目前平台的動態庫並解壓縮到暫存目錄後載入
+    /// - 資源檔案會解壓縮到此插件專屬的目錄，路徑透過設定的
+    ///   [`ASSETS_DIR_CONFIG_KEY`] 傳給插件
+    /// - 解壓縮的檔案會在插件卸載時刪除，載入失敗時立即刪除
+    /// - `path`: 封存檔的路徑
+    /// - 返回值: 成功或失敗的結果
+    pub fn load_plugin_archive(&mut self, path: &Path) -> Result<()> {
+        self.check_frozen(PluginError::LoadError, "load plugins")?;
+        let file = File::open(path).map_err(|e| {
+            PluginError::LoadError(format!("Failed to open plugin archive {:?}: {}", path, e))
+        })?;
+        let mut archive = ZipArchive::new(file).map_err(|e| {
+            PluginError::LoadError(format!("Failed to read plugin archive {:?}: {}", path, e))
+        })?;
+
+        // 讀取資訊清單
+        let manifest: ArchiveManifest = {
+            let entry = archive.by_name(ARCHIVE_MANIFEST).map_err(|e| {
+                PluginError::LoadError(format!(
+                    "Plugin archive {:?} has no {}: {}",
+                    path, ARCHIVE_MANIFEST, e
+                ))
+            })?;
+            serde_json::from_reader(entry).map_err(|e| {
+                PluginError::ConfigError(format!("Invalid plugin archive manifest: {}", e))
+            })?
+        };
+
+        // 讀取目前平台的動態庫
+        let target = ArchiveManifest::current_target();
+        let library = manifest.libraries.get(&target).ok_or_else(|| {
+            PluginError::LoadError(format!(
+                "Plugin archive {:?} has no library for {}",
+                path, target
+            ))
+        })?;
+        let mut bytes = Vec::new();
+        archive
+            .by_name(library)
+            .map_err(|e| e.into())
+            .and_then(|mut entry| entry.read_to_end(&mut bytes))
+            .map_err(|e: std::io::Error| {
+                PluginError::LoadError(format!("Failed to read {} from archive: {}", library, e))
+            })?;
+
+        // 解壓縮資源
+        let mut config = HashMap::new();
+        let assets_dir = match &manifest.assets {
+            Some(prefix) => {
+                let dir = self.extract_assets(&mut archive, &manifest.name, prefix)?;
+                config.insert(
+                    ASSETS_DIR_CONFIG_KEY.to_string(),
+                    dir.to_string_lossy().into_owned(),
+                );
+                Some(dir)
+            }
+            None => None,
+        };
+
+        let temp_path = match self.write_temp_plugin(&manifest.name, &bytes) {
+            Ok(temp_path) => temp_path,
+            Err(e) => {
+                if let Some(dir) = &assets_dir {
+                    let _ = std::fs::remove_dir_all(dir);
+                }
+                return Err(e);
+            }
+        };
+        let ret = self.open_plugin(&temp_path, config).and_then(|entry| {
+            let name = entry.plugin.name().to_string();
+            self.register_entry(name, entry)
+        });
+
+        // 載入成功 (即使啟用失敗) 時由插件條目追蹤解壓縮的檔案，否則立即刪除
+        match self
+            .plugins
+            .values_mut()
+            .find(|entry| entry.path == temp_path)
+        {
+            Some(entry) => {
+                entry.temp_file = Some(temp_path);
+                entry.extracted_dir = assets_dir;
+            }
+            None => {
+                let _ = std::fs::remove_file(&temp_path);
+                if let Some(dir) = &assets_dir {
+                    let _ = std::fs::remove_dir_all(dir);
+                }
+            }
+        }
+        ret.map(|_| ())
+    }
+    /// 將封存檔中指定目錄下的資源解壓縮到新的暫存目錄
+    /// - `archive`: 封存檔
+    /// - `name`: 插件名稱，用於目錄命名
+    /// - `prefix`: 資源在封存檔中的目錄
+    /// - 返回值: 解壓縮後的資源目錄
+    fn extract_assets(
+        &self,
+        archive: &mut ZipArchive<File>,
+        name: &str,
+        prefix: &str,
+    ) -> Result<PathBuf> {
+        let dest = self.unique_temp_path(name, "assets");
+        if let Err(e) = extract_prefix(archive, prefix, &dest) {
+            let _ = std::fs::remove_dir_all(&dest);
+            return Err(PluginError::LoadError(format!(
+                "Failed to extract plugin assets: {}",
+                e
+            )));
+        }
+        Ok(dest)
+    }
+}
+
+/// 將封存檔中 `prefix` 目錄下的檔案解壓縮到 `dest`
+/// - 只處理路徑位於封存檔內的項目，避免路徑穿越
+fn extract_prefix(
+    archive: &mut ZipArchive<File>,
+    prefix: &str,
+    dest: &Path,
+) -> std::io::Result<()> {
+    std::fs::create_dir_all(dest)?;
+    for i in 0..archive.len() {
+        let mut entry = archive.by_index(i)?;
+        let Some(entry_path) = entry.enclosed_name() else {
+            continue;
+        };
+        let Ok(relative) = entry_path.strip_prefix(prefix) else {
+            continue;
+        };
+        let target = dest.join(relative);
+        if entry.is_dir() {
+            std::fs::create_dir_all(&target)?;
+        } else {
+            if let Some(parent) = target.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            let mut out = File::create(&target)?;
+            std::io::copy(&mut entry, &mut out)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_util::{FakeLibrary, Fixture};
+    use super::super::PluginState;
+    use super::*;
+    use std::io::Write;
+    use zip::write::SimpleFileOptions;
+
+    /// 在插件目錄寫入封存檔，`files` 為 (封存檔內路徑, 內容)
+    fn write_archive(fixture: &Fixture, file: &str, files: &[(&str, &[u8])]) -> PathBuf {
+        let path = fixture.dir.join(file);
+        let mut writer = zip::ZipWriter::new(File::create(&path).unwrap());
+        for (name, contents) in files {
+            writer
+                .start_file(name.to_string(), SimpleFileOptions::default())
+                .unwrap();
+            writer.write_all(contents).unwrap();
+        }
+        writer.finish().unwrap();
+        path
+    }
+
+    /// 目前平台的動態庫位於 `lib/alpha`，資源位於 `assets/`
+    fn manifest(target: &str) -> String {
+        format!(
+            r#"{{"name": "alpha", "libraries": {{"{}": "lib/alpha"}}, "assets": "assets"}}"#,
+            target
+        )
+    }
+
+    #[test]
+    fn archive_extracts_library_and_assets_until_unload() {
+        let fixture = Fixture::new();
+        fixture
+            .loader
+            .register("alpha-library", FakeLibrary::new(&fixture.plugin("alpha")));
+        let manifest = manifest(&ArchiveManifest::current_target());
+        let path = write_archive(
+            &fixture,
+            "alpha.plugin",
+            &[
+                (ARCHIVE_MANIFEST, manifest.as_bytes()),
+                ("lib/alpha", b"alpha-library"),
+                ("assets/icons/logo.txt", b"logo"),
+            ],
+        );
+        let mut manager = fixture.manager();
+        manager.load_plugin_archive(&path).unwrap();
+        assert_eq!(manager.plugins["alpha"].state, PluginState::Enabled);
+
+        let assets = PathBuf::from(&manager.plugin_config("alpha").unwrap()[ASSETS_DIR_CONFIG_KEY]);
+        assert_eq!(
+            std::fs::read(assets.join("icons").join("logo.txt")).unwrap(),
+            b"logo"
+        );
+        manager.unload_plugin("alpha").unwrap();
+        assert!(!assets.exists());
+        assert_eq!(
+            std::fs::read_dir(fixture.dir.join(".tmp")).unwrap().count(),
+            0
+        );
+    }
+
+    #[test]
+    fn archive_without_a_library_for_this_target_is_rejected() {
+        let fixture = Fixture::new();
+        let manifest = manifest("plan9-mips");
+        let path = write_archive(
+            &fixture,
+            "alpha.plugin",
+            &[
+                (ARCHIVE_MANIFEST, manifest.as_bytes()),
+                ("lib/alpha", b"alpha-library"),
+            ],
+        );
+        let mut manager = fixture.manager();
+        let err = manager.load_plugin_archive(&path).unwrap_err();
+        assert!(matches!(err, PluginError::LoadError(msg) if msg.contains("has no library for")));
+        assert!(manager.plugin_names().is_empty());
+    }
+
+    #[test]
+    fn archive_needs_a_valid_manifest() {
+        let fixture = Fixture::new();
+        let missing = write_archive(&fixture, "missing.plugin", &[("lib/alpha", b"")]);
+        let invalid = write_archive(
+            &fixture,
+            "invalid.plugin",
+            &[(ARCHIVE_MANIFEST, b"{\"name\": 1}")],
+        );
+        let mut manager = fixture.manager();
+        assert!(matches!(
+            manager.load_plugin_archive(&missing),
+            Err(PluginError::LoadError(msg)) if msg.contains(ARCHIVE_MANIFEST)
+        ));
+        assert!(matches!(
+            manager.load_plugin_archive(&invalid),
+            Err(PluginError::ConfigError(_))
+        ));
+    }
+
+    #[test]
+    fn failed_archive_load_removes_extracted_files() {
+        let fixture = Fixture::new();
+        let manifest = manifest(&ArchiveManifest::current_target());
+        let path = write_archive(
+            &fixture,
+            "alpha.plugin",
+            &[
+                (ARCHIVE_MANIFEST, manifest.as_bytes()),
+                ("lib/alpha", b"unknown library"),
+                ("assets/logo.txt", b"logo"),
+            ],
+        );
+        let mut manager = fixture.manager();
+        assert!(manager.load_plugin_archive(&path).is_err());
+        assert_eq!(
+            std::fs::read_dir(fixture.dir.join(".tmp")).unwrap().count(),
+            0
+        );
+    }
+}
@@ -0,0 +1,192 @@
+//! 讀取動態庫檔頭中的目標架構
+//!
+//! 只解析 ELF、Mach-O 與 PE 檔頭中記錄機器類型的欄位，用於在 `Library::new` 之前
+//! 拒絕為其他架構編譯的插件；無法辨識的檔案交給 `Library::new` 處理。
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// 讀取動態庫的目標架構
+/// - 架構名稱與 [`std::env::consts::ARCH`] 使用相同的寫法 (例如 `x86_64`、`aarch64`)
+/// - Mach-O 通用二進位檔 (fat binary) 可能包含多個架構，不做判斷
+/// - `path`: 動態庫路徑
+/// - 返回值: 架構名稱，檔案無法讀取、格式無法辨識或架構未知時返回 `None`
+pub(crate) fn binary_arch(path: &Path) -> Option<&'static str> {
+    let mut file = File::open(path).ok()?;
+    let mut header = [0u8; 64];
+    let len = read_up_to(&mut file, &mut header)?;
+    let header = &header[..len];
+    if header.starts_with(b"\x7fELF") {
+        return elf_arch(header);
+    }
+    if header.len() >= 8 {
+        let magic = u32::from_le_bytes([header[0], header[1], header[2], header[3]]);
+        let read = match magic {
+            0xfeed_face | 0xfeed_facf => u32::from_le_bytes,
+            0xcefa_edfe | 0xcffa_edfe => u32::from_be_bytes,
+            _ => return pe_arch(&mut file, header),
+        };
+        let cpu_type = read([header[4], header[5], header[6], header[7]]);
+        return macho_arch(cpu_type);
+    }
+    None
+}
+
+/// 讀取檔頭，檔案比緩衝區短時只讀取實際長度
+/// - 返回值: 讀取的位元組數，讀取失敗時返回 `None`
+fn read_up_to(file: &mut File, buf: &mut [u8]) -> Option<usize> {
+    let mut len = 0;
+    while len < buf.len() {
+        match file.read(&mut buf[len..]) {
+            Ok(0) => break,
+            Ok(n) => len += n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(_) => return None,
+        }
+    }
+    Some(len)
+}
+
+/// 解析 ELF 檔頭的 `e_machine` 欄位
+fn elf_arch(header: &[u8]) -> Option<&'static str> {
+    let bytes = [*header.get(18)?, *header.get(19)?];
+    // EI_DATA: 1 為小端序，2 為大端序
+    let machine = match header.get(5)? {
+        1 => u16::from_le_bytes(bytes),
+        2 => u16::from_be_bytes(bytes),
+        _ => return None,
+    };
+    match machine {
+        3 => Some("x86"),
+        // EI_CLASS: 2 為 64 位元
+        8 if header[4] == 2 => Some("mips64"),
+        8 => Some("mips"),
+        20 => Some("powerpc"),
+        21 => Some("powerpc64"),
+        22 => Some("s390x"),
+        40 => Some("arm"),
+        62 => Some("x86_64"),
+        183 => Some("aarch64"),
+        243 if header[4] == 2 => Some("riscv64"),
+        243 => Some("riscv32"),
+        258 => Some("loongarch64"),
+        _ => None,
+    }
+}
+
+/// 將 Mach-O 檔頭的 `cputype` 欄位轉為架構名稱
+fn macho_arch(cpu_type: u32) -> Option<&'static str> {
+    match cpu_type {
+        7 => Some("x86"),
+        0x0100_0007 => Some("x86_64"),
+        12 => Some("arm"),
+        0x0100_000c => Some("aarch64"),
+        18 => Some("powerpc"),
+        0x0100_0012 => Some("powerpc64"),
+        _ => None,
+    }
+}
+
+/// 解析 PE 檔頭的 `Machine` 欄位
+/// - `file`: 動態庫檔案，用於讀取位於 `e_lfanew` 位移的 PE 檔頭
+/// - `header`: 檔案開頭的位元組
+fn pe_arch(file: &mut File, header: &[u8]) -> Option<&'static str> {
+    if !header.starts_with(b"MZ") || header.len() < 0x40 {
+        return None;
+    }
+    let offset = u32::from_le_bytes([header[0x3c], header[0x3d], header[0x3e], header[0x3f]]);
+    file.seek(SeekFrom::Start(u64::from(offset))).ok()?;
+    let mut pe = [0u8; 6];
+    file.read_exact(&mut pe).ok()?;
+    if &pe[..4] != b"PE\0\0" {
+        return None;
+    }
+    match u16::from_le_bytes([pe[4], pe[5]]) {
+        0x014c => Some("x86"),
+        0x8664 => Some("x86_64"),
+        0x01c0 | 0x01c4 => Some("arm"),
+        0xaa64 => Some("aarch64"),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_util::{write_file, TempDir};
+    use super::super::{is_incompatible_plugin, PluginManager};
+    use super::*;
+
+    /// 小端序 64 位元 ELF 檔頭
+    fn elf_header(machine: u16) -> Vec<u8> {
+        let mut header = vec![0u8; 64];
+        header[..4].copy_from_slice(b"\x7fELF");
+        header[4] = 2;
+        header[5] = 1;
+        header[18..20].copy_from_slice(&machine.to_le_bytes());
+        header
+    }
+
+    /// `e_lfanew` 指向 0x40 的 PE 檔頭
+    fn pe_header(machine: u16) -> Vec<u8> {
+        let mut header = vec![0u8; 0x40];
+        header[..2].copy_from_slice(b"MZ");
+        header[0x3c..0x40].copy_from_slice(&0x40u32.to_le_bytes());
+        header.extend_from_slice(b"PE\0\0");
+        header.extend_from_slice(&machine.to_le_bytes());
+        header
+    }
+
+    fn arch_of(dir: &TempDir, file: &str, contents: &[u8]) -> Option<&'static str> {
+        let path = dir.join(file);
+        write_file(&path, contents);
+        binary_arch(&path)
+    }
+
+    #[test]
+    fn headers_of_each_format_are_recognized() {
+        let dir = TempDir::new();
+        assert_eq!(arch_of(&dir, "elf.so", &elf_header(183)), Some("aarch64"));
+        assert_eq!(arch_of(&dir, "elf64.so", &elf_header(62)), Some("x86_64"));
+        let mut macho = 0xfeed_facfu32.to_le_bytes().to_vec();
+        macho.extend_from_slice(&0x0100_000cu32.to_le_bytes());
+        assert_eq!(arch_of(&dir, "macho.dylib", &macho), Some("aarch64"));
+        let mut swapped = 0xfeed_facfu32.to_be_bytes().to_vec();
+        swapped.extend_from_slice(&0x0100_0007u32.to_be_bytes());
+        assert_eq!(arch_of(&dir, "swapped.dylib", &swapped), Some("x86_64"));
+        assert_eq!(arch_of(&dir, "pe.dll", &pe_header(0x8664)), Some("x86_64"));
+        assert_eq!(
+            arch_of(&dir, "arm.dll", &pe_header(0xaa64)),
+            Some("aarch64")
+        );
+    }
+
+    #[test]
+    fn unknown_or_short_files_have_no_arch() {
+        let dir = TempDir::new();
+        assert_eq!(arch_of(&dir, "text.so", b"not a library"), None);
+        assert_eq!(arch_of(&dir, "short.so", b"\x7fEL"), None);
+        assert_eq!(arch_of(&dir, "machine.so", &elf_header(0xffff)), None);
+        assert_eq!(arch_of(&dir, "dos.dll", b"MZ"), None);
+        assert_eq!(binary_arch(&dir.join("missing.so")), None);
+    }
+
+    #[test]
+    fn plugins_for_another_arch_are_incompatible() {
+        let dir = TempDir::new();
+        let (machine, foreign) = if std::env::consts::ARCH == "aarch64" {
+            (62, "x86_64")
+        } else {
+            (183, "aarch64")
+        };
+        let path = dir.join("foreign.so");
+        write_file(&path, &elf_header(machine));
+        let mut manager = PluginManager::new(dir.path());
+        let err = manager.load_plugin(&path).unwrap_err();
+        assert!(is_incompatible_plugin(&err), "{}", err);
+        assert!(err.to_string().contains(&format!(
+            "is built for {}, host is {}",
+            foreign,
+            std::env::consts::ARCH
+        )));
+    }
+}
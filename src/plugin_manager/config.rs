@@ -0,0 +1,172 @@
+//! 從設定檔建立管理器
+use super::{default_plugin_extensions, PluginManager};
+use chm_core_define::{PluginError, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// 插件管理器的設定，可從 TOML 設定檔 (例如 `loader.toml`) 讀取
+/// - 設定檔中省略的欄位使用預設值
+///
+/// ```toml
+/// plugin_dir = "./plugins"
+/// auto_enable = true
+/// max_plugins = 32
+/// extensions = ["so", "plug"]
+/// strict = true
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PluginManagerConfig {
+    /// 插件目錄路徑，預設為 `./plugins`
+    pub plugin_dir: PathBuf,
+    /// 載入後是否自動啟用插件，預設為 `true`
+    pub auto_enable: bool,
+    /// 可同時載入的插件數量上限，預設不限制
+    pub max_plugins: Option<usize>,
+    /// 接受的插件副檔名，預設為 [`default_plugin_extensions`]
+    pub extensions: Vec<String>,
+    /// 是否接受沒有副檔名的插件檔案，預設為 `false`
+    pub accept_extensionless: bool,
+    /// 是否啟用嚴格模式，預設為 `false`
+    pub strict: bool,
+    /// 從記憶體載入插件時使用的暫存目錄，預設為系統暫存目錄
+    pub temp_dir: Option<PathBuf>,
+}
+impl Default for PluginManagerConfig {
+    fn default() -> Self {
+        Self {
+            plugin_dir: PathBuf::from("./plugins"),
+            auto_enable: true,
+            max_plugins: None,
+            extensions: default_plugin_extensions(),
+            accept_extensionless: false,
+            strict: false,
+            temp_dir: None,
+        }
+    }
+}
+impl PluginManagerConfig {
+    /// 從 TOML 設定檔讀取設定
+    /// - `path`: 設定檔路徑
+    /// - 返回值: 設定，檔案無法讀取或格式錯誤時返回 `ConfigError`
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path).map_err(|e| {
+            PluginError::ConfigError(format!("Failed to read config file {:?}: {}", path, e))
+        })?;
+        toml::from_str(&content)
+            .map_err(|e| PluginError::ConfigError(format!("Invalid config file {:?}: {}", path, e)))
+    }
+}
+
+#[allow(unused)]
+impl PluginManager {
+    /// 依設定創建插件管理器
+    /// - 之後調用的 `set_*` 函數會覆寫設定中的值
+    /// - `config`: 管理器設定
+    pub fn from_config(config: PluginManagerConfig) -> Self {
+        let mut manager = Self::new(&config.plugin_dir);
+        manager.set_auto_enable(config.auto_enable);
+        manager.set_max_plugins(config.max_plugins);
+        manager.set_extensions(config.extensions);
+        manager.set_accept_extensionless(config.accept_extensionless);
+        manager.set_strict(config.strict);
+        if let Some(temp_dir) = config.temp_dir {
+            manager.set_temp_dir(temp_dir);
+        }
+        manager
+    }
+    /// 依 TOML 設定檔創建插件管理器
+    /// - 之後調用的 `set_*` 函數會覆寫設定檔中的值
+    /// - `path`: 設定檔路徑
+    /// - 返回值: 插件管理器，設定檔無法讀取或格式錯誤時返回 `ConfigError`
+    pub fn from_config_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        PluginManagerConfig::from_file(path).map(Self::from_config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_util::{write_file, TempDir};
+    use super::super::PluginFileCheck;
+    use super::*;
+
+    #[test]
+    fn config_file_sets_extensions() {
+        let dir = TempDir::new();
+        let config = dir.join("loader.toml");
+        std::fs::write(
+            &config,
+            format!(
+                "plugin_dir = {:?}\nextensions = [\"plug\"]\naccept_extensionless = true\n",
+                dir.path()
+            ),
+        )
+        .unwrap();
+        let manager = PluginManager::from_config_file(&config).unwrap();
+        write_file(&dir.join("alpha.plug"), b"");
+        write_file(&dir.join("beta.so"), b"");
+        write_file(&dir.join("gamma"), b"");
+        assert_eq!(
+            manager.check_plugin_file(&dir.join("alpha.plug")),
+            PluginFileCheck::Valid
+        );
+        assert_eq!(
+            manager.check_plugin_file(&dir.join("beta.so")),
+            PluginFileCheck::WrongExtension
+        );
+        assert_eq!(
+            manager.check_plugin_file(&dir.join("gamma")),
+            PluginFileCheck::Valid
+        );
+    }
+
+    #[test]
+    fn config_file_sets_manager_settings() {
+        let dir = TempDir::new();
+        let config = dir.join("loader.toml");
+        std::fs::write(
+            &config,
+            format!(
+                "plugin_dir = {:?}\nauto_enable = false\nmax_plugins = 2\nstrict = true\n",
+                dir.path()
+            ),
+        )
+        .unwrap();
+        let mut manager = PluginManager::from_config_file(&config).unwrap();
+        assert_eq!(manager.plugin_dir, dir.path());
+        assert!(!manager.auto_enable);
+        assert_eq!(manager.max_plugins, Some(2));
+        assert!(manager.strict);
+
+        // 之後的 `set_*` 調用覆寫設定檔中的值
+        manager.set_strict(false);
+        manager.set_max_plugins(None);
+        assert!(!manager.strict);
+        assert_eq!(manager.max_plugins, None);
+    }
+
+    #[test]
+    fn unreadable_or_invalid_config_files_are_config_errors() {
+        let dir = TempDir::new();
+        let invalid = dir.join("loader.toml");
+        std::fs::write(&invalid, "max_plugins = \"many\"").unwrap();
+        assert!(matches!(
+            PluginManager::from_config_file(&invalid),
+            Err(PluginError::ConfigError(msg)) if msg.starts_with("Invalid config file")
+        ));
+        assert!(matches!(
+            PluginManager::from_config_file(dir.join("missing.toml")),
+            Err(PluginError::ConfigError(msg)) if msg.starts_with("Failed to read config file")
+        ));
+    }
+
+    #[test]
+    fn omitted_fields_use_defaults() {
+        let config: PluginManagerConfig = toml::from_str("strict = true").unwrap();
+        assert!(config.strict);
+        assert_eq!(config.extensions, default_plugin_extensions());
+        assert_eq!(config.plugin_dir, PathBuf::from("./plugins"));
+        assert!(config.auto_enable);
+    }
+}
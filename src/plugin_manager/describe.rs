@@ -0,0 +1,243 @@
+//! 管理器的診斷報告
+use super::event_bus::matches_event;
+use super::{PluginEntry, PluginManager, PluginState};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+
+#[allow(unused)]
+impl PluginManager {
+    /// 產生管理器的文字報告
+    /// - 包含每個插件的名稱、版本、路徑、狀態 (含錯誤訊息)、事件訂閱與載入耗時，依名稱排序
+    /// - 返回值: 可直接輸出的多行文字
+    pub fn describe(&self) -> String {
+        let names = self.sorted_names();
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "PluginManager ({:?}, {} plugins)",
+            self.plugin_dir,
+            names.len()
+        );
+        for name in &names {
+            let entry = &self.plugins[name];
+            let state = match &entry.state {
+                PluginState::Error(msg) => format!("Error: {}", msg),
+                state => format!("{:?}", state),
+            };
+            let _ = writeln!(out, "- {} v{} [{}]", name, entry.plugin.version(), state);
+            let _ = writeln!(out, "    path: {:?}", entry.path);
+            let subscriptions = self.plugin_subscriptions(name);
+            if !subscriptions.is_empty() {
+                let _ = writeln!(out, "    subscriptions: {}", subscriptions.join(", "));
+            }
+            if !entry.dependencies.is_empty() {
+                let _ = writeln!(out, "    dependencies: {}", entry.dependencies.join(", "));
+            }
+            #[cfg(feature = "metrics")]
+            {
+                let t = &entry.timings;
+                let _ = writeln!(
+                    out,
+                    "    timings: total {:?} (open {:?}, create {:?}, on_load {:?}, on_enable {:?})",
+                    t.total(),
+                    t.library_open,
+                    t.create,
+                    t.on_load,
+                    t.on_enable
+                );
+            }
+        }
+        out
+    }
+    /// 產生管理器的 JSON 報告，內容與 [`PluginManager::describe`] 相同
+    /// - 耗時以毫秒表示，未啟用 `metrics` 功能時為 `null`
+    /// - 返回值: JSON 物件
+    pub fn describe_json(&self) -> Value {
+        let plugins: Vec<Value> = self
+            .sorted_names()
+            .iter()
+            .map(|name| self.describe_entry_json(name, &self.plugins[name]))
+            .collect();
+        json!({
+            "plugin_dir": self.plugin_dir,
+            "strict": self.strict,
+            "plugins": plugins,
+            "profiles": self.snapshot().profiles,
+        })
+    }
+    /// 產生插件與事件關係的 Graphviz DOT 圖
+    /// - 插件以方框表示，事件以橢圓表示；邊由發布者指向事件，再由事件指向訂閱者
+    /// - 發布的事件來自插件匯出的可選 `plugin_published_events` 符號
+    ///   (`fn(&dyn Plugin) -> Vec<String>`)，載入時讀取一次；訂閱的事件來自事件訂閱
+    /// - 萬用字元訂閱以獨立的事件節點表示，匹配的已發布事件以虛線連到該節點
+    /// - 沒有發布者或沒有訂閱者的事件以紅色虛線外框標示，方便找出孤立的事件
+    /// - 節點依名稱排序，相同的管理器狀態會產生相同的輸出
+    /// - 返回值: DOT 格式的文字，可交給 `dot -Tsvg` 等工具繪製
+    pub fn export_graph(&self) -> String {
+        let names = self.sorted_names();
+        let mut published: BTreeMap<String, BTreeSet<&str>> = BTreeMap::new();
+        let mut subscribed: BTreeMap<String, BTreeSet<&str>> = BTreeMap::new();
+        for name in &names {
+            for event in &self.plugins[name].published_events {
+                published
+                    .entry(event.clone())
+                    .or_default()
+                    .insert(name.as_str());
+            }
+            for event in self.plugin_subscriptions(name) {
+                subscribed.entry(event).or_default().insert(name.as_str());
+            }
+        }
+        let events: BTreeSet<&String> = published.keys().chain(subscribed.keys()).collect();
+        let plugin_id = |name: &str| dot_quote(&format!("plugin:{}", name));
+        let event_id = |event: &str| dot_quote(&format!("event:{}", event));
+        let is_wildcard = |event: &str| event.ends_with('*') && !published.contains_key(event);
+
+        let mut out = String::from("digraph plugins {\n    rankdir=LR;\n");
+        for name in &names {
+            let _ = writeln!(
+                out,
+                "    {} [label={}, shape=box];",
+                plugin_id(name),
+                dot_quote(name)
+            );
+        }
+        for event in &events {
+            let has_publisher = if is_wildcard(event) {
+                published.keys().any(|p| matches_event(event, p))
+            } else {
+                published.contains_key(*event)
+            };
+            let has_subscriber = subscribed
+                .keys()
+                .any(|pattern| matches_event(pattern, event));
+            let style = if has_publisher && has_subscriber {
+                ""
+            } else {
+                ", style=dashed, color=red"
+            };
+            let _ = writeln!(
+                out,
+                "    {} [label={}, shape=ellipse{}];",
+                event_id(event),
+                dot_quote(event),
+                style
+            );
+        }
+        for (event, plugins) in &published {
+            for name in plugins {
+                let _ = writeln!(out, "    {} -> {};", plugin_id(name), event_id(event));
+            }
+        }
+        for (pattern, plugins) in &subscribed {
+            if is_wildcard(pattern) {
+                for event in published
+                    .keys()
+                    .filter(|event| matches_event(pattern, event))
+                {
+                    let _ = writeln!(
+                        out,
+                        "    {} -> {} [style=dashed];",
+                        event_id(event),
+                        event_id(pattern)
+                    );
+                }
+            }
+            for name in plugins {
+                let _ = writeln!(out, "    {} -> {};", event_id(pattern), plugin_id(name));
+            }
+        }
+        out.push_str("}\n");
+        out
+    }
+    /// 產生單一插件的 JSON 報告
+    /// - `name`: 插件的註冊名稱
+    /// - `entry`: 插件條目
+    fn describe_entry_json(&self, name: &str, entry: &PluginEntry) -> Value {
+        let error = match &entry.state {
+            PluginState::Error(msg) => Some(msg.as_str()),
+            _ => None,
+        };
+        #[cfg(feature = "metrics")]
+        let timings = {
+            let ms = |d: std::time::Duration| d.as_secs_f64() * 1000.0;
+            let t = &entry.timings;
+            json!({
+                "library_open": ms(t.library_open),
+                "create": ms(t.create),
+                "on_load": ms(t.on_load),
+                "on_enable": ms(t.on_enable),
+                "total": ms(t.total()),
+            })
+        };
+        #[cfg(not(feature = "metrics"))]
+        let timings = Value::Null;
+        json!({
+            "name": name,
+            "version": entry.plugin.version(),
+            "description": entry.plugin.description(),
+            "path": entry.path,
+            "state": entry.state,
+            "error": error,
+            "subscriptions": self.plugin_subscriptions(name),
+            "capabilities": entry.capabilities,
+            "priority": entry.priority,
+            "dependencies": entry.dependencies,
+            "timings_ms": timings,
+        })
+    }
+}
+
+/// 將文字轉為 DOT 的引號字串
+/// - `s`: 節點識別碼或標籤
+fn dot_quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_util::Fixture;
+
+    #[test]
+    fn describe_lists_every_plugin_with_errors() {
+        let fixture = Fixture::new();
+        fixture.add(&fixture.plugin("alpha").subscribe(&["save", "load"]));
+        fixture.add(&fixture.plugin("beta").failing("on_enable", "boom"));
+        let mut manager = fixture.manager();
+        let _ = manager.load_all_plugins();
+
+        let report = manager.describe();
+        assert!(report.contains("2 plugins"));
+        assert!(report.contains("- alpha v1.0.0 [Enabled]"));
+        assert!(report.contains("subscriptions: load, save"));
+        assert!(report.contains("alpha.so"));
+        let beta = report
+            .lines()
+            .find(|line| line.starts_with("- beta"))
+            .unwrap();
+        assert!(beta.contains("[Error: ") && beta.contains("boom"));
+    }
+
+    #[test]
+    fn describe_json_matches_the_text_report() {
+        let fixture = Fixture::new();
+        fixture.add(&fixture.plugin("alpha").subscribe(&["save"]));
+        fixture.add(&fixture.plugin("beta").failing("on_enable", "boom"));
+        let mut manager = fixture.manager();
+        let _ = manager.load_all_plugins();
+
+        let json = manager.describe_json();
+        let plugins = json["plugins"].as_array().unwrap();
+        assert_eq!(plugins.len(), 2);
+        assert_eq!(plugins[0]["name"], "alpha");
+        assert_eq!(plugins[0]["version"], "1.0.0");
+        assert_eq!(plugins[0]["subscriptions"][0], "save");
+        assert!(plugins[0]["error"].is_null());
+        assert!(plugins[1]["error"].as_str().unwrap().contains("boom"));
+        #[cfg(feature = "metrics")]
+        assert!(plugins[0]["timings_ms"]["total"].is_number());
+        #[cfg(not(feature = "metrics"))]
+        assert!(plugins[0]["timings_ms"].is_null());
+    }
+}
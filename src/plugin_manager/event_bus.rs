@@ -0,0 +1,436 @@
+//! 事件總線
+//!
+//! 記錄事件名稱與訂閱者之間的對應，不依賴插件，可單獨作為訂閱登記表使用。
+use chm_core_define::{PluginError, Result};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+/// 訂閱者查詢快取的項目上限，超過時清空重建
+pub(crate) const SUBSCRIBER_CACHE_CAPACITY: usize = 1024;
+
+/// 事件系統，用於管理事件的訂閱和通知
+/// - 訂閱的事件名稱以 `*` 結尾時為萬用字元訂閱，會匹配所有以 `*` 之前的部分開頭的事件，
+///   單獨的 `*` 匹配所有事件
+/// - 取消訂閱後沒有訂閱者的事件會自動移除，總線不會累積空的項目
+/// - 可限制每個訂閱者與整個總線的訂閱數量，預設不限制
+///
+/// # Examples
+/// ```
+/// use main_loader::EventBus;
+///
+/// let mut bus = EventBus::new();
+/// bus.subscribe("user.login", "audit").unwrap();
+/// bus.subscribe("user.*", "metrics").unwrap();
+/// assert_eq!(bus.get_subscribers("user.login"), vec!["audit", "metrics"]);
+/// assert_eq!(bus.get_subscribers("user.logout"), vec!["metrics"]);
+///
+/// bus.unsubscribe("user.login", "audit");
+/// assert!(bus.get_subscriptions("audit").is_empty());
+/// ```
+#[derive(Debug, Default)]
+pub struct EventBus {
+    /// 事件名稱與訂閱者名稱的字串池，所有訂閱共用同一份字串，沒有訂閱使用時會移除
+    names: HashSet<Arc<str>>,
+    /// 完整事件名稱對應的訂閱者集合
+    subscribers: HashMap<Arc<str>, HashSet<Arc<str>>>, // event_name -> plugin_names
+    /// 萬用字元訂閱對應的訂閱者集合，派送時需逐一比對
+    wildcards: HashMap<Arc<str>, HashSet<Arc<str>>>,
+    /// 每個訂閱者的訂閱數量上限，`None` 表示不限制
+    max_subscriptions_per_plugin: Option<usize>,
+    /// 整個總線的訂閱數量上限，`None` 表示不限制
+    max_total_subscriptions: Option<usize>,
+    /// 事件名稱對應的已合併、已排序訂閱者列表，訂閱變更時清空
+    /// - 快取持有字串池的引用，因此必須在釋放字串前清空
+    cache: Mutex<HashMap<String, Arc<[Arc<str>]>>>,
+}
+impl EventBus {
+    /// 創建新的事件總線
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// 設定每個訂閱者的訂閱數量上限
+    /// - 只影響之後的訂閱，已超出上限的訂閱者保留原有的訂閱
+    /// - `max`: 上限，`None` 表示不限制
+    pub fn set_max_subscriptions_per_plugin(&mut self, max: Option<usize>) {
+        self.max_subscriptions_per_plugin = max;
+    }
+    /// 設定整個總線的訂閱數量上限
+    /// - 只影響之後的訂閱，已超出上限時保留原有的訂閱
+    /// - `max`: 上限，`None` 表示不限制
+    pub fn set_max_total_subscriptions(&mut self, max: Option<usize>) {
+        self.max_total_subscriptions = max;
+    }
+    /// 訂閱事件
+    /// - 重複的訂閱不做任何事，也不計入數量
+    /// - `event`: 要訂閱的事件名稱或萬用字元
+    /// - `plugin`: 訂閱者名稱
+    /// - 返回值: 成功或失敗的結果，超出訂閱數量上限時返回 `EventError`
+    pub fn subscribe(&mut self, event: &str, plugin: &str) -> Result<()> {
+        let table = if event.ends_with('*') {
+            &self.wildcards
+        } else {
+            &self.subscribers
+        };
+        if table.get(event).is_some_and(|names| names.contains(plugin)) {
+            return Ok(());
+        }
+        let current = self.subscription_count(plugin);
+        self.check_limits(plugin, current + 1, self.total_subscriptions() + 1)?;
+        let event = self.intern(event);
+        let plugin = self.intern(plugin);
+        let table = if event.ends_with('*') {
+            &mut self.wildcards
+        } else {
+            &mut self.subscribers
+        };
+        table.entry(event).or_default().insert(plugin);
+        self.invalidate();
+        Ok(())
+    }
+    /// 檢查以一組事件取代訂閱者目前的所有訂閱時是否超出上限，不修改總線
+    /// - 用於在註冊或替換插件前一次檢查，避免訂閱到一半才失敗
+    /// - `plugin`: 訂閱者名稱
+    /// - `events`: 訂閱者的新訂閱，重複的項目只計算一次
+    /// - 返回值: 成功或失敗的結果，超出訂閱數量上限時返回 `EventError`
+    pub fn check_subscriptions(&self, plugin: &str, events: &[String]) -> Result<()> {
+        let requested = events.iter().collect::<HashSet<_>>().len();
+        let total = self.total_subscriptions() - self.subscription_count(plugin) + requested;
+        self.check_limits(plugin, requested, total)
+    }
+    /// 獲取訂閱者目前的訂閱數量，包含萬用字元訂閱
+    /// - `plugin`: 訂閱者名稱
+    pub fn subscription_count(&self, plugin: &str) -> usize {
+        self.iter()
+            .filter(|(_, plugins)| plugins.contains(plugin))
+            .count()
+    }
+    /// 獲取整個總線目前的訂閱數量
+    pub fn total_subscriptions(&self) -> usize {
+        self.iter().map(|(_, plugins)| plugins.len()).sum()
+    }
+    /// 檢查訂閱數量是否在上限內
+    /// - `plugin`: 訂閱者名稱
+    /// - `per_plugin`: 訂閱者的訂閱數量
+    /// - `total`: 整個總線的訂閱數量
+    fn check_limits(&self, plugin: &str, per_plugin: usize, total: usize) -> Result<()> {
+        if let Some(max) = self.max_subscriptions_per_plugin {
+            if per_plugin > max {
+                return Err(PluginError::EventError(format!(
+                    "Plugin {} would have {} subscriptions, limit is {}",
+                    plugin, per_plugin, max
+                )));
+            }
+        }
+        if let Some(max) = self.max_total_subscriptions {
+            if total > max {
+                return Err(PluginError::EventError(format!(
+                    "Subscribing plugin {} would bring the event bus to {} subscriptions, limit is {}",
+                    plugin, total, max
+                )));
+            }
+        }
+        Ok(())
+    }
+    /// 取消訂閱事件
+    /// - `event`: 要取消的事件名稱或萬用字元，需與訂閱時相同
+    /// - `plugin`: 要取消訂閱的訂閱者名稱
+    pub fn unsubscribe(&mut self, event: &str, plugin: &str) {
+        self.invalidate();
+        let table = if event.ends_with('*') {
+            &mut self.wildcards
+        } else {
+            &mut self.subscribers
+        };
+        if let Some(subscribers) = table.get_mut(event) {
+            subscribers.remove(plugin);
+            if subscribers.is_empty() {
+                table.remove(event);
+            }
+        }
+        self.release(event);
+        self.release(plugin);
+    }
+    /// 移除所有訂閱
+    /// - 訂閱數量上限保持不變
+    pub fn clear(&mut self) {
+        self.invalidate();
+        self.subscribers.clear();
+        self.wildcards.clear();
+        self.names.clear();
+    }
+    /// 獲取某事件的所有訂閱者，包含萬用字元訂閱
+    /// - `event`: 事件名稱
+    /// - 返回值: 訂閱此事件的訂閱者名稱列表，依名稱排序且不重複
+    pub fn get_subscribers(&self, event: &str) -> Vec<String> {
+        self.get_subscribers_interned(event)
+            .iter()
+            .map(|name| name.to_string())
+            .collect()
+    }
+    /// 獲取某事件的所有訂閱者，不複製名稱字串
+    /// - 與 [`EventBus::get_subscribers`] 相同，但返回字串池中的共用字串；
+    ///   合併萬用字元訂閱並排序的結果會依事件名稱快取，訂閱變更前重複查詢同一事件不會配置記憶體，
+    ///   適合頻繁派送事件的呼叫者
+    /// - `event`: 事件名稱
+    /// - 返回值: 訂閱此事件的訂閱者名稱列表，依名稱排序且不重複
+    pub fn get_subscribers_interned(&self, event: &str) -> Arc<[Arc<str>]> {
+        let mut cache = self.cache.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(subscribers) = cache.get(event) {
+            return Arc::clone(subscribers);
+        }
+        let exact = self.subscribers.get(event).into_iter().flatten();
+        let wildcard = self
+            .wildcards
+            .iter()
+            .filter(|(pattern, _)| matches_event(pattern, event))
+            .flat_map(|(_, names)| names);
+        let mut subscribers: Vec<Arc<str>> = exact.chain(wildcard).cloned().collect();
+        subscribers.sort();
+        subscribers.dedup();
+        let subscribers: Arc<[Arc<str>]> = subscribers.into();
+        if cache.len() >= SUBSCRIBER_CACHE_CAPACITY {
+            cache.clear();
+        }
+        cache.insert(event.to_string(), Arc::clone(&subscribers));
+        subscribers
+    }
+    /// 獲取某訂閱者訂閱的所有事件
+    /// - `plugin`: 訂閱者名稱
+    /// - 返回值: 此訂閱者訂閱的事件名稱或萬用字元列表，依名稱排序
+    pub fn get_subscriptions(&self, plugin: &str) -> Vec<String> {
+        let mut events: Vec<String> = self
+            .iter()
+            .filter(|(_, plugins)| plugins.contains(plugin))
+            .map(|(event, _)| event.to_string())
+            .collect();
+        events.sort();
+        events
+    }
+    /// 遍歷所有訂閱
+    /// - 返回值: (事件名稱或萬用字元, 訂閱者集合) 的迭代器，順序不固定
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &HashSet<Arc<str>>)> {
+        self.subscribers
+            .iter()
+            .chain(&self.wildcards)
+            .map(|(event, names)| (&**event, names))
+    }
+    /// 清空訂閱者查詢快取
+    fn invalidate(&mut self) {
+        self.cache
+            .get_mut()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
+    }
+    /// 字串是否仍在字串池中
+    #[cfg(test)]
+    pub(crate) fn is_interned(&self, name: &str) -> bool {
+        self.names.contains(name)
+    }
+    /// 從字串池取得共用字串，不存在時加入
+    /// - `name`: 事件名稱或訂閱者名稱
+    fn intern(&mut self, name: &str) -> Arc<str> {
+        if let Some(interned) = self.names.get(name) {
+            return Arc::clone(interned);
+        }
+        let interned: Arc<str> = Arc::from(name);
+        self.names.insert(Arc::clone(&interned));
+        interned
+    }
+    /// 字串已沒有任何訂閱使用時從字串池移除
+    /// - `name`: 事件名稱或訂閱者名稱
+    fn release(&mut self, name: &str) {
+        if self
+            .names
+            .get(name)
+            .is_some_and(|interned| Arc::strong_count(interned) == 1)
+        {
+            self.names.remove(name);
+        }
+    }
+}
+
+/// 檢查訂閱的事件名稱或萬用字元是否匹配事件
+/// - `pattern`: 訂閱時的事件名稱或萬用字元
+/// - `event`: 事件名稱
+pub(crate) fn matches_event(pattern: &str, event: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => event.starts_with(prefix),
+        None => pattern == event,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_util::{event, Fixture};
+    use super::*;
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::cell::Cell;
+
+    /// 記錄目前執行緒配置次數的配置器，其他執行緒上的測試不影響計數
+    struct CountingAllocator;
+    thread_local! {
+        static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+    }
+    unsafe impl GlobalAlloc for CountingAllocator {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+            System.alloc(layout)
+        }
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            System.dealloc(ptr, layout)
+        }
+    }
+    #[global_allocator]
+    static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+    fn allocations() -> usize {
+        ALLOCATIONS.with(Cell::get)
+    }
+
+    #[test]
+    fn standalone_bus_routes_exact_and_wildcard_subscriptions() {
+        let mut bus = EventBus::new();
+        bus.subscribe("user.login", "audit").unwrap();
+        bus.subscribe("user.login", "audit").unwrap();
+        bus.subscribe("user.*", "metrics").unwrap();
+        bus.subscribe("*", "logger").unwrap();
+        assert_eq!(bus.total_subscriptions(), 3);
+        assert_eq!(
+            bus.get_subscribers("user.login"),
+            ["audit", "logger", "metrics"]
+        );
+        assert_eq!(bus.get_subscribers("user.logout"), ["logger", "metrics"]);
+        assert_eq!(bus.get_subscribers("system.boot"), ["logger"]);
+        assert_eq!(bus.get_subscriptions("metrics"), ["user.*"]);
+        assert!(bus.get_subscriptions("nobody").is_empty());
+    }
+
+    #[test]
+    fn unsubscribing_compacts_empty_entries() {
+        let mut bus = EventBus::new();
+        bus.subscribe("tick", "alpha").unwrap();
+        bus.subscribe("tick*", "beta").unwrap();
+        bus.unsubscribe("tick", "alpha");
+        bus.unsubscribe("tick*", "beta");
+        // 未訂閱過的項目不做任何事
+        bus.unsubscribe("tock", "gamma");
+        assert_eq!(bus.iter().count(), 0);
+        assert!(bus.get_subscribers("tick").is_empty());
+        assert!(!bus.is_interned("tick"));
+        assert!(!bus.is_interned("alpha"));
+    }
+
+    #[test]
+    fn wildcard_patterns_match_prefixes() {
+        assert!(matches_event("user.*", "user.login"));
+        assert!(matches_event("*", "anything"));
+        assert!(matches_event("tick", "tick"));
+        assert!(!matches_event("tick", "ticks"));
+        assert!(!matches_event("user.*", "admin.login"));
+    }
+
+    #[test]
+    fn repeated_lookups_do_not_allocate() {
+        let mut bus = EventBus::new();
+        bus.subscribe("user.login", "audit").unwrap();
+        bus.subscribe("user.*", "metrics").unwrap();
+        bus.subscribe("*", "audit").unwrap();
+        let first = bus.get_subscribers_interned("user.login");
+        assert_eq!(&*first, [Arc::from("audit"), Arc::from("metrics")]);
+
+        let before = allocations();
+        for _ in 0..100 {
+            let subscribers = bus.get_subscribers_interned("user.login");
+            assert_eq!(subscribers.len(), 2);
+        }
+        assert_eq!(allocations(), before);
+
+        // 透過管理器派送時重用快取的派送順序
+        let fixture = Fixture::new();
+        fixture.add(&fixture.plugin("alpha").subscribe(&["tick"]));
+        fixture.add(&fixture.plugin("beta").subscribe(&["tick*"]));
+        let mut manager = fixture.manager();
+        manager.load_all_plugins().unwrap();
+        // 假插件處理事件時會記錄呼叫，因此以禁用的訂閱者量測派送本身
+        manager.disable_plugin("alpha").unwrap();
+        manager.disable_plugin("beta").unwrap();
+        let mut events: Vec<_> = (0..101).map(|_| event("tick")).collect();
+        manager.broadcast_event(events.pop().unwrap()).unwrap();
+
+        let before = allocations();
+        for event in events {
+            manager.broadcast_event(event).unwrap();
+        }
+        assert_eq!(allocations(), before);
+    }
+
+    #[test]
+    fn subscription_changes_invalidate_cached_lookups() {
+        let mut bus = EventBus::new();
+        bus.subscribe("user.login", "audit").unwrap();
+        assert_eq!(bus.get_subscribers("user.login"), ["audit"]);
+        bus.subscribe("user.*", "metrics").unwrap();
+        assert_eq!(bus.get_subscribers("user.login"), ["audit", "metrics"]);
+        bus.unsubscribe("user.login", "audit");
+        assert_eq!(bus.get_subscribers("user.login"), ["metrics"]);
+        bus.clear();
+        assert!(bus.get_subscribers("user.login").is_empty());
+    }
+
+    #[test]
+    fn unsubscribing_releases_names_held_by_cached_lookups() {
+        let mut bus = EventBus::new();
+        bus.subscribe("tick", "alpha").unwrap();
+        let _ = bus.get_subscribers_interned("tick");
+        bus.unsubscribe("tick", "alpha");
+        assert!(!bus.is_interned("tick"));
+        assert!(!bus.is_interned("alpha"));
+    }
+
+    #[test]
+    fn replacement_checks_exclude_the_current_subscriptions() {
+        let mut bus = EventBus::new();
+        bus.set_max_subscriptions_per_plugin(Some(2));
+        bus.set_max_total_subscriptions(Some(3));
+        bus.subscribe("a", "alpha").unwrap();
+        bus.subscribe("b", "alpha").unwrap();
+        bus.subscribe("a", "beta").unwrap();
+        // 重複的項目只計算一次，被取代的訂閱不計入總數
+        let events = ["c".to_string(), "d".to_string(), "c".to_string()];
+        bus.check_subscriptions("alpha", &events).unwrap();
+        let err = bus
+            .check_subscriptions("alpha", &["c".into(), "d".into(), "e".into()])
+            .unwrap_err();
+        assert!(err.to_string().contains("limit is 2"), "{}", err);
+        let err = bus
+            .check_subscriptions("beta", &["b".into(), "c".into()])
+            .unwrap_err();
+        assert!(err.to_string().contains("limit is 3"), "{}", err);
+        assert_eq!(bus.total_subscriptions(), 3);
+    }
+
+    #[test]
+    fn clear_keeps_subscription_limits() {
+        let mut bus = EventBus::new();
+        bus.set_max_subscriptions_per_plugin(Some(2));
+        bus.set_max_total_subscriptions(Some(3));
+        bus.subscribe("a", "alpha").unwrap();
+        bus.subscribe("b.*", "alpha").unwrap();
+        bus.clear();
+        assert_eq!(bus.total_subscriptions(), 0);
+        assert!(bus.get_subscribers("b.x").is_empty());
+
+        bus.subscribe("a", "alpha").unwrap();
+        bus.subscribe("b", "alpha").unwrap();
+        assert!(matches!(
+            bus.subscribe("c", "alpha"),
+            Err(PluginError::EventError(_))
+        ));
+        bus.subscribe("a", "beta").unwrap();
+        assert!(matches!(
+            bus.subscribe("b", "beta"),
+            Err(PluginError::EventError(_))
+        ));
+        assert_eq!(bus.total_subscriptions(), 3);
+    }
+}
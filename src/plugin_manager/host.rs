@@ -0,0 +1,350 @@
+//! 插件存取管理器的宿主句柄
+//!
+//! 插件透過匯出可選的 `plugin_set_host` 符號 (`fn(Arc<dyn HostHandle>)`) 取得句柄，
+//! 管理器會在調用 `on_load` 之前傳入。
+//!
+//! 執行緒與借用模型:
+//! - 句柄不持有管理器的借用，只共享一份以 `Mutex` 保護的狀態，因此可以保存起來，
+//!   也可以在其他執行緒上使用
+//! - [`HostHandle::broadcast`] 只會把事件放入待處理佇列並立即返回，不會在呼叫端的堆疊上
+//!   派送事件；即使在 `handle_event` 中呼叫也不會重入管理器或造成死結
+//! - 待處理的事件會在管理器的執行緒上，於目前的 `broadcast_event` 結束前或調用
+//!   [`PluginManager::process_host_events`] 時派送
+//! - 佇列中同時有多個待處理事件時 (例如回應事件、宿主句柄發送的事件或同時到期的延遲事件)，
+//!   依 `Event.priority` 由高到低派送，相同優先級依加入佇列的順序派送；
+//!   `priority` 不影響單一事件的訂閱者順序，訂閱者順序由插件的優先級決定
+//! - [`HostHandle::is_plugin_loaded`] 讀取的是管理器維護的已載入名稱集合，
+//!   反映最近一次載入或卸載完成後的狀態
+//! - 每個插件取得的句柄各自獨立，[`HostHandle::heartbeat`] 只會更新持有該句柄的插件，
+//!   可在背景執行緒上定期調用
+//! - [`HostHandle::log`] 會以插件的註冊名稱標記訊息並交給 `log` crate 輸出，
+//!   設定 [`PluginManager::set_plugin_log_capacity`] 後也會保留在每個插件的緩衝區中
+use chm_core_define::plugin_define::Event;
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Instant, SystemTime};
+
+/// 插件日誌在 `log` crate 中使用的 target
+pub const PLUGIN_LOG_TARGET: &str = "main_loader::plugin";
+
+/// 插件透過 [`HostHandle::log`] 記錄的日誌
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogRecord {
+    /// 記錄日誌的插件註冊名稱
+    pub plugin: String,
+    /// 日誌等級
+    pub level: log::Level,
+    /// 訊息內容
+    pub message: String,
+    /// 記錄的時間
+    pub time: SystemTime,
+}
+
+/// 插件可使用的宿主功能
+///
+/// 具名通道:
+/// - 由宿主以 [`PluginManager::create_channel`] 建立，插件以 [`HostHandle::sender`] 與
+///   [`HostHandle::receiver`] 取得端點，直接交換事件而不經過全域廣播
+/// - 傳送端可取得多次 (多個生產者)，接收端只能取得一次 (單一消費者)
+/// - 管理器擁有通道的登記，取得過任一端點的插件卸載 (含重新載入或替換時釋放的舊實例) 時，
+///   通道會從登記中移除，之後無法再取得端點
+/// - 已取得的端點由插件持有，隨插件實例釋放；接收端在所有傳送端釋放後收到斷線，
+///   傳送端在接收端釋放後發送失敗
+pub trait HostHandle: Send + Sync {
+    /// 發送事件，事件會在稍後由管理器派送給所有訂閱者
+    /// - `event`: 要發送的事件
+    fn broadcast(&self, event: Event);
+    /// 檢查插件是否已載入
+    /// - `name`: 插件的註冊名稱
+    /// - 返回值: 插件是否已載入
+    fn is_plugin_loaded(&self, name: &str) -> bool;
+    /// 回報插件仍在正常運作，管理器會記錄調用時間
+    fn heartbeat(&self);
+    /// 取得具名通道的傳送端
+    /// - `channel`: 通道名稱
+    /// - 返回值: 傳送端，通道不存在時返回 `None`
+    fn sender(&self, channel: &str) -> Option<Sender<Event>>;
+    /// 取得具名通道的接收端
+    /// - 每個通道的接收端只能取得一次
+    /// - `channel`: 通道名稱
+    /// - 返回值: 接收端，通道不存在或接收端已被取得時返回 `None`
+    fn receiver(&self, channel: &str) -> Option<Receiver<Event>>;
+    /// 記錄日誌，取代直接輸出到宿主的標準輸出
+    /// - 訊息會以 `[插件名稱] 訊息` 的格式透過 `log` crate 輸出 (target 為 [`PLUGIN_LOG_TARGET`])
+    /// - 啟用日誌緩衝區時也會保留在該插件的緩衝區中，可用 [`PluginManager::plugin_logs`] 讀取
+    /// - `level`: 日誌等級
+    /// - `message`: 訊息內容
+    fn log(&self, level: log::Level, message: &str);
+}
+
+/// 待派送的事件
+#[derive(Debug)]
+struct PendingEvent {
+    /// 加入佇列的流水號，相同優先級時先加入的先派送
+    seq: u64,
+    /// 事件內容
+    event: Event,
+}
+impl PartialEq for PendingEvent {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+impl Eq for PendingEvent {}
+impl PartialOrd for PendingEvent {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl Ord for PendingEvent {
+    /// 優先級較高或流水號較小的事件較大，會先從堆積中取出
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.event
+            .priority
+            .cmp(&other.event.priority)
+            .then_with(|| other.seq.cmp(&self.seq))
+    }
+}
+
+/// 具名通道
+#[derive(Debug)]
+struct Channel {
+    /// 傳送端，取得時複製
+    sender: Sender<Event>,
+    /// 尚未被取得的接收端
+    receiver: Option<Receiver<Event>>,
+    /// 取得過端點的句柄識別碼
+    endpoints: HashSet<u64>,
+}
+
+/// 管理器與宿主句柄共享的狀態
+#[derive(Debug, Default)]
+struct HostState {
+    /// 待派送的事件
+    pending: BinaryHeap<PendingEvent>,
+    /// 下一個待派送事件的流水號
+    next_seq: u64,
+    /// 已載入插件的註冊名稱
+    loaded: HashSet<String>,
+    /// 下一個句柄的識別碼
+    next_client: u64,
+    /// 每個句柄最近一次回報心跳的時間
+    heartbeats: HashMap<u64, Instant>,
+    /// 具名通道
+    channels: HashMap<String, Channel>,
+    /// 每個句柄所屬插件的註冊名稱
+    clients: HashMap<u64, String>,
+    /// 每個插件保留的日誌數量上限，為 0 時不保留
+    log_capacity: usize,
+    /// 每個插件最近的日誌，鍵為插件的註冊名稱
+    logs: HashMap<String, VecDeque<LogRecord>>,
+}
+
+/// [`HostHandle`] 的實作，由管理器持有並複製給插件
+#[derive(Debug, Clone, Default)]
+pub(crate) struct SharedHost {
+    state: Arc<Mutex<HostState>>,
+}
+impl SharedHost {
+    /// 取得共享狀態的鎖
+    /// - 鎖只在單一操作期間持有，插件因 panic 而污染的鎖仍可繼續使用
+    fn lock(&self) -> MutexGuard<'_, HostState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+    /// 記錄已載入的插件
+    pub(crate) fn mark_loaded(&self, name: &str) {
+        self.lock().loaded.insert(name.to_string());
+    }
+    /// 移除已卸載的插件
+    pub(crate) fn mark_unloaded(&self, name: &str) {
+        self.lock().loaded.remove(name);
+    }
+    /// 取出下一個待派送的事件
+    /// - 返回值: 優先級最高的事件，相同優先級時為最早加入的事件
+    pub(crate) fn pop_pending(&self) -> Option<Event> {
+        self.lock().pending.pop().map(|pending| pending.event)
+    }
+    /// 分配新的句柄識別碼
+    /// - `name`: 句柄所屬插件的名稱，用於標記日誌
+    pub(crate) fn register_client(&self, name: &str) -> u64 {
+        let mut state = self.lock();
+        let client = state.next_client;
+        state.next_client += 1;
+        state.clients.insert(client, name.to_string());
+        client
+    }
+    /// 更新句柄所屬插件的名稱，例如插件以別名或在替換後註冊時
+    pub(crate) fn bind_client(&self, client: u64, name: &str) {
+        self.lock().clients.insert(client, name.to_string());
+    }
+    /// 建立交給插件的句柄
+    /// - `client`: 由 [`SharedHost::register_client`] 分配的識別碼
+    pub(crate) fn handle_for(&self, client: u64) -> Arc<dyn HostHandle> {
+        Arc::new(PluginHost {
+            shared: self.clone(),
+            client,
+        })
+    }
+    /// 獲取句柄最近一次回報心跳的時間
+    pub(crate) fn last_heartbeat(&self, client: u64) -> Option<Instant> {
+        self.lock().heartbeats.get(&client).copied()
+    }
+    /// 移除句柄的心跳記錄與名稱，並關閉它取得過端點的通道
+    pub(crate) fn forget_client(&self, client: u64) {
+        let mut state = self.lock();
+        state.heartbeats.remove(&client);
+        state.clients.remove(&client);
+        state
+            .channels
+            .retain(|_, channel| !channel.endpoints.contains(&client));
+    }
+    /// 設定每個插件保留的日誌數量上限
+    /// - 超出新上限的舊日誌會被捨棄，為 0 時清除所有日誌
+    pub(crate) fn set_log_capacity(&self, capacity: usize) {
+        let mut state = self.lock();
+        state.log_capacity = capacity;
+        state.logs.retain(|_, records| {
+            while records.len() > capacity {
+                records.pop_front();
+            }
+            !records.is_empty()
+        });
+    }
+    /// 獲取插件保留的日誌
+    /// - 返回值: 依記錄順序排列的日誌
+    pub(crate) fn logs(&self, name: &str) -> Vec<LogRecord> {
+        self.lock()
+            .logs
+            .get(name)
+            .map(|records| records.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+    /// 清除插件保留的日誌
+    pub(crate) fn clear_logs(&self, name: &str) {
+        self.lock().logs.remove(name);
+    }
+    /// 以句柄所屬插件的名稱記錄日誌
+    /// - 句柄已被移除 (插件已卸載) 時只透過 `log` crate 輸出，不保留
+    fn log_for(&self, client: u64, level: log::Level, message: &str) {
+        let mut state = self.lock();
+        let Some(plugin) = state.clients.get(&client).cloned() else {
+            log::log!(target: PLUGIN_LOG_TARGET, level, "[unloaded plugin] {}", message);
+            return;
+        };
+        log::log!(target: PLUGIN_LOG_TARGET, level, "[{}] {}", plugin, message);
+        let capacity = state.log_capacity;
+        if capacity == 0 {
+            return;
+        }
+        let records = state.logs.entry(plugin.clone()).or_default();
+        if records.len() >= capacity {
+            records.pop_front();
+        }
+        records.push_back(LogRecord {
+            plugin,
+            level,
+            message: message.to_string(),
+            time: SystemTime::now(),
+        });
+    }
+    /// 建立具名通道
+    /// - 返回值: 是否建立，同名通道已存在時返回 `false`
+    pub(crate) fn create_channel(&self, name: &str) -> bool {
+        let mut state = self.lock();
+        if state.channels.contains_key(name) {
+            return false;
+        }
+        let (sender, receiver) = mpsc::channel();
+        state.channels.insert(
+            name.to_string(),
+            Channel {
+                sender,
+                receiver: Some(receiver),
+                endpoints: HashSet::new(),
+            },
+        );
+        true
+    }
+    /// 關閉具名通道
+    /// - 返回值: 通道是否存在
+    pub(crate) fn close_channel(&self, name: &str) -> bool {
+        self.lock().channels.remove(name).is_some()
+    }
+    /// 獲取所有具名通道的名稱
+    pub(crate) fn channel_names(&self) -> Vec<String> {
+        self.lock().channels.keys().cloned().collect()
+    }
+    /// 取得通道的傳送端
+    /// - `client`: 取得端點的句柄識別碼，管理器自身取得時為 `None`
+    fn take_sender(&self, channel: &str, client: Option<u64>) -> Option<Sender<Event>> {
+        let mut state = self.lock();
+        let channel = state.channels.get_mut(channel)?;
+        channel.endpoints.extend(client);
+        Some(channel.sender.clone())
+    }
+    /// 取得通道的接收端
+    /// - `client`: 取得端點的句柄識別碼，管理器自身取得時為 `None`
+    fn take_receiver(&self, channel: &str, client: Option<u64>) -> Option<Receiver<Event>> {
+        let mut state = self.lock();
+        let channel = state.channels.get_mut(channel)?;
+        let receiver = channel.receiver.take()?;
+        channel.endpoints.extend(client);
+        Some(receiver)
+    }
+}
+impl HostHandle for SharedHost {
+    fn broadcast(&self, event: Event) {
+        let mut state = self.lock();
+        let seq = state.next_seq;
+        state.next_seq += 1;
+        state.pending.push(PendingEvent { seq, event });
+    }
+    fn is_plugin_loaded(&self, name: &str) -> bool {
+        self.lock().loaded.contains(name)
+    }
+    /// 管理器自身的句柄不屬於任何插件，不記錄心跳
+    fn heartbeat(&self) {}
+    fn sender(&self, channel: &str) -> Option<Sender<Event>> {
+        self.take_sender(channel, None)
+    }
+    fn receiver(&self, channel: &str) -> Option<Receiver<Event>> {
+        self.take_receiver(channel, None)
+    }
+    /// 管理器自身的句柄不屬於任何插件，只透過 `log` crate 輸出
+    fn log(&self, level: log::Level, message: &str) {
+        log::log!(target: PLUGIN_LOG_TARGET, level, "{}", message);
+    }
+}
+
+/// 交給單一插件的句柄
+struct PluginHost {
+    /// 共享的宿主狀態
+    shared: SharedHost,
+    /// 句柄的識別碼
+    client: u64,
+}
+impl HostHandle for PluginHost {
+    fn broadcast(&self, event: Event) {
+        self.shared.broadcast(event);
+    }
+    fn is_plugin_loaded(&self, name: &str) -> bool {
+        self.shared.is_plugin_loaded(name)
+    }
+    fn heartbeat(&self) {
+        self.shared
+            .lock()
+            .heartbeats
+            .insert(self.client, Instant::now());
+    }
+    fn sender(&self, channel: &str) -> Option<Sender<Event>> {
+        self.shared.take_sender(channel, Some(self.client))
+    }
+    fn receiver(&self, channel: &str) -> Option<Receiver<Event>> {
+        self.shared.take_receiver(channel, Some(self.client))
+    }
+    fn log(&self, level: log::Level, message: &str) {
+        self.shared.log_for(self.client, level, message);
+    }
+}
@@ -0,0 +1,193 @@
+//! 插件動態庫的載入後端
+//!
+//! 管理器透過 [`PluginLoader`] 開啟插件檔案，並從返回的 [`LoadedLibrary`] 讀取
+//! `create_plugin`、`unload_plugin` 等匯出符號。預設的 [`LibloadingLoader`] 以 `libloading`
+//! 開啟作業系統的動態庫；其他後端 (例如測試用、直接提供行程內插件的登記表)
+//! 可實作 [`PluginLoader`] 與 [`LibraryHandle`]，並以 [`PluginManager::set_loader`] 設定。
+use super::{binary_arch, incompatible_plugin_error, PluginManager};
+use chm_core_define::{PluginError, Result};
+use libloading::Library;
+use std::path::Path;
+use std::sync::Arc;
+
+/// 插件檔案的載入後端
+/// - 可能在 `spawn_blocking` 的阻塞執行緒上調用，因此必須是 `Send + Sync`
+pub trait PluginLoader: Send + Sync {
+    /// 開啟插件檔案
+    /// - 只開啟檔案並提供匯出符號，不創建插件實例
+    /// - `path`: 插件檔案的路徑
+    /// - 返回值: 已開啟的動態庫，失敗時返回 `LoadError`
+    fn load(&self, path: &Path) -> Result<LoadedLibrary>;
+}
+
+/// 已開啟動態庫的匯出符號來源
+/// - 同一動態庫的多個插件以 `Arc` 共用句柄，因此必須是 `Send + Sync`
+pub trait LibraryHandle: Send + Sync {
+    /// 查詢匯出符號的位址
+    /// - 管理器讀取的符號都是函數，位址會被轉為對應的函數指針型別
+    /// - `symbol`: 符號名稱
+    /// - 返回值: 符號的位址，符號不存在時返回 `None`
+    fn symbol(&self, symbol: &[u8]) -> Option<*const ()>;
+    /// 底層的 `libloading` 動態庫，供 [`PluginManager::with_library`] 使用
+    /// - 返回值: 不是以 `libloading` 開啟時返回 `None`
+    fn as_library(&self) -> Option<&Library> {
+        None
+    }
+}
+impl LibraryHandle for Library {
+    fn symbol(&self, symbol: &[u8]) -> Option<*const ()> {
+        // 以指針型別讀取時，符號的值即為其位址
+        unsafe { self.get::<*const ()>(symbol).ok().map(|s| *s) }
+    }
+    fn as_library(&self) -> Option<&Library> {
+        Some(self)
+    }
+}
+
+/// 由 [`PluginLoader`] 開啟的動態庫
+/// - 插件實例與從符號取得的函數指針都不能比它活得更久，管理器會在釋放所有實例後才釋放它
+pub struct LoadedLibrary {
+    /// 匯出符號的來源
+    handle: Box<dyn LibraryHandle>,
+}
+impl std::fmt::Debug for LoadedLibrary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LoadedLibrary")
+            .field("library", &self.handle.as_library())
+            .finish_non_exhaustive()
+    }
+}
+impl From<Library> for LoadedLibrary {
+    fn from(library: Library) -> Self {
+        Self::new(library)
+    }
+}
+impl LoadedLibrary {
+    /// 以自訂的符號來源建立動態庫
+    /// - `handle`: 匯出符號的來源
+    pub fn new<H: LibraryHandle + 'static>(handle: H) -> Self {
+        Self {
+            handle: Box::new(handle),
+        }
+    }
+    /// 讀取匯出的函數
+    /// - `symbol`: 符號名稱
+    /// - 返回值: 符號的值，符號不存在時返回 `None`
+    ///
+    /// # Safety
+    /// 呼叫者必須確保 `T` 是與符號實際簽名一致的函數指針型別
+    pub unsafe fn get<T: Copy>(&self, symbol: &[u8]) -> Option<T> {
+        assert_eq!(
+            std::mem::size_of::<T>(),
+            std::mem::size_of::<*const ()>(),
+            "symbols can only be read as function pointers"
+        );
+        let address = self.handle.symbol(symbol)?;
+        Some(std::mem::transmute_copy::<*const (), T>(&address))
+    }
+    /// 底層的 `libloading` 動態庫
+    /// - 返回值: 不是以 `libloading` 開啟時返回 `None`
+    pub fn as_library(&self) -> Option<&Library> {
+        self.handle.as_library()
+    }
+}
+
+/// 預設的載入後端，以 `libloading` 開啟作業系統的動態庫
+#[derive(Debug, Default, Clone, Copy)]
+pub struct LibloadingLoader;
+impl PluginLoader for LibloadingLoader {
+    fn load(&self, path: &Path) -> Result<LoadedLibrary> {
+        // 目錄與特殊檔案交給 `Library::new` 只會得到難以理解的錯誤，先行拒絕
+        if let Ok(metadata) = path.metadata() {
+            if metadata.is_dir() {
+                return Err(PluginError::LoadError(format!(
+                    "Plugin path {:?} is a directory",
+                    path
+                )));
+            }
+            if !metadata.is_file() {
+                return Err(PluginError::LoadError(format!(
+                    "Plugin path {:?} is not a regular file",
+                    path
+                )));
+            }
+        }
+        // 為其他架構編譯的動態庫在 `Library::new` 只會得到平台相關的錯誤，先行以檔頭判斷
+        if let Some(arch) = binary_arch::binary_arch(path) {
+            if arch != std::env::consts::ARCH {
+                return Err(incompatible_plugin_error(format!(
+                    "{:?} is built for {}, host is {}",
+                    path,
+                    arch,
+                    std::env::consts::ARCH
+                )));
+            }
+        }
+        let library = unsafe { Library::new(path) }
+            .map_err(|e| PluginError::LoadError(format!("Failed to load library: {}", e)))?;
+        Ok(LoadedLibrary::from(library))
+    }
+}
+
+impl PluginManager {
+    /// 設定插件檔案的載入後端
+    /// - 只影響之後開啟的插件，已載入的插件繼續使用原本的動態庫
+    /// - `loader`: 載入後端，預設為 [`LibloadingLoader`]
+    pub fn set_loader(&mut self, loader: Box<dyn PluginLoader>) {
+        self.loader = Arc::from(loader);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_util::{event, FakeLibrary, Fixture};
+    use super::super::PluginState;
+    use super::*;
+
+    /// 拒絕所有檔案的載入後端
+    struct RejectingLoader;
+    impl PluginLoader for RejectingLoader {
+        fn load(&self, path: &Path) -> Result<LoadedLibrary> {
+            Err(PluginError::LoadError(format!("rejected {:?}", path)))
+        }
+    }
+
+    #[test]
+    fn mock_loader_drives_the_full_lifecycle_without_dynamic_libraries() {
+        let fixture = Fixture::new();
+        let path = fixture.add(&fixture.plugin("alpha").subscribe(&["tick"]));
+        let library = fixture.loader.load(&path).unwrap();
+        assert!(library.as_library().is_none());
+        assert!(unsafe { library.get::<fn()>(b"missing_symbol") }.is_none());
+
+        let mut manager = fixture.manager();
+        manager.load_all_plugins().unwrap();
+        manager.broadcast_event(event("tick")).unwrap();
+        manager.unload_plugin("alpha").unwrap();
+        assert_eq!(
+            fixture.log.calls(),
+            [
+                "alpha:on_load",
+                "alpha:on_enable",
+                "alpha:handle_event:tick",
+                "alpha:on_disable",
+                "alpha:on_unload",
+            ]
+        );
+    }
+
+    #[test]
+    fn replaced_loader_only_affects_later_loads() {
+        let fixture = Fixture::new();
+        fixture.add(&fixture.plugin("alpha"));
+        let beta = fixture.install("beta.so", FakeLibrary::new(&fixture.plugin("beta")));
+        let mut manager = fixture.manager();
+        manager.load_plugin(&fixture.dir.join("alpha.so")).unwrap();
+
+        manager.set_loader(Box::new(RejectingLoader));
+        let err = manager.load_plugin(&beta).unwrap_err();
+        assert!(matches!(err, PluginError::LoadError(msg) if msg.contains("rejected")));
+        assert_eq!(manager.state_of("alpha"), Some(&PluginState::Enabled));
+        assert_eq!(manager.plugin_names(), ["alpha"]);
+    }
+}
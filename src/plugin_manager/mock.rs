@@ -0,0 +1,113 @@
+//! 不經動態庫註冊插件實例
+//!
+//! 供測試以行程內的假插件驗證事件路由與生命週期，不需要編譯真正的動態庫。
+//! 插件實例由一個沒有匯出符號的 [`LoadedLibrary`] 持有，因此可選的符號 (優先級、依賴、
+//! 健康檢查等) 一律使用預設值。
+use super::{check_instance, BoxedPlugin, LibraryHandle, LoadedLibrary, PluginManager};
+use chm_core_define::{PluginError, Result};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+/// 行程內插件的符號來源
+/// - 只提供不做任何事的 `unload_plugin`，行程內插件沒有動態庫的清理工作
+struct InMemoryLibrary;
+impl LibraryHandle for InMemoryLibrary {
+    fn symbol(&self, symbol: &[u8]) -> Option<*const ()> {
+        fn unload_plugin() {}
+        (symbol == b"unload_plugin").then_some(unload_plugin as fn() as *const ())
+    }
+}
+
+#[allow(unused)]
+impl PluginManager {
+    /// 註冊已創建的插件實例
+    /// - 與 [`PluginManager::load_plugin`] 相同地檢查中繼資料、建立資料目錄、調用 `on_load`、
+    ///   註冊事件訂閱並依設定自動啟用，只略過開啟動態庫的步驟
+    /// - 插件沒有檔案路徑，無法重新載入或熱重載
+    /// - `plugin`: 插件實例，以 `name()` 註冊
+    /// - 返回值: 成功或失敗的結果，名稱已存在時返回 `LoadError`
+    pub fn register_plugin_instance(&mut self, plugin: BoxedPlugin) -> Result<()> {
+        self.check_frozen(PluginError::LoadError, "load plugins")?;
+        check_instance(plugin.as_ref())?;
+        let name = plugin.name().to_string();
+        if self.plugins.contains_key(&name) {
+            return Err(PluginError::LoadError(format!(
+                "Plugin {} is already loaded",
+                name
+            )));
+        }
+        let library = Arc::new(LoadedLibrary::new(InMemoryLibrary));
+        let entry =
+            self.setup_entry(plugin, &library, Path::new(""), None, None, HashMap::new())?;
+        self.register_entry(name, entry)?;
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "mock"))]
+mod tests {
+    use super::super::test_util::{event, Fixture};
+    use super::super::PluginState;
+    use chm_core_define::PluginError;
+
+    #[test]
+    fn registered_instance_receives_events_and_lifecycle_hooks() {
+        let fixture = Fixture::new();
+        let mut manager = fixture.manager();
+        let plugin = fixture.plugin("alpha").subscribe(&["tick"]);
+        manager
+            .register_plugin_instance(Box::new(plugin.clone()))
+            .unwrap();
+        assert_eq!(manager.state_of("alpha"), Some(&PluginState::Enabled));
+
+        manager.broadcast_event(event("tick")).unwrap();
+        manager.disable_plugin("alpha").unwrap();
+        manager.broadcast_event(event("tick")).unwrap();
+        manager.enable_plugin("alpha").unwrap();
+        manager.unload_plugin("alpha").unwrap();
+        assert_eq!(
+            fixture.log.calls(),
+            [
+                "alpha:on_load",
+                "alpha:on_enable",
+                "alpha:handle_event:tick",
+                "alpha:on_disable",
+                "alpha:on_enable",
+                "alpha:on_disable",
+                "alpha:on_unload",
+            ]
+        );
+        assert!(manager.state_of("alpha").is_none());
+    }
+
+    #[test]
+    fn duplicate_and_invalid_instances_are_rejected() {
+        let fixture = Fixture::new();
+        let mut manager = fixture.manager();
+        manager
+            .register_plugin_instance(Box::new(fixture.plugin("alpha")))
+            .unwrap();
+        let err = manager
+            .register_plugin_instance(Box::new(fixture.plugin("alpha")))
+            .unwrap_err();
+        assert!(matches!(err, PluginError::LoadError(msg) if msg.contains("already loaded")));
+        let err = manager
+            .register_plugin_instance(Box::new(fixture.plugin("beta").version("")))
+            .unwrap_err();
+        assert!(matches!(err, PluginError::LoadError(_)));
+        assert_eq!(fixture.log.count("alpha:on_load"), 1);
+        assert!(manager.state_of("beta").is_none());
+    }
+
+    #[test]
+    fn frozen_manager_rejects_instances() {
+        let fixture = Fixture::new();
+        let mut manager = fixture.manager();
+        manager.freeze();
+        assert!(manager
+            .register_plugin_instance(Box::new(fixture.plugin("alpha")))
+            .is_err());
+        assert!(fixture.log.calls().is_empty());
+    }
+}
@@ -0,0 +1,285 @@
+//! 從網路下載並載入插件
+//!
+//! 遠端插件會以 HTTPS 下載到暫存目錄後，依 [`PluginManager::load_plugin_from_bytes`]
+//! 的方式載入；下載的檔案在插件卸載時刪除。
+//!
+//! # 安全性
+//! 載入插件等同於在宿主行程中以完整權限執行插件的程式碼，從網路載入時任何能影響下載內容的人
+//! (伺服器、被入侵的插件商店、中間人) 都能在宿主上執行任意程式碼。因此:
+//! - 必須先以 [`PluginManager::set_checksum_verifier`] 設定驗證函數，未設定時拒絕載入;
+//!   驗證函數應比對預先取得的雜湊值或簽章，而非只檢查下載是否完整
+//! - 只接受 `https://` 網址，`http://` 只允許本機位址 (`localhost`、`127.0.0.1`、`[::1]`) 用於測試
+//! - 快取的內容每次載入都會重新驗證
+use super::PluginManager;
+use chm_core_define::{PluginError, Result};
+use std::io::Read;
+
+/// 下載插件的大小上限 (位元組)
+pub const MAX_PLUGIN_DOWNLOAD_SIZE: u64 = 256 * 1024 * 1024;
+
+/// 遠端插件的驗證函數，參數為網址與下載的內容，返回 `true` 時才會載入
+#[cfg(not(feature = "send"))]
+pub type ChecksumVerifier = Box<dyn Fn(&str, &[u8]) -> bool>;
+/// 遠端插件的驗證函數，參數為網址與下載的內容，返回 `true` 時才會載入
+#[cfg(feature = "send")]
+pub type ChecksumVerifier = Box<dyn Fn(&str, &[u8]) -> bool + Send>;
+
+/// 已下載的插件內容，用於條件式請求
+#[derive(Debug)]
+pub(crate) struct CachedDownload {
+    /// 伺服器回應的 `ETag`
+    etag: Option<String>,
+    /// 伺服器回應的 `Last-Modified`，沒有 `ETag` 時使用
+    last_modified: Option<String>,
+    /// 下載的內容
+    bytes: Vec<u8>,
+}
+
+#[allow(unused)]
+impl PluginManager {
+    /// 設定遠端插件的驗證函數
+    /// - [`PluginManager::load_plugin_from_url`] 下載 (或使用快取) 後、載入前調用，返回 `false` 時拒絕載入
+    /// - `verifier`: 驗證函數，參數為網址與下載的內容
+    pub fn set_checksum_verifier(&mut self, verifier: ChecksumVerifier) {
+        self.checksum_verifier = Some(verifier);
+    }
+    /// 從網址下載並載入插件
+    /// - 下載的內容寫入暫存目錄後以 [`PluginManager::load_plugin_from_bytes`] 載入，
+    ///   暫存檔案在插件卸載時刪除
+    /// - 同一網址再次載入時會帶上 `If-None-Match` (或 `If-Modified-Since`)，
+    ///   伺服器回應 `304 Not Modified` 時使用快取的內容而不重新下載；驗證失敗時清除此網址的快取
+    /// - 會阻塞目前的執行緒直到下載完成
+    /// - 安全性: 插件會以宿主的完整權限執行，能影響下載內容的人都能在宿主上執行任意程式碼;
+    ///   必須先以 [`PluginManager::set_checksum_verifier`] 設定驗證函數 (應比對預先取得的雜湊值或簽章)，
+    ///   且只接受 `https://` 網址，`http://` 只允許本機位址
+    /// - `url`: 插件的網址
+    /// - 返回值: 成功或失敗的結果，未設定驗證函數、網址不安全、下載失敗或驗證失敗時返回 `LoadError`
+    pub fn load_plugin_from_url(&mut self, url: &str) -> Result<()> {
+        self.check_frozen(PluginError::LoadError, "load plugins")?;
+        if self.checksum_verifier.is_none() {
+            return Err(PluginError::LoadError(format!(
+                "Refusing to load plugin from {}: no checksum verifier is configured",
+                url
+            )));
+        }
+        if !is_allowed_url(url) {
+            return Err(PluginError::LoadError(format!(
+                "Refusing to load plugin from {}: only https URLs are allowed",
+                url
+            )));
+        }
+        let bytes = self.download(url)?;
+        if !self
+            .checksum_verifier
+            .as_ref()
+            .is_some_and(|verify| verify(url, &bytes))
+        {
+            // 不保留未通過驗證的內容，避免之後的 `304` 回應沿用它
+            self.download_cache.remove(url);
+            return Err(PluginError::LoadError(format!(
+                "Plugin downloaded from {} failed verification",
+                url
+            )));
+        }
+        self.load_plugin_from_bytes(&name_hint(url), &bytes)
+    }
+    /// 清除下載快取
+    /// - 之後每次載入都會重新下載
+    pub fn clear_download_cache(&mut self) {
+        self.download_cache.clear();
+    }
+    /// 下載插件並更新快取
+    /// - `url`: 插件的網址
+    /// - 返回值: 下載內容的副本 (載入時需要可變借用管理器)，伺服器回應 `304` 時為快取的內容
+    fn download(&mut self, url: &str) -> Result<Vec<u8>> {
+        let mut request = ureq::get(url);
+        if let Some(cached) = self.download_cache.get(url) {
+            if let Some(etag) = &cached.etag {
+                request = request.set("If-None-Match", etag);
+            } else if let Some(last_modified) = &cached.last_modified {
+                request = request.set("If-Modified-Since", last_modified);
+            }
+        }
+        let response = request.call().map_err(|e| {
+            PluginError::LoadError(format!("Failed to download plugin from {}: {}", url, e))
+        })?;
+        if response.status() == 304 {
+            if let Some(cached) = self.download_cache.get(url) {
+                println!("Plugin at {} is unchanged, using cached download", url);
+                return Ok(cached.bytes.clone());
+            }
+        }
+        let etag = response.header("ETag").map(str::to_string);
+        let last_modified = response.header("Last-Modified").map(str::to_string);
+        let mut bytes = Vec::new();
+        response
+            .into_reader()
+            .take(MAX_PLUGIN_DOWNLOAD_SIZE + 1)
+            .read_to_end(&mut bytes)
+            .map_err(|e| {
+                PluginError::LoadError(format!("Failed to download plugin from {}: {}", url, e))
+            })?;
+        if bytes.len() as u64 > MAX_PLUGIN_DOWNLOAD_SIZE {
+            return Err(PluginError::LoadError(format!(
+                "Plugin at {} is larger than {} bytes",
+                url, MAX_PLUGIN_DOWNLOAD_SIZE
+            )));
+        }
+        self.download_cache.insert(
+            url.to_string(),
+            CachedDownload {
+                etag,
+                last_modified,
+                bytes: bytes.clone(),
+            },
+        );
+        Ok(bytes)
+    }
+}
+
+/// 檢查網址是否允許下載插件
+/// - `url`: 插件的網址
+/// - 返回值: `https://` 網址或本機的 `http://` 網址時為 `true`
+fn is_allowed_url(url: &str) -> bool {
+    if url.starts_with("https://") {
+        return true;
+    }
+    let Some(rest) = url.strip_prefix("http://") else {
+        return false;
+    };
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    let host = match authority.strip_prefix('[') {
+        Some(v6) => v6.split(']').next().unwrap_or_default(),
+        None => authority.split(':').next().unwrap_or_default(),
+    };
+    matches!(host, "localhost" | "127.0.0.1" | "::1")
+}
+
+/// 由網址的最後一段產生暫存檔案名稱的提示
+/// - `url`: 插件的網址
+fn name_hint(url: &str) -> String {
+    let path = url.split(['?', '#']).next().unwrap_or_default();
+    let file_name = path.rsplit('/').next().unwrap_or_default();
+    file_name.split('.').next().unwrap_or_default().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_util::{FakeLibrary, Fixture};
+    use super::super::PluginState;
+    use super::*;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::sync::{Arc, Mutex};
+
+    /// 在本機提供單一檔案的 HTTP 伺服器，回應 `ETag: "v1"`，帶有相符 `If-None-Match` 的請求回應 304
+    /// - 返回值: 檔案的網址與每個請求的回應狀態碼
+    fn serve(body: &'static [u8], requests: usize) -> (String, Arc<Mutex<Vec<u16>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/plugins/alpha.so", listener.local_addr().unwrap());
+        let statuses = Arc::new(Mutex::new(Vec::new()));
+        let served = Arc::clone(&statuses);
+        std::thread::spawn(move || {
+            for stream in listener.incoming().take(requests) {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut cached = false;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if line.trim().is_empty() {
+                        break;
+                    }
+                    cached |= line
+                        .to_ascii_lowercase()
+                        .starts_with("if-none-match: \"v1\"");
+                }
+                let status = if cached { 304 } else { 200 };
+                served.lock().unwrap().push(status);
+                let head = if cached {
+                    "HTTP/1.1 304 Not Modified\r\nETag: \"v1\"\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                        .to_string()
+                } else {
+                    format!(
+                        "HTTP/1.1 200 OK\r\nETag: \"v1\"\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                        body.len()
+                    )
+                };
+                stream.write_all(head.as_bytes()).unwrap();
+                if !cached {
+                    stream.write_all(body).unwrap();
+                }
+            }
+        });
+        (url, statuses)
+    }
+
+    #[test]
+    fn only_https_and_local_http_urls_are_allowed() {
+        assert!(is_allowed_url("https://plugins.example.com/alpha.so"));
+        assert!(is_allowed_url("http://localhost:8080/alpha.so"));
+        assert!(is_allowed_url("http://127.0.0.1/alpha.so"));
+        assert!(is_allowed_url("http://[::1]:80/alpha.so"));
+        assert!(!is_allowed_url("http://plugins.example.com/alpha.so"));
+        assert!(!is_allowed_url("http://localhost.example.com/alpha.so"));
+        assert!(!is_allowed_url("ftp://localhost/alpha.so"));
+        assert_eq!(name_hint("https://example.com/x/alpha.so?v=2"), "alpha");
+    }
+
+    #[test]
+    fn remote_loads_require_a_verifier_and_a_safe_url() {
+        let fixture = Fixture::new();
+        let mut manager = fixture.manager();
+        let err = manager
+            .load_plugin_from_url("https://example.com/alpha.so")
+            .unwrap_err();
+        assert!(matches!(err, PluginError::LoadError(msg) if msg.contains("no checksum verifier")));
+        manager.set_checksum_verifier(Box::new(|_, _| true));
+        let err = manager
+            .load_plugin_from_url("http://example.com/alpha.so")
+            .unwrap_err();
+        assert!(matches!(err, PluginError::LoadError(msg) if msg.contains("only https")));
+    }
+
+    #[test]
+    fn downloads_are_verified_cached_and_removed_on_unload() {
+        let fixture = Fixture::new();
+        fixture
+            .loader
+            .register("remote alpha", FakeLibrary::new(&fixture.plugin("alpha")));
+        let (url, statuses) = serve(b"remote alpha", 2);
+        let mut manager = fixture.manager();
+        let verified = Arc::new(Mutex::new(Vec::new()));
+        let seen = Arc::clone(&verified);
+        manager.set_checksum_verifier(Box::new(move |_, bytes| {
+            seen.lock().unwrap().push(bytes.to_vec());
+            bytes == b"remote alpha"
+        }));
+
+        manager.load_plugin_from_url(&url).unwrap();
+        assert_eq!(manager.state_of("alpha"), Some(&PluginState::Enabled));
+        manager.unload_plugin("alpha").unwrap();
+        assert_eq!(
+            std::fs::read_dir(fixture.dir.join(".tmp")).unwrap().count(),
+            0
+        );
+
+        // 再次載入時伺服器回應 304，使用快取的內容並重新驗證
+        manager.load_plugin_from_url(&url).unwrap();
+        assert_eq!(*statuses.lock().unwrap(), [200, 304]);
+        assert_eq!(verified.lock().unwrap().len(), 2);
+        assert_eq!(manager.state_of("alpha"), Some(&PluginState::Enabled));
+    }
+
+    #[test]
+    fn failed_verification_rejects_the_download() {
+        let fixture = Fixture::new();
+        let (url, _) = serve(b"tampered", 1);
+        let mut manager = fixture.manager();
+        manager.set_checksum_verifier(Box::new(|_, bytes| bytes == b"expected"));
+        let err = manager.load_plugin_from_url(&url).unwrap_err();
+        assert!(matches!(err, PluginError::LoadError(msg) if msg.contains("failed verification")));
+        assert!(manager.plugin_names().is_empty());
+        assert!(manager.download_cache.is_empty());
+    }
+}
@@ -0,0 +1,192 @@
+//! 收到 SIGHUP 時重新載入插件
+//!
+//! 依 Unix 常駐程式的慣例，SIGHUP 表示重新載入設定與插件。訊號處理函數只設定旗標
+//! (或寫入 `signal-hook` 的內部管道)，實際的重新載入一律在一般執行緒上執行:
+//! - 同時啟用 `send` 功能時，[`install_sighup_reload`] 會啟動背景執行緒，在收到訊號後鎖定
+//!   共享的管理器並調用 [`PluginManager::reload_all_plugins`]
+//! - 未啟用 `send` 功能時插件實例不保證 `Send`，管理器不能交給背景執行緒，
+//!   改用 [`install_sighup_flag`] 取得旗標，由持有管理器的執行緒 (例如主迴圈) 定期調用
+//!   [`SighupReload::reload_if_requested`]
+//!
+//! Windows 等非 Unix 平台沒有 SIGHUP，兩個函數都不註冊任何處理函數；
+//! 可改由其他機制 (例如管理指令) 調用 [`SighupReload::request`] 或直接重新載入。
+use super::{LoadReport, PluginManager};
+use chm_core_define::Result;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+#[cfg(feature = "send")]
+use std::sync::Mutex;
+
+/// SIGHUP 重新載入的請求旗標
+#[derive(Debug, Clone, Default)]
+pub struct SighupReload {
+    /// 收到訊號後設為 `true`，重新載入時清除
+    requested: Arc<AtomicBool>,
+}
+impl SighupReload {
+    /// 手動請求重新載入，效果與收到 SIGHUP 相同
+    /// - 非 Unix 平台沒有 SIGHUP，可由其他機制 (例如管理指令) 調用此函數
+    pub fn request(&self) {
+        self.requested.store(true, Ordering::SeqCst);
+    }
+    /// 檢查是否有待處理的重新載入請求
+    pub fn is_requested(&self) -> bool {
+        self.requested.load(Ordering::SeqCst)
+    }
+    /// 有重新載入請求時重新載入所有插件
+    /// - 多次收到的訊號會合併為一次重新載入
+    /// - `manager`: 要重新載入的管理器
+    /// - 返回值: 有請求時為 [`PluginManager::reload_all_plugins`] 的載入報告，否則為 `None`
+    pub fn reload_if_requested(&self, manager: &mut PluginManager) -> Option<LoadReport> {
+        if !self.requested.swap(false, Ordering::SeqCst) {
+            return None;
+        }
+        println!("Reloading plugins on SIGHUP");
+        Some(manager.reload_all_plugins())
+    }
+}
+
+/// 註冊只設定旗標的 SIGHUP 訊號處理函數
+/// - 訊號處理函數只設定返回值的旗標，不在訊號上下文中執行任何重新載入
+/// - 非 Unix 平台不註冊任何處理函數，返回的旗標只能透過 [`SighupReload::request`] 觸發
+/// - 返回值: 重新載入的請求旗標，註冊失敗時返回 `ConfigError`
+pub fn install_sighup_flag() -> Result<SighupReload> {
+    let reload = SighupReload::default();
+    #[cfg(unix)]
+    signal_hook::flag::register(signal_hook::consts::SIGHUP, Arc::clone(&reload.requested))
+        .map_err(|e| {
+            chm_core_define::PluginError::ConfigError(format!(
+                "Failed to register SIGHUP handler: {}",
+                e
+            ))
+        })?;
+    Ok(reload)
+}
+
+/// 在背景執行緒上執行 SIGHUP 重新載入的監看器
+/// - 釋放監看器不會停止監看，需要停止時調用 [`SighupWatcher::stop`]
+#[cfg(feature = "send")]
+#[derive(Debug)]
+pub struct SighupWatcher {
+    /// 用於關閉訊號迭代器的句柄
+    #[cfg(unix)]
+    handle: signal_hook::iterator::Handle,
+    /// 監看執行緒
+    #[cfg(unix)]
+    thread: std::thread::JoinHandle<()>,
+}
+#[cfg(feature = "send")]
+impl SighupWatcher {
+    /// 停止監看並等待監看執行緒結束
+    /// - 進行中的重新載入會先完成
+    pub fn stop(self) {
+        #[cfg(unix)]
+        {
+            self.handle.close();
+            let _ = self.thread.join();
+        }
+    }
+}
+
+/// 收到 SIGHUP 時重新載入共享管理器中的所有插件
+/// - 訊號處理函數只喚醒監看執行緒；監看執行緒鎖定 `manager` 後調用
+///   [`PluginManager::reload_all_plugins`]，重新載入期間其他執行緒會等待鎖
+/// - 重新載入尚未完成時收到的多次訊號會合併為一次重新載入
+/// - 管理器的鎖已中毒時仍會繼續重新載入
+/// - 非 Unix 平台不啟動執行緒，返回的監看器不做任何事
+/// - `manager`: 共享的管理器
+/// - 返回值: 監看器，註冊失敗時返回 `ConfigError`
+#[cfg(feature = "send")]
+#[allow(unused_variables)]
+pub fn install_sighup_reload(manager: Arc<Mutex<PluginManager>>) -> Result<SighupWatcher> {
+    #[cfg(unix)]
+    {
+        let mut signals = signal_hook::iterator::Signals::new([signal_hook::consts::SIGHUP])
+            .map_err(|e| {
+                chm_core_define::PluginError::ConfigError(format!(
+                    "Failed to register SIGHUP handler: {}",
+                    e
+                ))
+            })?;
+        let handle = signals.handle();
+        let thread = std::thread::Builder::new()
+            .name("sighup-reload".into())
+            .spawn(move || {
+                for _ in signals.forever() {
+                    println!("Reloading plugins on SIGHUP");
+                    let report = manager
+                        .lock()
+                        .unwrap_or_else(|e| e.into_inner())
+                        .reload_all_plugins();
+                    for (path, e) in &report.failed {
+                        eprintln!("Failed to reload plugin {:?} on SIGHUP: {}", path, e);
+                    }
+                }
+            })
+            .map_err(|e| {
+                chm_core_define::PluginError::ConfigError(format!(
+                    "Failed to start SIGHUP watcher thread: {}",
+                    e
+                ))
+            })?;
+        Ok(SighupWatcher { handle, thread })
+    }
+    #[cfg(not(unix))]
+    Ok(SighupWatcher {})
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::super::test_util::Fixture;
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    /// 等待條件成立，最多等待五秒
+    fn wait_for(mut condition: impl FnMut() -> bool) -> bool {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while Instant::now() < deadline {
+            if condition() {
+                return true;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        condition()
+    }
+
+    #[test]
+    fn raised_sighup_sets_the_flag() {
+        let fixture = Fixture::new();
+        fixture.add(&fixture.plugin("alpha"));
+        let mut manager = fixture.manager();
+        manager.load_all_plugins().unwrap();
+        let reload = install_sighup_flag().unwrap();
+        assert!(reload.reload_if_requested(&mut manager).is_none());
+
+        signal_hook::low_level::raise(signal_hook::consts::SIGHUP).unwrap();
+        assert!(wait_for(|| reload.is_requested()));
+        let report = reload.reload_if_requested(&mut manager).unwrap();
+        assert_eq!(report.loaded, ["alpha"]);
+        assert_eq!(fixture.log.count("alpha:on_load"), 2);
+        assert!(!reload.is_requested());
+    }
+
+    #[cfg(feature = "send")]
+    #[test]
+    fn raised_sighup_reloads_the_shared_manager() {
+        let fixture = Fixture::new();
+        fixture.add(&fixture.plugin("alpha"));
+        let mut manager = fixture.manager();
+        manager.load_all_plugins().unwrap();
+        let manager = Arc::new(Mutex::new(manager));
+        let watcher = install_sighup_reload(Arc::clone(&manager)).unwrap();
+
+        signal_hook::low_level::raise(signal_hook::consts::SIGHUP).unwrap();
+        assert!(wait_for(|| fixture.log.count("alpha:on_load") >= 2));
+        watcher.stop();
+        let manager = manager.lock().unwrap();
+        assert_eq!(
+            manager.state_of("alpha"),
+            Some(&super::super::PluginState::Enabled)
+        );
+    }
+}
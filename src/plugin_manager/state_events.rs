@@ -0,0 +1,127 @@
+//! 插件狀態變化的通知
+//!
+//! 啟用 `async` 功能時，管理器會把每次生命週期轉換 (載入、啟用、禁用、進入錯誤狀態、
+//! 恢復與卸載) 發送到一個廣播通道，可透過 [`PluginManager::state_changes`] 以非同步串流訂閱。
+//! 未啟用 `async` 功能時通知不做任何事。
+#[cfg(feature = "async")]
+use super::PluginManager;
+use super::PluginState;
+#[cfg(feature = "async")]
+use tokio::sync::broadcast;
+#[cfg(feature = "async")]
+use tokio_stream::{Stream, StreamExt};
+
+/// 狀態變化通道的預設容量
+#[cfg(feature = "async")]
+pub const DEFAULT_STATE_CHANNEL_CAPACITY: usize = 64;
+
+/// 狀態變化的發送端，由管理器持有
+#[derive(Debug)]
+#[cfg_attr(not(feature = "async"), derive(Default))]
+pub(crate) struct StateNotifier {
+    #[cfg(feature = "async")]
+    sender: broadcast::Sender<(String, PluginState)>,
+}
+#[cfg(feature = "async")]
+impl Default for StateNotifier {
+    fn default() -> Self {
+        Self {
+            sender: broadcast::channel(DEFAULT_STATE_CHANNEL_CAPACITY).0,
+        }
+    }
+}
+impl StateNotifier {
+    /// 發送狀態變化
+    /// - 沒有訂閱者時直接捨棄
+    /// - `name`: 插件的註冊名稱
+    /// - `state`: 插件的新狀態
+    #[allow(unused_variables)]
+    pub(crate) fn notify(&self, name: &str, state: &PluginState) {
+        #[cfg(feature = "async")]
+        if self.sender.receiver_count() > 0 {
+            let _ = self.sender.send((name.to_string(), state.clone()));
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+impl PluginManager {
+    /// 訂閱插件狀態變化的串流
+    /// - 每次生命週期轉換都會產生一個 (註冊名稱, 新狀態) 項目，依發生順序排列;
+    ///   重新載入或替換插件時只有狀態改變才會產生項目
+    /// - 每個串流都會收到訂閱之後發生的所有狀態變化，互不影響
+    /// - 通道容量有限 (預設為 [`DEFAULT_STATE_CHANNEL_CAPACITY`])，管理器不會等待訂閱者;
+    ///   消費太慢的串流會略過最舊的未讀項目，直接從仍在通道中的項目繼續
+    /// - 管理器釋放後串流結束
+    /// - 返回值: 狀態變化的串流
+    pub fn state_changes(&self) -> impl Stream<Item = (String, PluginState)> {
+        tokio_stream::wrappers::BroadcastStream::new(self.state_notifier.sender.subscribe())
+            .filter_map(|change| change.ok())
+    }
+    /// 設定狀態變化通道的容量
+    /// - 會建立新的通道，先前透過 [`PluginManager::state_changes`] 取得的串流隨即結束，
+    ///   因此應在訂閱之前設定
+    /// - `capacity`: 每個訂閱者最多可累積的未讀項目數量，必須大於 0，為 0 時會 panic
+    pub fn set_state_channel_capacity(&mut self, capacity: usize) {
+        self.state_notifier.sender = broadcast::channel(capacity).0;
+    }
+}
+
+#[cfg(all(test, feature = "async"))]
+mod tests {
+    use super::super::test_util::Fixture;
+    use super::*;
+
+    /// 在目前執行緒上收集串流直到結束
+    fn collect(stream: impl Stream<Item = (String, PluginState)>) -> Vec<(String, PluginState)> {
+        tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap()
+            .block_on(stream.collect())
+    }
+
+    #[test]
+    fn every_stream_receives_all_transitions_in_order() {
+        let fixture = Fixture::new();
+        fixture.add(&fixture.plugin("alpha"));
+        let mut manager = fixture.manager();
+        let first = manager.state_changes();
+        let second = manager.state_changes();
+        manager.load_all_plugins().unwrap();
+        manager.disable_plugin("alpha").unwrap();
+        manager.unload_plugin("alpha").unwrap();
+        drop(manager);
+
+        let expected = [
+            ("alpha".to_string(), PluginState::Loaded),
+            ("alpha".to_string(), PluginState::Enabled),
+            ("alpha".to_string(), PluginState::Disabled),
+            ("alpha".to_string(), PluginState::Unloaded),
+        ];
+        assert_eq!(collect(first), expected);
+        assert_eq!(collect(second), expected);
+    }
+
+    #[test]
+    fn lagging_stream_skips_the_oldest_transitions() {
+        let fixture = Fixture::new();
+        fixture.add(&fixture.plugin("alpha"));
+        let mut manager = fixture.manager();
+        manager.set_state_channel_capacity(2);
+        let stream = manager.state_changes();
+        manager.load_all_plugins().unwrap();
+        manager.disable_plugin("alpha").unwrap();
+        manager.enable_plugin("alpha").unwrap();
+        manager.disable_plugin("alpha").unwrap();
+        // 釋放管理器時卸載插件，共 6 次轉換
+        drop(manager);
+
+        assert_eq!(
+            collect(stream),
+            [
+                ("alpha".to_string(), PluginState::Disabled),
+                ("alpha".to_string(), PluginState::Unloaded),
+            ]
+        );
+    }
+}
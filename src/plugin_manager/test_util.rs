@@ -0,0 +1,608 @@
+//! 測試用的假插件與載入後端
+//!
+//! [`FakeLoader`] 以插件檔案的內容作為鍵查詢登記的 [`FakeLibrary`]，因此測試只需寫入
+//! 一般檔案即可走完目錄掃描、開啟動態庫、讀取符號與創建實例的完整流程。
+//! 匯出的符號都是不捕獲環境的函數：無參數的符號在查詢時讀取目前的 [`FakeLibrary`]
+//! (查詢後會立即調用)，接收插件實例的符號則直接讀取 [`FakePlugin`] 的共享狀態。
+//!
+//! 各功能的測試只使用其中一部分，停用部分功能時其餘的輔助函數不會被使用。
+#![allow(dead_code)]
+use super::{
+    BoxedPlugin, HostHandle, LibraryHandle, LoadedLibrary, MigrationBlob, PluginLoader,
+    ResourceUsage, CREATE_PLUGINS_SYMBOL, CREATE_PLUGIN_SYMBOL, TRY_CREATE_PLUGIN_SYMBOL,
+};
+use chm_core_define::plugin_define::{Event, Plugin};
+use chm_core_define::{PluginError, Result};
+use std::any::Any;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+static TEMP_DIR_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// 測試結束時自動刪除的暫存目錄
+pub(crate) struct TempDir(PathBuf);
+impl TempDir {
+    pub(crate) fn new() -> Self {
+        let path = std::env::temp_dir().join(format!(
+            "main_loader-test-{}-{}",
+            std::process::id(),
+            TEMP_DIR_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        std::fs::create_dir_all(&path).unwrap();
+        Self(path)
+    }
+    pub(crate) fn path(&self) -> &Path {
+        &self.0
+    }
+    pub(crate) fn join<P: AsRef<Path>>(&self, path: P) -> PathBuf {
+        self.0.join(path)
+    }
+}
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+/// 以可執行權限寫入檔案
+pub(crate) fn write_file(path: &Path, contents: &[u8]) {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).unwrap();
+    }
+    std::fs::write(path, contents).unwrap();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o755)).unwrap();
+    }
+}
+
+/// 插件鉤子的調用紀錄，格式為 `插件名稱:鉤子`，事件處理為 `插件名稱:handle_event:事件名稱`
+#[derive(Debug, Clone, Default)]
+pub(crate) struct CallLog(Arc<Mutex<Vec<String>>>);
+impl CallLog {
+    pub(crate) fn push(&self, call: String) {
+        self.0.lock().unwrap().push(call);
+    }
+    pub(crate) fn calls(&self) -> Vec<String> {
+        self.0.lock().unwrap().clone()
+    }
+    /// 取出並清空目前的紀錄
+    pub(crate) fn take(&self) -> Vec<String> {
+        std::mem::take(&mut *self.0.lock().unwrap())
+    }
+    /// 指定鉤子的調用順序，只保留插件名稱
+    pub(crate) fn order_of(&self, hook: &str) -> Vec<String> {
+        self.calls()
+            .iter()
+            .filter_map(|call| {
+                let (name, rest) = call.split_once(':')?;
+                (rest == hook).then(|| name.to_string())
+            })
+            .collect()
+    }
+    pub(crate) fn count(&self, call: &str) -> usize {
+        self.calls().iter().filter(|c| *c == call).count()
+    }
+}
+
+/// 假插件可由測試在載入後調整的行為
+#[derive(Default)]
+pub(crate) struct FakeState {
+    /// 會失敗的鉤子與錯誤訊息
+    pub(crate) failing: HashMap<String, String>,
+    /// 只失敗有限次數的鉤子及剩餘的失敗次數
+    pub(crate) failures_left: HashMap<String, usize>,
+    /// 收到指定事件時返回的回應事件
+    pub(crate) replies: HashMap<String, Event>,
+    /// 收到指定事件時透過宿主句柄發送的事件
+    pub(crate) broadcasts: HashMap<String, Event>,
+    /// `plugin_set_host` 傳入的宿主句柄
+    pub(crate) host: Option<Arc<dyn HostHandle>>,
+    /// `plugin_configure` 或設定變更鉤子收到的設定
+    pub(crate) config: Option<HashMap<String, String>>,
+    /// `plugin_export_state` 匯出的狀態
+    pub(crate) export: Option<MigrationBlob>,
+    /// `plugin_on_migrate` 收到的舊版本與狀態
+    pub(crate) migrated: Option<(String, MigrationBlob)>,
+    /// `plugin_resource_usage` 回報的資源用量
+    pub(crate) usage: ResourceUsage,
+    /// `plugin_published_events` 宣告的事件
+    pub(crate) published: Vec<String>,
+    /// `plugin_priority` 回報的優先級，未設定時使用動態庫的值
+    pub(crate) priority: Option<i32>,
+    /// `plugin_dependencies` 回報的依賴，未設定時使用動態庫的值
+    pub(crate) dependencies: Option<Vec<String>>,
+    /// 每次調用 `handle_event` 前的等待時間
+    pub(crate) delay: Option<std::time::Duration>,
+    /// 調用其他鉤子時的等待時間，鍵為鉤子名稱
+    pub(crate) slow: HashMap<String, std::time::Duration>,
+    /// 調用鉤子時設為 `true` 的旗標，鍵為鉤子名稱
+    pub(crate) flags: HashMap<String, Arc<AtomicBool>>,
+}
+
+/// 記錄每個鉤子調用的假插件
+/// - 複製出的實例共享同一份 [`FakeState`]，測試保留的樣板可觀察與調整已載入的實例
+#[derive(Clone)]
+pub(crate) struct FakePlugin {
+    name: String,
+    version: String,
+    description: String,
+    events: Vec<String>,
+    state: Arc<Mutex<FakeState>>,
+    log: CallLog,
+}
+impl fmt::Debug for FakePlugin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FakePlugin")
+            .field("name", &self.name)
+            .field("version", &self.version)
+            .finish_non_exhaustive()
+    }
+}
+impl FakePlugin {
+    pub(crate) fn new(name: &str, log: &CallLog) -> Self {
+        Self {
+            name: name.to_string(),
+            version: "1.0.0".to_string(),
+            description: format!("fake plugin {}", name),
+            events: Vec::new(),
+            state: Arc::default(),
+            log: log.clone(),
+        }
+    }
+    pub(crate) fn version(mut self, version: &str) -> Self {
+        self.version = version.to_string();
+        self
+    }
+    pub(crate) fn description(mut self, description: &str) -> Self {
+        self.description = description.to_string();
+        self
+    }
+    pub(crate) fn subscribe(mut self, events: &[&str]) -> Self {
+        self.events = events.iter().map(|e| e.to_string()).collect();
+        self
+    }
+    /// 讓鉤子失敗，`hook` 為鉤子名稱，例如 `on_enable`、`handle_event`
+    pub(crate) fn failing(self, hook: &str, message: &str) -> Self {
+        self.set_failing(hook, Some(message));
+        self
+    }
+    /// 讓鉤子的前 `times` 次調用失敗，之後恢復正常
+    pub(crate) fn failing_times(self, hook: &str, times: usize, message: &str) -> Self {
+        self.set_failing(hook, Some(message));
+        self.state().failures_left.insert(hook.to_string(), times);
+        self
+    }
+    /// 讓鉤子在返回前等待 `delay`
+    pub(crate) fn slow(self, hook: &str, delay: std::time::Duration) -> Self {
+        self.state().slow.insert(hook.to_string(), delay);
+        self
+    }
+    /// 調用鉤子時把 `flag` 設為 `true`，例如在 `on_load` 時取消載入
+    pub(crate) fn sets_flag(self, hook: &str, flag: &Arc<AtomicBool>) -> Self {
+        self.state()
+            .flags
+            .insert(hook.to_string(), Arc::clone(flag));
+        self
+    }
+    /// 收到 `on` 事件時返回 `reply`
+    pub(crate) fn reply(self, on: &str, reply: Event) -> Self {
+        self.state().replies.insert(on.to_string(), reply);
+        self
+    }
+    /// 收到 `on` 事件時透過宿主句柄發送 `event`
+    pub(crate) fn broadcast_on(self, on: &str, event: Event) -> Self {
+        self.state().broadcasts.insert(on.to_string(), event);
+        self
+    }
+    /// 設定鉤子是否失敗，`message` 為 `None` 時恢復正常
+    pub(crate) fn set_failing(&self, hook: &str, message: Option<&str>) {
+        let mut state = self.state();
+        state.failures_left.remove(hook);
+        match message {
+            Some(message) => state.failing.insert(hook.to_string(), message.to_string()),
+            None => state.failing.remove(hook),
+        };
+    }
+    pub(crate) fn state(&self) -> MutexGuard<'_, FakeState> {
+        self.state.lock().unwrap()
+    }
+    pub(crate) fn id(&self) -> &str {
+        &self.name
+    }
+    /// 記錄調用並依設定返回錯誤
+    fn hook(&self, hook: &str, make: fn(String) -> PluginError) -> Result<()> {
+        self.log.push(format!("{}:{}", self.name, hook));
+        if let Some(flag) = self.state().flags.get(hook) {
+            flag.store(true, Ordering::SeqCst);
+        }
+        let delay = self.state().slow.get(hook).copied();
+        if let Some(delay) = delay {
+            std::thread::sleep(delay);
+        }
+        let mut state = self.state();
+        let Some(message) = state.failing.get(hook).cloned() else {
+            return Ok(());
+        };
+        if let Some(left) = state.failures_left.get_mut(hook) {
+            *left = left.saturating_sub(1);
+            if *left == 0 {
+                state.failures_left.remove(hook);
+                state.failing.remove(hook);
+            }
+        }
+        Err(make(message))
+    }
+}
+impl Plugin for FakePlugin {
+    fn name(&self) -> &str {
+        &self.name
+    }
+    fn version(&self) -> &str {
+        &self.version
+    }
+    fn description(&self) -> &str {
+        &self.description
+    }
+    fn on_load(&self) -> Result<()> {
+        self.hook("on_load", PluginError::InitError)
+    }
+    fn on_unload(&self) -> Result<()> {
+        self.hook("on_unload", PluginError::InitError)
+    }
+    fn on_enable(&self) -> Result<()> {
+        self.hook("on_enable", PluginError::EnableError)
+    }
+    fn on_disable(&self) -> Result<()> {
+        self.hook("on_disable", PluginError::DisableError)
+    }
+    fn handle_event(&self, event: &Event) -> Result<Option<Event>> {
+        self.log
+            .push(format!("{}:handle_event:{}", self.name, event.name));
+        let delay = self.state().delay;
+        if let Some(delay) = delay {
+            std::thread::sleep(delay);
+        }
+        let state = self.state();
+        if let Some(message) = state.failing.get("handle_event") {
+            return Err(PluginError::EventError(message.clone()));
+        }
+        let reply = state.replies.get(&event.name).cloned();
+        let broadcast = state.broadcasts.get(&event.name).cloned();
+        let host = state.host.clone();
+        drop(state);
+        if let (Some(broadcast), Some(host)) = (broadcast, host) {
+            host.broadcast(broadcast);
+        }
+        Ok(reply)
+    }
+    fn subscribed_events(&self) -> Vec<String> {
+        self.events.clone()
+    }
+}
+
+/// 建立事件
+pub(crate) fn event(name: &str) -> Event {
+    Event {
+        name: name.to_string(),
+        data: HashMap::new(),
+        priority: 0,
+    }
+}
+
+/// 假動態庫匯出的插件與符號
+#[derive(Clone, Default)]
+pub(crate) struct FakeLibrary {
+    /// 創建實例時複製的插件樣板
+    plugins: Vec<FakePlugin>,
+    /// 以 `create_plugins` 創建全部樣板
+    multi: bool,
+    /// 以 `try_create_plugin` 創建，值為其返回的錯誤
+    fallible: Option<Option<String>>,
+    /// 是否匯出 `create_plugin`
+    no_create: bool,
+    /// 是否匯出 `unload_plugin`
+    no_unload: bool,
+    /// 只匯出沒有 `Send` 約束的 `create_plugin`
+    without_send: bool,
+    priority: Option<i32>,
+    dependencies: Option<Vec<String>>,
+    capabilities: Option<Vec<String>>,
+    abi: Option<(u32, Option<u32>)>,
+    /// 額外匯出的可選鉤子符號
+    hooks: HashSet<&'static str>,
+}
+impl FakeLibrary {
+    /// 以 `create_plugin` 創建單一插件的動態庫
+    pub(crate) fn new(plugin: &FakePlugin) -> Self {
+        Self {
+            plugins: vec![plugin.clone()],
+            ..Self::default()
+        }
+    }
+    /// 以 `create_plugins` 創建多個插件的動態庫
+    pub(crate) fn multi(plugins: &[&FakePlugin]) -> Self {
+        Self {
+            plugins: plugins.iter().map(|p| (*p).clone()).collect(),
+            multi: true,
+            ..Self::default()
+        }
+    }
+    /// 沒有任何創建符號的動態庫
+    pub(crate) fn without_create() -> Self {
+        Self {
+            no_create: true,
+            ..Self::default()
+        }
+    }
+    /// 改以 `try_create_plugin` 創建，`error` 為其返回的錯誤
+    pub(crate) fn fallible(mut self, error: Option<&str>) -> Self {
+        self.fallible = Some(error.map(str::to_string));
+        self
+    }
+    /// 只匯出沒有 `Send` 約束的 `create_plugin`，模擬未以 `send` 形式匯出的動態庫
+    pub(crate) fn without_send(mut self) -> Self {
+        self.without_send = true;
+        self
+    }
+    pub(crate) fn without_unload(mut self) -> Self {
+        self.no_unload = true;
+        self
+    }
+    pub(crate) fn priority(mut self, priority: i32) -> Self {
+        self.priority = Some(priority);
+        self
+    }
+    pub(crate) fn dependencies(mut self, dependencies: &[&str]) -> Self {
+        self.dependencies = Some(dependencies.iter().map(|d| d.to_string()).collect());
+        self
+    }
+    pub(crate) fn capabilities(mut self, capabilities: &[&str]) -> Self {
+        self.capabilities = Some(capabilities.iter().map(|c| c.to_string()).collect());
+        self
+    }
+    pub(crate) fn abi(mut self, version: u32, min: Option<u32>) -> Self {
+        self.abi = Some((version, min));
+        self
+    }
+    /// 匯出可選的鉤子符號，例如 `plugin_set_host`
+    pub(crate) fn export(mut self, symbol: &'static str) -> Self {
+        self.hooks.insert(symbol);
+        self
+    }
+}
+
+thread_local! {
+    /// 最近一次查詢符號的假動態庫
+    static CURRENT: RefCell<Option<FakeLibrary>> = const { RefCell::new(None) };
+}
+
+/// 讀取最近一次查詢符號的假動態庫
+fn current<T>(f: impl FnOnce(&FakeLibrary) -> T) -> T {
+    CURRENT.with(|current| f(current.borrow().as_ref().expect("no fake library")))
+}
+
+/// 將插件實例轉回假插件
+fn fake(plugin: &dyn Plugin) -> &FakePlugin {
+    // 假動態庫只會創建 `FakePlugin`
+    unsafe { &*(plugin as *const dyn Plugin as *const FakePlugin) }
+}
+
+fn fake_mut(plugin: &mut dyn Plugin) -> &mut FakePlugin {
+    unsafe { &mut *(plugin as *mut dyn Plugin as *mut FakePlugin) }
+}
+
+fn create_plugin() -> BoxedPlugin {
+    current(|lib| Box::new(lib.plugins[0].clone()))
+}
+fn create_plugin_without_send() -> Box<dyn Plugin> {
+    current(|lib| Box::new(lib.plugins[0].clone()))
+}
+fn try_create_plugin() -> std::result::Result<BoxedPlugin, String> {
+    current(|lib| match lib.fallible.clone().flatten() {
+        Some(error) => Err(error),
+        None => Ok(Box::new(lib.plugins[0].clone()) as BoxedPlugin),
+    })
+}
+fn create_plugins() -> Vec<BoxedPlugin> {
+    current(|lib| {
+        lib.plugins
+            .iter()
+            .map(|p| Box::new(p.clone()) as BoxedPlugin)
+            .collect()
+    })
+}
+fn unload_plugin() {}
+fn plugin_priority(plugin: &dyn Plugin) -> i32 {
+    let priority = fake(plugin).state().priority;
+    priority.unwrap_or_else(|| current(|lib| lib.priority.unwrap_or_default()))
+}
+fn plugin_dependencies(plugin: &dyn Plugin) -> Vec<String> {
+    let dependencies = fake(plugin).state().dependencies.clone();
+    dependencies.unwrap_or_else(|| current(|lib| lib.dependencies.clone().unwrap_or_default()))
+}
+fn plugin_capabilities(_plugin: &dyn Plugin) -> Vec<String> {
+    current(|lib| lib.capabilities.clone().unwrap_or_default())
+}
+fn plugin_abi_version() -> u32 {
+    current(|lib| lib.abi.map_or(0, |(version, _)| version))
+}
+fn plugin_abi_min() -> u32 {
+    current(|lib| lib.abi.and_then(|(_, min)| min).unwrap_or_default())
+}
+fn plugin_set_host(host: Arc<dyn HostHandle>) {
+    current(|lib| {
+        for plugin in &lib.plugins {
+            plugin.state().host = Some(host.clone());
+        }
+    })
+}
+fn plugin_published_events(plugin: &dyn Plugin) -> Vec<String> {
+    fake(plugin).state().published.clone()
+}
+fn plugin_as_any(plugin: &dyn Plugin) -> &dyn Any {
+    fake(plugin)
+}
+fn plugin_health_check(plugin: &dyn Plugin) -> Result<()> {
+    fake(plugin).hook("health_check", PluginError::EventError)
+}
+fn plugin_resource_usage(plugin: &dyn Plugin) -> ResourceUsage {
+    fake(plugin).state().usage
+}
+fn plugin_config_change(plugin: &mut dyn Plugin, config: &HashMap<String, String>) -> Result<()> {
+    let plugin = fake_mut(plugin);
+    plugin.hook("config_change", PluginError::ConfigError)?;
+    plugin.state().config = Some(config.clone());
+    Ok(())
+}
+fn plugin_export_state(plugin: &dyn Plugin) -> Option<MigrationBlob> {
+    fake(plugin).state().export.clone()
+}
+fn plugin_on_migrate(plugin: &mut dyn Plugin, from: &str, blob: MigrationBlob) -> Result<()> {
+    let plugin = fake_mut(plugin);
+    plugin.hook("on_migrate", PluginError::InitError)?;
+    plugin.state().migrated = Some((from.to_string(), blob));
+    Ok(())
+}
+fn plugin_on_all_loaded(plugin: &mut dyn Plugin) -> Result<()> {
+    fake_mut(plugin).hook("on_all_loaded", PluginError::InitError)
+}
+
+impl LibraryHandle for FakeLibrary {
+    fn symbol(&self, symbol: &[u8]) -> Option<*const ()> {
+        CURRENT.with(|current| *current.borrow_mut() = Some(self.clone()));
+        let name = std::str::from_utf8(symbol).ok()?;
+        if self.without_send {
+            if name == "create_plugin" {
+                return Some(create_plugin_without_send as fn() -> Box<dyn Plugin> as *const ());
+            }
+            if name.ends_with("_send") {
+                return None;
+            }
+        }
+        let address = match name {
+            CREATE_PLUGIN_SYMBOL if !self.no_create && !self.multi => {
+                create_plugin as fn() -> BoxedPlugin as *const ()
+            }
+            TRY_CREATE_PLUGIN_SYMBOL if self.fallible.is_some() => {
+                try_create_plugin as fn() -> std::result::Result<BoxedPlugin, String> as *const ()
+            }
+            CREATE_PLUGINS_SYMBOL if self.multi => {
+                create_plugins as fn() -> Vec<BoxedPlugin> as *const ()
+            }
+            "unload_plugin" if !self.no_unload => unload_plugin as fn() as *const (),
+            "plugin_priority" if self.priority.is_some() || self.hooks.contains(name) => {
+                plugin_priority as fn(&dyn Plugin) -> i32 as *const ()
+            }
+            "plugin_dependencies" if self.dependencies.is_some() || self.hooks.contains(name) => {
+                plugin_dependencies as fn(&dyn Plugin) -> Vec<String> as *const ()
+            }
+            "plugin_capabilities" if self.capabilities.is_some() => {
+                plugin_capabilities as fn(&dyn Plugin) -> Vec<String> as *const ()
+            }
+            "plugin_abi_version" if self.abi.is_some() => {
+                plugin_abi_version as fn() -> u32 as *const ()
+            }
+            "plugin_abi_min" if self.abi.is_some_and(|(_, min)| min.is_some()) => {
+                plugin_abi_min as fn() -> u32 as *const ()
+            }
+            _ if !self.hooks.contains(name) => return None,
+            "plugin_set_host" => plugin_set_host as fn(Arc<dyn HostHandle>) as *const (),
+            "plugin_published_events" => {
+                plugin_published_events as fn(&dyn Plugin) -> Vec<String> as *const ()
+            }
+            "plugin_as_any" => plugin_as_any as fn(&dyn Plugin) -> &dyn Any as *const (),
+            "plugin_health_check" => {
+                plugin_health_check as fn(&dyn Plugin) -> Result<()> as *const ()
+            }
+            "plugin_resource_usage" => {
+                plugin_resource_usage as fn(&dyn Plugin) -> ResourceUsage as *const ()
+            }
+            "plugin_config_change" => {
+                plugin_config_change as fn(&mut dyn Plugin, &HashMap<String, String>) -> Result<()>
+                    as *const ()
+            }
+            "plugin_export_state" => {
+                plugin_export_state as fn(&dyn Plugin) -> Option<MigrationBlob> as *const ()
+            }
+            "plugin_on_migrate" => {
+                plugin_on_migrate as fn(&mut dyn Plugin, &str, MigrationBlob) -> Result<()>
+                    as *const ()
+            }
+            "plugin_on_all_loaded" => {
+                plugin_on_all_loaded as fn(&mut dyn Plugin) -> Result<()> as *const ()
+            }
+            _ => return None,
+        };
+        Some(address)
+    }
+}
+
+/// 以檔案內容查詢假動態庫的載入後端
+#[derive(Clone, Default)]
+pub(crate) struct FakeLoader {
+    libraries: Arc<Mutex<HashMap<String, FakeLibrary>>>,
+}
+impl FakeLoader {
+    /// 登記內容為 `key` 的插件檔案對應的假動態庫
+    pub(crate) fn register(&self, key: &str, library: FakeLibrary) {
+        self.libraries
+            .lock()
+            .unwrap()
+            .insert(key.to_string(), library);
+    }
+}
+impl PluginLoader for FakeLoader {
+    fn load(&self, path: &Path) -> Result<LoadedLibrary> {
+        let key = std::fs::read_to_string(path)
+            .map_err(|e| PluginError::LoadError(format!("Failed to load library: {}", e)))?;
+        let library = self.libraries.lock().unwrap().get(&key).cloned();
+        library.map(LoadedLibrary::new).ok_or_else(|| {
+            PluginError::LoadError(format!("Failed to load library: unknown file {:?}", key))
+        })
+    }
+}
+
+/// 測試的插件目錄、假載入後端與調用紀錄
+pub(crate) struct Fixture {
+    pub(crate) dir: TempDir,
+    pub(crate) loader: FakeLoader,
+    pub(crate) log: CallLog,
+}
+impl Fixture {
+    pub(crate) fn new() -> Self {
+        Self {
+            dir: TempDir::new(),
+            loader: FakeLoader::default(),
+            log: CallLog::default(),
+        }
+    }
+    /// 以此目錄與假載入後端建立管理器
+    pub(crate) fn manager(&self) -> super::PluginManager {
+        let mut manager = super::PluginManager::new(self.dir.path());
+        manager.set_loader(Box::new(self.loader.clone()));
+        manager.set_temp_dir(self.dir.join(".tmp"));
+        manager
+    }
+    /// 建立記錄到此紀錄的假插件
+    pub(crate) fn plugin(&self, name: &str) -> FakePlugin {
+        FakePlugin::new(name, &self.log)
+    }
+    /// 在插件目錄寫入檔案 `file` 並登記其對應的假動態庫
+    /// - 檔案內容為 `file`，因此相同名稱的檔案在不同目錄中對應同一個動態庫
+    pub(crate) fn install(&self, file: &str, library: FakeLibrary) -> PathBuf {
+        self.loader.register(file, library);
+        let path = self.dir.join(file);
+        write_file(&path, file.as_bytes());
+        path
+    }
+    /// 安裝只包含 `plugin` 的動態庫，檔案名稱為 `{name}.so`
+    pub(crate) fn add(&self, plugin: &FakePlugin) -> PathBuf {
+        self.install(&format!("{}.so", plugin.id()), FakeLibrary::new(plugin))
+    }
+}
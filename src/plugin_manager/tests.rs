@@ -0,0 +1,3855 @@
+//! 管理器的測試，以 [`test_util`](super::test_util) 的假載入後端取代真正的動態庫
+use super::test_util::*;
+use super::*;
+
+// 依條件卸載
+
+#[test]
+fn unload_where_removes_only_errored_plugins() {
+    let fixture = Fixture::new();
+    fixture.add(&fixture.plugin("alpha"));
+    fixture.add(&fixture.plugin("beta").failing("on_enable", "boom"));
+    fixture.add(&fixture.plugin("gamma"));
+    let mut manager = fixture.manager();
+    let _ = manager.load_all_plugins();
+    assert!(matches!(
+        manager.state_of("beta"),
+        Some(PluginState::Error(_))
+    ));
+
+    manager
+        .unload_where(|_, state| matches!(state, PluginState::Error(_)))
+        .unwrap();
+    assert_eq!(manager.plugin_names(), ["alpha", "gamma"]);
+    assert_eq!(fixture.log.order_of("on_unload"), ["beta"]);
+}
+
+#[test]
+fn unload_where_aggregates_errors_and_keeps_going() {
+    let fixture = Fixture::new();
+    fixture.add(&fixture.plugin("alpha").failing("on_unload", "stuck"));
+    fixture.add(&fixture.plugin("beta"));
+    fixture.add(&fixture.plugin("gamma"));
+    let mut manager = fixture.manager();
+    manager.load_all_plugins().unwrap();
+
+    let err = manager.unload_where(|name, _| name != "gamma").unwrap_err();
+    assert!(
+        matches!(&err, PluginError::LoadError(msg) if msg.contains("alpha") && msg.contains("stuck"))
+    );
+    assert!(!manager.plugins.contains_key("beta"));
+    assert!(manager.plugins.contains_key("gamma"));
+}
+
+// 訂閱者數量
+
+#[test]
+fn event_subscriber_counts_rank_hot_events_first() {
+    let fixture = Fixture::new();
+    fixture.add(&fixture.plugin("alpha").subscribe(&["tick", "save"]));
+    fixture.add(&fixture.plugin("beta").subscribe(&["tick"]));
+    fixture.add(&fixture.plugin("gamma").subscribe(&["tick", "quit"]));
+    let mut manager = fixture.manager();
+    manager.load_all_plugins().unwrap();
+    assert_eq!(
+        manager.event_subscriber_counts(),
+        [
+            ("tick".to_string(), 3),
+            ("quit".to_string(), 1),
+            ("save".to_string(), 1),
+        ]
+    );
+}
+
+#[test]
+fn event_subscriber_counts_skip_stale_subscribers() {
+    let fixture = Fixture::new();
+    fixture.add(&fixture.plugin("alpha").subscribe(&["tick"]));
+    let mut manager = fixture.manager();
+    manager.load_all_plugins().unwrap();
+    manager.event_bus.subscribe("tick", "ghost").unwrap();
+    manager.event_bus.subscribe("orphan", "ghost").unwrap();
+    assert_eq!(manager.event_subscriber_counts(), [("tick".to_string(), 1)]);
+}
+
+// 批次操作
+
+#[test]
+fn try_enable_all_reports_every_plugin_without_short_circuiting() {
+    let fixture = Fixture::new();
+    fixture.add(&fixture.plugin("alpha"));
+    fixture.add(&fixture.plugin("beta").failing("on_enable", "boom"));
+    fixture.add(&fixture.plugin("gamma"));
+    let mut manager = fixture.manager();
+    manager.set_auto_enable(false);
+    manager.load_all_plugins().unwrap();
+
+    let results = manager.try_enable_all();
+    let outcome: Vec<(&str, bool)> = results
+        .iter()
+        .map(|(name, ret)| (name.as_str(), ret.is_ok()))
+        .collect();
+    assert_eq!(outcome, [("alpha", true), ("beta", false), ("gamma", true)]);
+    assert_eq!(manager.state_of("gamma"), Some(&PluginState::Enabled));
+    assert!(matches!(
+        manager.state_of("beta"),
+        Some(PluginState::Error(_))
+    ));
+}
+
+#[test]
+fn enable_all_plugins_aggregates_failures() {
+    let fixture = Fixture::new();
+    fixture.add(&fixture.plugin("alpha").failing("on_enable", "first"));
+    fixture.add(&fixture.plugin("beta").failing("on_enable", "second"));
+    fixture.add(&fixture.plugin("gamma"));
+    let mut manager = fixture.manager();
+    manager.set_auto_enable(false);
+    manager.load_all_plugins().unwrap();
+
+    let err = manager.enable_all_plugins().unwrap_err();
+    assert!(matches!(&err, PluginError::EnableError(msg)
+        if msg.contains("first") && msg.contains("second")));
+    assert_eq!(manager.plugins["gamma"].state, PluginState::Enabled);
+}
+
+#[test]
+fn try_unload_all_reports_every_plugin() {
+    let fixture = Fixture::new();
+    fixture.add(&fixture.plugin("alpha"));
+    fixture.add(&fixture.plugin("beta").failing("on_unload", "stuck"));
+    let mut manager = fixture.manager();
+    manager.load_all_plugins().unwrap();
+
+    let results = manager.try_unload_all();
+    let names: Vec<&str> = results.iter().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(names, ["alpha", "beta"]);
+    assert!(results[0].1.is_ok());
+    assert!(results[1].1.is_err());
+    assert_eq!(fixture.log.order_of("on_unload"), ["alpha", "beta"]);
+}
+
+// 創建管理器
+
+#[test]
+fn new_create_creates_missing_plugin_dir() {
+    let dir = TempDir::new();
+    let plugin_dir = dir.join("nested").join("plugins");
+    let manager = PluginManager::new_create(&plugin_dir).unwrap();
+    assert!(plugin_dir.is_dir());
+    assert!(manager.get_all_plugins().is_empty());
+    // 目錄已存在時同樣成功
+    PluginManager::new_create(&plugin_dir).unwrap();
+}
+
+#[test]
+fn new_create_reports_uncreatable_dir() {
+    let dir = TempDir::new();
+    let file = dir.join("file");
+    write_file(&file, b"");
+    let err = PluginManager::new_create(file.join("plugins")).unwrap_err();
+    assert!(
+        matches!(err, PluginError::LoadError(msg) if msg.starts_with("Failed to create plugin directory"))
+    );
+}
+
+// 快照與恢復
+
+/// 建立四個插件並使其分別處於啟用、禁用、已載入與錯誤狀態
+fn snapshot_fixture() -> (Fixture, ManagerSnapshot) {
+    let fixture = Fixture::new();
+    for name in ["enabled", "disabled", "loaded"] {
+        fixture.add(&fixture.plugin(name));
+    }
+    fixture.add(&fixture.plugin("broken").failing("on_enable", "no database"));
+    let mut manager = fixture.manager();
+    manager.set_auto_enable(false);
+    manager.load_all_plugins().unwrap();
+    manager.enable_plugin("enabled").unwrap();
+    manager.enable_plugin("disabled").unwrap();
+    manager.disable_plugin("disabled").unwrap();
+    assert!(manager.enable_plugin("broken").is_err());
+    let snapshot = manager.snapshot();
+    drop(manager);
+    fixture.log.take();
+    (fixture, snapshot)
+}
+
+#[test]
+fn restore_round_trips_every_state() {
+    let (fixture, snapshot) = snapshot_fixture();
+    let mut manager = fixture.manager();
+    let report = manager.restore(&snapshot);
+    assert!(report.failed.is_empty(), "{:?}", report.failed);
+    assert_eq!(report.loaded, ["broken", "disabled", "enabled", "loaded"]);
+    let restored: Vec<(String, PluginState)> = manager
+        .snapshot()
+        .plugins
+        .into_iter()
+        .map(|plugin| (plugin.name, plugin.state))
+        .collect();
+    let recorded: Vec<(String, PluginState)> = snapshot
+        .plugins
+        .iter()
+        .map(|plugin| (plugin.name.clone(), plugin.state.clone()))
+        .collect();
+    assert_eq!(restored, recorded);
+    // 自動啟用被暫停，只有記錄為啟用的插件調用了 `on_enable`
+    assert_eq!(fixture.log.order_of("on_enable"), ["enabled"]);
+    // 恢復後重新開啟自動啟用
+    let path = fixture.add(&fixture.plugin("late"));
+    manager.load_plugin(&path).unwrap();
+    assert_eq!(manager.state_of("late"), Some(&PluginState::Enabled));
+}
+
+#[test]
+fn restore_reports_moved_files_and_restores_the_rest() {
+    let (fixture, snapshot) = snapshot_fixture();
+    std::fs::rename(
+        fixture.dir.join("enabled.so"),
+        fixture.dir.join("moved.so.bak"),
+    )
+    .unwrap();
+    let mut manager = fixture.manager();
+    let report = manager.restore(&snapshot);
+    assert_eq!(report.failed.len(), 1);
+    assert_eq!(report.failed[0].0, fixture.dir.join("enabled.so"));
+    assert!(report.failed[0].1.contains("not found"));
+    assert_eq!(report.loaded, ["broken", "disabled", "loaded"]);
+    assert_eq!(manager.state_of("enabled"), None);
+    assert_eq!(manager.state_of("disabled"), Some(&PluginState::Disabled));
+    assert_eq!(manager.state_of("loaded"), Some(&PluginState::Loaded));
+    assert!(matches!(
+        manager.state_of("broken"),
+        Some(PluginState::Error(msg)) if msg.contains("no database")
+    ));
+}
+
+// 插件名稱驗證
+
+#[test]
+fn default_name_validator_rejects_empty_long_and_control_names() {
+    assert!(default_name_validator("alpha-1.0"));
+    assert!(default_name_validator(&"a".repeat(MAX_PLUGIN_NAME_LEN)));
+    assert!(!default_name_validator(""));
+    assert!(!default_name_validator(
+        &"a".repeat(MAX_PLUGIN_NAME_LEN + 1)
+    ));
+    assert!(!default_name_validator("bad\nname"));
+    assert!(!default_name_validator("tab\tname"));
+}
+
+#[test]
+fn load_rejects_a_name_with_a_newline() {
+    let fixture = Fixture::new();
+    let path = fixture.install("bad.so", FakeLibrary::new(&fixture.plugin("bad\nname")));
+    let mut manager = fixture.manager();
+    let err = manager.load_plugin(&path).unwrap_err();
+    assert!(matches!(err, PluginError::LoadError(msg) if msg.contains("name")));
+    assert!(manager.plugins.is_empty());
+    assert!(fixture.log.calls().is_empty());
+}
+
+#[test]
+fn load_rejects_an_overlong_name() {
+    let fixture = Fixture::new();
+    let long = "a".repeat(MAX_PLUGIN_NAME_LEN + 1);
+    let path = fixture.install("long.so", FakeLibrary::new(&fixture.plugin(&long)));
+    let mut manager = fixture.manager();
+    let err = manager.load_plugin(&path).unwrap_err();
+    assert!(matches!(err, PluginError::LoadError(msg) if msg.contains("name")));
+    assert!(manager.plugins.is_empty());
+}
+
+#[test]
+fn custom_name_validator_replaces_the_default() {
+    let fixture = Fixture::new();
+    let dashed = fixture.add(&fixture.plugin("with-dash"));
+    let plain = fixture.add(&fixture.plugin("plain"));
+    let mut manager = fixture.manager();
+    manager.set_name_validator(Box::new(|name: &str| !name.contains('-')));
+    assert!(matches!(
+        manager.load_plugin(&dashed),
+        Err(PluginError::LoadError(_))
+    ));
+    manager.load_plugin(&plain).unwrap();
+    assert_eq!(manager.plugin_names(), ["plain"]);
+}
+
+// 重新載入
+
+#[test]
+fn reload_swaps_instance_and_subscriptions_together() {
+    let fixture = Fixture::new();
+    let path = fixture.add(&fixture.plugin("alpha").subscribe(&["old"]));
+    let mut manager = fixture.manager();
+    manager.load_all_plugins().unwrap();
+
+    let file = path.file_name().unwrap().to_str().unwrap();
+    let new = fixture.plugin("alpha").version("2.0.0").subscribe(&["new"]);
+    fixture.loader.register(file, FakeLibrary::new(&new));
+    fixture.log.take();
+    manager.reload_plugin("alpha").unwrap();
+    assert_eq!(manager.get_plugin("alpha").unwrap().version(), "2.0.0");
+    assert_eq!(manager.plugins["alpha"].state, PluginState::Enabled);
+    assert!(manager.event_bus.get_subscribers("old").is_empty());
+    assert_eq!(manager.event_bus.get_subscribers("new"), ["alpha"]);
+    assert_eq!(
+        fixture.log.calls(),
+        [
+            "alpha:on_load",
+            "alpha:on_enable",
+            "alpha:on_disable",
+            "alpha:on_unload",
+        ]
+    );
+}
+
+#[test]
+fn failed_reload_keeps_the_old_instance_subscribed() {
+    let fixture = Fixture::new();
+    let path = fixture.add(&fixture.plugin("alpha").subscribe(&["tick"]));
+    let mut manager = fixture.manager();
+    manager.load_all_plugins().unwrap();
+
+    let file = path.file_name().unwrap().to_str().unwrap();
+    let broken = fixture
+        .plugin("alpha")
+        .version("2.0.0")
+        .failing("on_enable", "broken");
+    fixture.loader.register(file, FakeLibrary::new(&broken));
+    assert!(manager.reload_plugin("alpha").is_err());
+    assert_eq!(manager.get_plugin("alpha").unwrap().version(), "1.0.0");
+    assert_eq!(manager.plugins["alpha"].state, PluginState::Enabled);
+    assert_eq!(manager.event_bus.get_subscribers("tick"), ["alpha"]);
+}
+
+#[test]
+fn reload_preserves_disabled_state() {
+    let fixture = Fixture::new();
+    fixture.add(&fixture.plugin("alpha"));
+    let mut manager = fixture.manager();
+    manager.load_all_plugins().unwrap();
+    manager.disable_plugin("alpha").unwrap();
+    fixture.log.take();
+
+    manager.reload_plugin("alpha").unwrap();
+    assert_eq!(manager.plugins["alpha"].state, PluginState::Disabled);
+    assert_eq!(fixture.log.count("alpha:on_enable"), 0);
+}
+
+// 載入耗時
+
+#[cfg(feature = "metrics")]
+#[test]
+fn load_timings_record_each_phase() {
+    let fixture = Fixture::new();
+    let delay = std::time::Duration::from_millis(20);
+    fixture.add(&fixture.plugin("alpha").slow("on_load", delay));
+    fixture.add(&fixture.plugin("beta").slow("on_enable", delay));
+    let mut manager = fixture.manager();
+    manager.load_all_plugins().unwrap();
+
+    let alpha = manager.get_load_timings("alpha").unwrap();
+    assert!(alpha.on_load >= delay);
+    let beta = manager.get_load_timings("beta").unwrap();
+    assert!(beta.on_enable >= delay);
+    assert_eq!(
+        beta.total(),
+        beta.library_open + beta.create + beta.on_load + beta.on_enable
+    );
+    assert!(manager.get_load_timings("missing").is_none());
+}
+
+#[cfg(feature = "metrics")]
+#[test]
+fn slowest_plugins_sorts_by_total_and_truncates() {
+    let fixture = Fixture::new();
+    let slow = fixture
+        .plugin("slow")
+        .slow("on_load", std::time::Duration::from_millis(40));
+    let medium = fixture
+        .plugin("medium")
+        .slow("on_load", std::time::Duration::from_millis(20));
+    fixture.add(&slow);
+    fixture.add(&medium);
+    fixture.add(&fixture.plugin("fast"));
+    let mut manager = fixture.manager();
+    manager.load_all_plugins().unwrap();
+
+    let slowest: Vec<String> = manager
+        .slowest_plugins(2)
+        .into_iter()
+        .map(|(name, _)| name)
+        .collect();
+    assert_eq!(slowest, ["slow", "medium"]);
+}
+
+// 從記憶體載入
+
+/// 暫存目錄中的檔案
+fn temp_files(fixture: &Fixture) -> Vec<PathBuf> {
+    match std::fs::read_dir(fixture.dir.join(".tmp")) {
+        Ok(entries) => entries.map(|entry| entry.unwrap().path()).collect(),
+        Err(_) => Vec::new(),
+    }
+}
+
+#[test]
+fn load_from_bytes_uses_a_private_temp_file_removed_on_unload() {
+    let fixture = Fixture::new();
+    fixture
+        .loader
+        .register("embedded", FakeLibrary::new(&fixture.plugin("alpha")));
+    let mut manager = fixture.manager();
+    manager
+        .load_plugin_from_bytes("alpha", b"embedded")
+        .unwrap();
+    assert_eq!(manager.plugins["alpha"].state, PluginState::Enabled);
+
+    let files = temp_files(&fixture);
+    assert_eq!(files.len(), 1);
+    assert_eq!(
+        files[0].extension().unwrap(),
+        std::env::consts::DLL_EXTENSION
+    );
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = std::fs::metadata(&files[0]).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o700);
+    }
+
+    manager.unload_plugin("alpha").unwrap();
+    assert!(temp_files(&fixture).is_empty());
+}
+
+#[test]
+fn failed_load_from_bytes_removes_the_temp_file() {
+    let fixture = Fixture::new();
+    let mut manager = fixture.manager();
+    assert!(manager
+        .load_plugin_from_bytes("alpha", b"not a library")
+        .is_err());
+    assert!(temp_files(&fixture).is_empty());
+    assert!(manager.plugins.is_empty());
+}
+
+// 能力查詢
+
+#[test]
+fn plugins_with_capability_lists_only_advertisers() {
+    let fixture = Fixture::new();
+    fixture.install(
+        "pdf.so",
+        FakeLibrary::new(&fixture.plugin("pdf")).capabilities(&["can_export_pdf", "can_print"]),
+    );
+    fixture.install(
+        "printer.so",
+        FakeLibrary::new(&fixture.plugin("printer")).capabilities(&["can_print"]),
+    );
+    fixture.add(&fixture.plugin("plain"));
+    let mut manager = fixture.manager();
+    manager.load_all_plugins().unwrap();
+
+    assert_eq!(manager.plugins_with_capability("can_export_pdf"), ["pdf"]);
+    assert_eq!(
+        manager.plugins_with_capability("can_print"),
+        ["pdf", "printer"]
+    );
+    assert!(manager.plugins_with_capability("can_fly").is_empty());
+}
+
+// 事件總線一致性
+
+#[test]
+fn validate_consistency_reports_unknown_subscribers() {
+    let fixture = Fixture::new();
+    fixture.add(&fixture.plugin("alpha").subscribe(&["tick"]));
+    let mut manager = fixture.manager();
+    manager.load_all_plugins().unwrap();
+    assert!(manager.validate_consistency().is_empty());
+
+    manager.event_bus.subscribe("tick", "ghost").unwrap();
+    assert_eq!(
+        manager.validate_consistency(),
+        ["Event tick is subscribed by unknown plugin ghost"]
+    );
+}
+
+#[test]
+fn broadcast_skips_unknown_subscribers_unless_strict() {
+    let fixture = Fixture::new();
+    fixture.add(&fixture.plugin("alpha").subscribe(&["tick"]));
+    let mut manager = fixture.manager();
+    manager.load_all_plugins().unwrap();
+    manager.event_bus.subscribe("tick", "ghost").unwrap();
+
+    manager.broadcast_event(event("tick")).unwrap();
+    assert_eq!(fixture.log.count("alpha:handle_event:tick"), 1);
+
+    manager.set_strict(true);
+    let err = manager.broadcast_event(event("tick")).unwrap_err();
+    assert!(matches!(err, PluginError::EventError(msg) if msg.contains("unknown plugin ghost")));
+}
+
+// 插件設定檔
+
+#[test]
+fn activate_profile_enables_members_and_disables_the_rest() {
+    let fixture = Fixture::new();
+    for name in ["alpha", "beta", "gamma"] {
+        fixture.add(&fixture.plugin(name));
+    }
+    let mut manager = fixture.manager();
+    manager.load_all_plugins().unwrap();
+    manager.define_profile("minimal", vec!["alpha".into()]);
+    manager.define_profile("full", vec!["alpha".into(), "beta".into(), "gamma".into()]);
+
+    manager.activate_profile("minimal").unwrap();
+    assert_eq!(manager.plugins["alpha"].state, PluginState::Enabled);
+    assert_eq!(manager.plugins["beta"].state, PluginState::Disabled);
+    assert_eq!(manager.plugins["gamma"].state, PluginState::Disabled);
+
+    manager.activate_profile("full").unwrap();
+    assert!(manager
+        .plugins
+        .values()
+        .all(|entry| entry.state == PluginState::Enabled));
+}
+
+#[test]
+fn activate_profile_reports_missing_plugins_but_activates_the_rest() {
+    let fixture = Fixture::new();
+    fixture.add(&fixture.plugin("alpha"));
+    fixture.add(&fixture.plugin("beta"));
+    let mut manager = fixture.manager();
+    manager.load_all_plugins().unwrap();
+    manager.disable_plugin("beta").unwrap();
+    manager.define_profile("partial", vec!["missing".into(), "beta".into()]);
+
+    let err = manager.activate_profile("partial").unwrap_err();
+    assert!(matches!(err, PluginError::EnableError(msg) if msg.contains("missing")));
+    assert_eq!(manager.plugins["beta"].state, PluginState::Enabled);
+    assert_eq!(manager.plugins["alpha"].state, PluginState::Disabled);
+
+    assert!(matches!(
+        manager.activate_profile("undefined"),
+        Err(PluginError::ConfigError(_))
+    ));
+}
+
+#[test]
+fn profiles_persist_through_snapshots() {
+    let fixture = Fixture::new();
+    fixture.add(&fixture.plugin("alpha"));
+    let mut manager = fixture.manager();
+    manager.load_all_plugins().unwrap();
+    manager.define_profile("minimal", vec!["alpha".into()]);
+    let snapshot = manager.snapshot();
+    assert_eq!(snapshot.profiles["minimal"], ["alpha"]);
+
+    drop(manager);
+    let mut restored = fixture.manager();
+    restored.restore(&snapshot);
+    restored.activate_profile("minimal").unwrap();
+}
+
+// 事件錯誤處理
+
+#[test]
+fn event_error_handler_sees_every_failure() {
+    let fixture = Fixture::new();
+    fixture.add(
+        &fixture
+            .plugin("alpha")
+            .subscribe(&["tick"])
+            .failing("handle_event", "bad tick"),
+    );
+    let mut manager = fixture.manager();
+    manager.load_all_plugins().unwrap();
+    let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let sink = Arc::clone(&seen);
+    manager.set_event_error_handler(Box::new(move |name, event, error| {
+        sink.lock()
+            .unwrap()
+            .push(format!("{} {} {}", name, event.name, error));
+    }));
+
+    manager.broadcast_event(event("tick")).unwrap();
+    manager.broadcast_event(event("tick")).unwrap();
+    let seen = seen.lock().unwrap();
+    assert_eq!(seen.len(), 2);
+    assert!(seen[0].starts_with("alpha tick ") && seen[0].contains("bad tick"));
+}
+
+#[test]
+fn consecutive_event_failures_auto_disable_the_plugin() {
+    let fixture = Fixture::new();
+    let alpha = fixture.plugin("alpha").subscribe(&["tick"]);
+    fixture.add(&alpha);
+    let mut manager = fixture.manager();
+    manager.load_all_plugins().unwrap();
+    manager.set_auto_disable_threshold(Some(2));
+
+    // 成功處理會讓計數歸零
+    alpha.set_failing("handle_event", Some("bad tick"));
+    manager.broadcast_event(event("tick")).unwrap();
+    alpha.set_failing("handle_event", None);
+    manager.broadcast_event(event("tick")).unwrap();
+    alpha.set_failing("handle_event", Some("bad tick"));
+    manager.broadcast_event(event("tick")).unwrap();
+    assert_eq!(manager.plugins["alpha"].state, PluginState::Enabled);
+
+    manager.broadcast_event(event("tick")).unwrap();
+    assert_eq!(manager.plugins["alpha"].state, PluginState::Disabled);
+    manager.broadcast_event(event("tick")).unwrap();
+    assert_eq!(fixture.log.count("alpha:handle_event:tick"), 4);
+}
+
+// 訂閱查詢
+
+#[test]
+fn plugin_subscriptions_are_sorted_and_empty_for_unknown_plugins() {
+    let fixture = Fixture::new();
+    fixture.add(&fixture.plugin("alpha").subscribe(&["save", "load", "quit"]));
+    fixture.add(&fixture.plugin("beta").subscribe(&["load"]));
+    let mut manager = fixture.manager();
+    manager.load_all_plugins().unwrap();
+    assert_eq!(
+        manager.plugin_subscriptions("alpha"),
+        ["load", "quit", "save"]
+    );
+    assert_eq!(manager.plugin_subscriptions("beta"), ["load"]);
+    assert!(manager.plugin_subscriptions("missing").is_empty());
+    manager.unload_plugin("alpha").unwrap();
+    assert!(manager.plugin_subscriptions("alpha").is_empty());
+}
+
+// 原生清理函數
+
+#[test]
+fn unload_outcome_reports_native_cleanup() {
+    let fixture = Fixture::new();
+    fixture.add(&fixture.plugin("alpha"));
+    fixture.install(
+        "beta.so",
+        FakeLibrary::new(&fixture.plugin("beta")).without_unload(),
+    );
+    let mut manager = fixture.manager();
+    manager.load_all_plugins().unwrap();
+
+    let alpha = manager.unload_plugin("alpha").unwrap();
+    assert_eq!(
+        alpha,
+        UnloadOutcome {
+            ran_native_cleanup: true
+        }
+    );
+    let beta = manager.unload_plugin("beta").unwrap();
+    assert_eq!(
+        beta,
+        UnloadOutcome {
+            ran_native_cleanup: false
+        }
+    );
+    assert_eq!(fixture.log.order_of("on_unload"), ["alpha", "beta"]);
+}
+
+// 非同步載入
+
+#[cfg(feature = "async")]
+#[test]
+fn load_all_plugins_async_reports_loaded_and_failed_files() {
+    let fixture = Fixture::new();
+    fixture.add(&fixture.plugin("alpha"));
+    fixture.add(&fixture.plugin("beta").subscribe(&["tick"]));
+    let broken = fixture.dir.join("broken.so");
+    write_file(&broken, b"not a library");
+    let mut manager = fixture.manager();
+
+    let report = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap()
+        .block_on(manager.load_all_plugins_async())
+        .unwrap();
+    let mut loaded = report.loaded.clone();
+    loaded.sort();
+    assert_eq!(loaded, ["alpha", "beta"]);
+    assert_eq!(report.failed.len(), 1);
+    assert_eq!(report.failed[0].0, broken);
+    assert_eq!(manager.plugins["beta"].state, PluginState::Enabled);
+
+    manager.broadcast_event(event("tick")).unwrap();
+    assert_eq!(fixture.log.count("beta:handle_event:tick"), 1);
+}
+
+// 別名
+
+#[test]
+fn load_plugin_as_registers_under_the_alias() {
+    let fixture = Fixture::new();
+    let first = fixture.install(
+        "first.so",
+        FakeLibrary::new(&fixture.plugin("exporter").subscribe(&["export"])),
+    );
+    let second = fixture.install(
+        "second.so",
+        FakeLibrary::new(&fixture.plugin("exporter").subscribe(&["export"])),
+    );
+    let mut manager = fixture.manager();
+    manager.load_plugin(&first).unwrap();
+    manager.load_plugin_as(&second, "exporter-2").unwrap();
+
+    assert_eq!(manager.plugin_names(), ["exporter", "exporter-2"]);
+    assert_eq!(manager.get_plugin("exporter-2").unwrap().name(), "exporter");
+    assert_eq!(manager.plugin_subscriptions("exporter-2"), ["export"]);
+    let mut subscribers = manager.event_bus.get_subscribers("export");
+    subscribers.sort();
+    assert_eq!(subscribers, ["exporter", "exporter-2"]);
+
+    manager.broadcast_event(event("export")).unwrap();
+    assert_eq!(fixture.log.count("exporter:handle_event:export"), 2);
+    manager.disable_plugin("exporter-2").unwrap();
+    manager.unload_plugin("exporter-2").unwrap();
+    assert_eq!(manager.plugin_names(), ["exporter"]);
+    assert_eq!(manager.event_bus.get_subscribers("export"), ["exporter"]);
+}
+
+#[test]
+fn load_plugin_as_rejects_taken_and_invalid_aliases() {
+    let fixture = Fixture::new();
+    let path = fixture.add(&fixture.plugin("exporter"));
+    let mut manager = fixture.manager();
+    manager.load_plugin(&path).unwrap();
+    assert!(matches!(
+        manager.load_plugin_as(&path, "exporter"),
+        Err(PluginError::LoadError(msg)) if msg.contains("already loaded")
+    ));
+    // 已被使用的別名不會開啟動態庫
+    assert_eq!(fixture.log.count("exporter:on_load"), 1);
+    assert!(matches!(
+        manager.load_plugin_as(&path, "bad\nalias"),
+        Err(PluginError::LoadError(msg)) if msg.contains("Invalid plugin alias")
+    ));
+    assert_eq!(manager.plugin_names(), ["exporter"]);
+}
+
+#[test]
+fn loading_a_registered_name_again_keeps_the_first_instance() {
+    let fixture = Fixture::new();
+    let path = fixture.add(&fixture.plugin("exporter"));
+    let mut manager = fixture.manager();
+    manager.load_plugin(&path).unwrap();
+    assert!(matches!(
+        manager.load_plugin(&path),
+        Err(PluginError::LoadError(msg)) if msg.contains("already loaded")
+    ));
+    assert_eq!(manager.plugins["exporter"].state, PluginState::Enabled);
+    // 重複的實例已卸載，第一個實例仍在使用
+    assert_eq!(
+        fixture.log.calls(),
+        [
+            "exporter:on_load",
+            "exporter:on_enable",
+            "exporter:on_load",
+            "exporter:on_unload",
+        ]
+    );
+}
+
+// 插件檔案檢查
+
+#[test]
+fn check_plugin_file_explains_missing_and_unexecutable_files() {
+    let fixture = Fixture::new();
+    let valid = fixture.add(&fixture.plugin("alpha"));
+    let manager = fixture.manager();
+    assert_eq!(manager.check_plugin_file(&valid), PluginFileCheck::Valid);
+    assert!(manager.is_valid_plugin_file(&valid));
+    assert_eq!(
+        manager.check_plugin_file(&fixture.dir.join("missing.so")),
+        PluginFileCheck::NotAFile
+    );
+    assert_eq!(
+        manager.check_plugin_file(&fixture.dir.join("notes.txt")),
+        PluginFileCheck::WrongExtension
+    );
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+#[test]
+fn load_all_plugins_skips_unexecutable_files() {
+    use std::os::unix::fs::PermissionsExt;
+    let fixture = Fixture::new();
+    fixture.add(&fixture.plugin("alpha"));
+    let beta = fixture.add(&fixture.plugin("beta"));
+    std::fs::set_permissions(&beta, std::fs::Permissions::from_mode(0o644)).unwrap();
+    let mut manager = fixture.manager();
+    assert_eq!(
+        manager.check_plugin_file(&beta),
+        PluginFileCheck::NotExecutable
+    );
+    assert!(!manager.is_valid_plugin_file(&beta));
+    manager.load_all_plugins().unwrap();
+    assert_eq!(manager.plugin_names(), ["alpha"]);
+}
+
+// 插件優先級
+
+#[test]
+fn higher_priority_plugins_handle_events_first() {
+    let fixture = Fixture::new();
+    for (name, priority) in [("low", 1), ("high", 10), ("mid", 5)] {
+        fixture.install(
+            &format!("{}.so", name),
+            FakeLibrary::new(&fixture.plugin(name).subscribe(&["tick"])).priority(priority),
+        );
+    }
+    let mut manager = fixture.manager();
+    manager.load_all_plugins().unwrap();
+    assert_eq!(manager.plugins["high"].priority, 10);
+
+    manager.broadcast_event(event("tick")).unwrap();
+    assert_eq!(
+        fixture.log.order_of("handle_event:tick"),
+        ["high", "mid", "low"]
+    );
+}
+
+#[test]
+fn cleared_manager_can_load_from_another_directory() {
+    let fixture = Fixture::new();
+    fixture.add(&fixture.plugin("alpha").subscribe(&["tick"]));
+    let mut manager = fixture.manager();
+    manager.load_all_plugins().unwrap();
+    manager.clear().unwrap();
+    assert!(manager.plugin_names().is_empty());
+    assert!(manager.event_bus.get_subscribers("tick").is_empty());
+    assert_eq!(fixture.log.order_of("on_unload"), ["alpha"]);
+
+    let other = TempDir::new();
+    fixture
+        .loader
+        .register("beta.so", FakeLibrary::new(&fixture.plugin("beta")));
+    write_file(&other.join("beta.so"), b"beta.so");
+    manager.set_plugin_dir(other.path());
+    manager.load_all_plugins().unwrap();
+    assert_eq!(manager.plugin_names(), ["beta"]);
+}
+
+#[test]
+fn clear_aggregates_unload_errors_and_still_empties() {
+    let fixture = Fixture::new();
+    fixture.add(&fixture.plugin("alpha").failing("on_unload", "stuck"));
+    fixture.add(&fixture.plugin("beta"));
+    let mut manager = fixture.manager();
+    manager.load_all_plugins().unwrap();
+    let err = manager.clear().unwrap_err();
+    assert!(matches!(err, PluginError::LoadError(msg) if msg.contains("stuck")));
+    assert!(manager.plugin_names().is_empty());
+}
+
+// 依賴順序卸載
+
+/// 安裝 `base` <- `middle` <- `top` 的依賴鏈
+fn dependency_chain(fixture: &Fixture) {
+    fixture.add(&fixture.plugin("base"));
+    fixture.install(
+        "middle.so",
+        FakeLibrary::new(&fixture.plugin("middle")).dependencies(&["base"]),
+    );
+    fixture.install(
+        "top.so",
+        FakeLibrary::new(&fixture.plugin("top")).dependencies(&["middle"]),
+    );
+}
+
+#[test]
+fn unload_refuses_while_dependents_are_loaded() {
+    let fixture = Fixture::new();
+    dependency_chain(&fixture);
+    let mut manager = fixture.manager();
+    manager.load_all_plugins().unwrap();
+
+    let err = manager.unload_plugin("base").unwrap_err();
+    assert!(matches!(&err, PluginError::LoadError(msg) if msg.contains("required by: middle")));
+    assert_eq!(manager.plugins["base"].state, PluginState::Enabled);
+    assert!(fixture.log.order_of("on_unload").is_empty());
+
+    manager.force_unload_plugin("base").unwrap();
+    assert_eq!(manager.plugin_names(), ["middle", "top"]);
+}
+
+#[test]
+fn unload_all_plugins_runs_in_reverse_dependency_order() {
+    let fixture = Fixture::new();
+    dependency_chain(&fixture);
+    let mut manager = fixture.manager();
+    manager.load_all_plugins().unwrap();
+    manager.unload_all_plugins().unwrap();
+    assert_eq!(fixture.log.order_of("on_unload"), ["top", "middle", "base"]);
+    assert!(manager.plugin_names().is_empty());
+}
+
+// 宿主句柄
+
+#[test]
+fn plugins_broadcast_to_each_other_through_the_host_handle() {
+    let fixture = Fixture::new();
+    let sender = fixture
+        .plugin("sender")
+        .subscribe(&["ping"])
+        .broadcast_on("ping", event("pong"));
+    fixture.install(
+        "sender.so",
+        FakeLibrary::new(&sender).export("plugin_set_host"),
+    );
+    fixture.add(&fixture.plugin("receiver").subscribe(&["pong"]));
+    let mut manager = fixture.manager();
+    manager.load_all_plugins().unwrap();
+
+    manager.broadcast_event(event("ping")).unwrap();
+    assert_eq!(
+        fixture.log.calls()[fixture.log.calls().len() - 2..],
+        ["sender:handle_event:ping", "receiver:handle_event:pong"]
+    );
+
+    let host = sender.state().host.clone().unwrap();
+    assert!(host.is_plugin_loaded("receiver"));
+    manager.unload_plugin("receiver").unwrap();
+    assert!(!host.is_plugin_loaded("receiver"));
+}
+
+#[test]
+fn host_events_sent_outside_dispatch_wait_for_the_next_broadcast() {
+    let fixture = Fixture::new();
+    let sender = fixture.plugin("sender");
+    fixture.install(
+        "sender.so",
+        FakeLibrary::new(&sender).export("plugin_set_host"),
+    );
+    fixture.add(&fixture.plugin("receiver").subscribe(&["pong"]));
+    let mut manager = fixture.manager();
+    manager.load_all_plugins().unwrap();
+
+    // 其他執行緒透過句柄發送的事件只會排入佇列
+    let host = sender.state().host.clone().unwrap();
+    std::thread::spawn(move || host.broadcast(event("pong")))
+        .join()
+        .unwrap();
+    assert_eq!(fixture.log.count("receiver:handle_event:pong"), 0);
+    manager.process_host_events().unwrap();
+    assert_eq!(fixture.log.count("receiver:handle_event:pong"), 1);
+}
+
+// 替換插件
+
+#[test]
+fn swap_plugin_replaces_the_library_and_keeps_state() {
+    let fixture = Fixture::new();
+    fixture.install(
+        "exporter_v1.so",
+        FakeLibrary::new(&fixture.plugin("exporter").subscribe(&["export"])),
+    );
+    let v2 = fixture.install(
+        "exporter_v2.other",
+        FakeLibrary::new(
+            &fixture
+                .plugin("exporter")
+                .version("2.0.0")
+                .subscribe(&["export"]),
+        ),
+    );
+    let mut manager = fixture.manager();
+    manager.load_all_plugins().unwrap();
+    manager.disable_plugin("exporter").unwrap();
+
+    manager.swap_plugin("exporter", &v2).unwrap();
+    assert_eq!(manager.get_plugin("exporter").unwrap().version(), "2.0.0");
+    assert_eq!(manager.plugins["exporter"].state, PluginState::Disabled);
+    assert_eq!(manager.plugin_subscriptions("exporter"), ["export"]);
+    manager.enable_plugin("exporter").unwrap();
+    fixture.log.take();
+    manager.broadcast_event(event("export")).unwrap();
+    assert_eq!(fixture.log.calls(), ["exporter:handle_event:export"]);
+}
+
+#[test]
+fn failed_swap_leaves_the_old_plugin_untouched() {
+    let fixture = Fixture::new();
+    fixture.add(&fixture.plugin("exporter").subscribe(&["export"]));
+    let broken = fixture.install(
+        "broken.other",
+        FakeLibrary::new(
+            &fixture
+                .plugin("exporter")
+                .version("2.0.0")
+                .failing("on_enable", "broken"),
+        ),
+    );
+    let renamed = fixture.install(
+        "renamed.other",
+        FakeLibrary::new(&fixture.plugin("importer").version("3.0.0")),
+    );
+    let mut manager = fixture.manager();
+    manager.load_all_plugins().unwrap();
+
+    assert!(manager.swap_plugin("exporter", &broken).is_err());
+    assert!(manager.swap_plugin("exporter", &renamed).is_err());
+    assert!(manager
+        .swap_plugin("exporter", &fixture.dir.join("missing.so"))
+        .is_err());
+    assert_eq!(manager.get_plugin("exporter").unwrap().version(), "1.0.0");
+    assert_eq!(manager.plugins["exporter"].state, PluginState::Enabled);
+    assert_eq!(manager.plugin_subscriptions("exporter"), ["export"]);
+
+    // 名稱不同時可強制替換
+    manager.force_swap_plugin("exporter", &renamed).unwrap();
+    assert_eq!(manager.plugin_names(), ["exporter"]);
+    assert_eq!(manager.get_plugin("exporter").unwrap().version(), "3.0.0");
+}
+
+// 重入派送
+
+#[test]
+fn events_broadcast_from_handlers_are_queued_not_recursive() {
+    let fixture = Fixture::new();
+    let first = fixture
+        .plugin("first")
+        .subscribe(&["start", "follow"])
+        .broadcast_on("start", event("follow"));
+    fixture.install(
+        "first.so",
+        FakeLibrary::new(&first).export("plugin_set_host"),
+    );
+    fixture.add(&fixture.plugin("second").subscribe(&["start", "follow"]));
+    let mut manager = fixture.manager();
+    manager.load_all_plugins().unwrap();
+    fixture.log.take();
+
+    manager.broadcast_event(event("start")).unwrap();
+    assert_eq!(
+        fixture.log.calls(),
+        [
+            "first:handle_event:start",
+            "second:handle_event:start",
+            "first:handle_event:follow",
+            "second:handle_event:follow",
+        ]
+    );
+}
+
+#[test]
+fn reply_events_are_delivered_after_the_current_event() {
+    let fixture = Fixture::new();
+    fixture.add(
+        &fixture
+            .plugin("alpha")
+            .subscribe(&["ping"])
+            .reply("ping", event("pong")),
+    );
+    fixture.add(&fixture.plugin("beta").subscribe(&["ping", "pong"]));
+    let mut manager = fixture.manager();
+    manager.load_all_plugins().unwrap();
+    fixture.log.take();
+
+    manager.broadcast_event(event("ping")).unwrap();
+    assert_eq!(
+        fixture.log.calls(),
+        [
+            "alpha:handle_event:ping",
+            "beta:handle_event:ping",
+            "beta:handle_event:pong",
+        ]
+    );
+}
+
+// macOS 的插件檔案
+
+#[cfg(target_os = "macos")]
+#[test]
+fn macos_accepts_so_files_and_bundles_without_the_exec_bit() {
+    use std::os::unix::fs::PermissionsExt;
+    let fixture = Fixture::new();
+    let dylib = fixture.install("alpha.dylib", FakeLibrary::new(&fixture.plugin("alpha")));
+    let so = fixture.install("beta.so", FakeLibrary::new(&fixture.plugin("beta")));
+    std::fs::set_permissions(&so, std::fs::Permissions::from_mode(0o644)).unwrap();
+    let unreadable = fixture.install("gamma.so", FakeLibrary::new(&fixture.plugin("gamma")));
+    std::fs::set_permissions(&unreadable, std::fs::Permissions::from_mode(0o200)).unwrap();
+    let manager = fixture.manager();
+    assert_eq!(manager.check_plugin_file(&dylib), PluginFileCheck::Valid);
+    assert_eq!(manager.check_plugin_file(&so), PluginFileCheck::Valid);
+    assert_eq!(
+        manager.check_plugin_file(&unreadable),
+        PluginFileCheck::NotExecutable
+    );
+}
+
+// 具體型別
+
+/// 與假插件不同的插件型別，用於驗證型別不符的情況
+#[derive(Debug)]
+struct OtherPlugin;
+impl Plugin for OtherPlugin {
+    fn name(&self) -> &str {
+        "other"
+    }
+    fn version(&self) -> &str {
+        "1.0.0"
+    }
+    fn description(&self) -> &str {
+        "other plugin"
+    }
+    fn on_load(&self) -> Result<()> {
+        Ok(())
+    }
+    fn on_unload(&self) -> Result<()> {
+        Ok(())
+    }
+    fn handle_event(&self, _event: &Event) -> Result<Option<Event>> {
+        Ok(None)
+    }
+    fn subscribed_events(&self) -> Vec<String> {
+        Vec::new()
+    }
+}
+
+#[test]
+fn get_plugin_typed_downcasts_only_to_the_exported_type() {
+    let fixture = Fixture::new();
+    fixture.install(
+        "alpha.so",
+        FakeLibrary::new(&fixture.plugin("alpha").version("1.2.3")).export("plugin_as_any"),
+    );
+    fixture.add(&fixture.plugin("beta"));
+    let mut manager = fixture.manager();
+    manager.load_all_plugins().unwrap();
+
+    let alpha = manager.get_plugin_typed::<FakePlugin>("alpha").unwrap();
+    assert_eq!(alpha.id(), "alpha");
+    assert_eq!(Plugin::version(alpha), "1.2.3");
+    assert!(manager.get_plugin_typed::<OtherPlugin>("alpha").is_none());
+    // 未匯出 `plugin_as_any` 或插件不存在
+    assert!(manager.get_plugin_typed::<FakePlugin>("beta").is_none());
+    assert!(manager.get_plugin_typed::<FakePlugin>("missing").is_none());
+}
+
+// 延遲事件
+
+#[test]
+fn scheduled_events_fire_once_when_due() {
+    let fixture = Fixture::new();
+    fixture.add(&fixture.plugin("alpha").subscribe(&["timeout"]));
+    let mut manager = fixture.manager();
+    manager.load_all_plugins().unwrap();
+    let start = Instant::now();
+    manager.schedule_event(event("timeout"), Duration::from_secs(10));
+
+    manager.pump_scheduled(start).unwrap();
+    assert_eq!(fixture.log.count("alpha:handle_event:timeout"), 0);
+    manager
+        .pump_scheduled(start + Duration::from_secs(11))
+        .unwrap();
+    assert_eq!(fixture.log.count("alpha:handle_event:timeout"), 1);
+    manager
+        .pump_scheduled(start + Duration::from_secs(60))
+        .unwrap();
+    assert_eq!(fixture.log.count("alpha:handle_event:timeout"), 1);
+}
+
+#[test]
+fn scheduled_events_fire_in_time_order_and_die_with_their_owner() {
+    let fixture = Fixture::new();
+    fixture.add(&fixture.plugin("alpha").subscribe(&["*"]));
+    fixture.add(&fixture.plugin("owner"));
+    let mut manager = fixture.manager();
+    manager.load_all_plugins().unwrap();
+    let start = Instant::now();
+    manager.schedule_event(event("late"), Duration::from_secs(2));
+    manager.schedule_event(event("early"), Duration::from_secs(1));
+    manager.schedule_event_for("owner", event("orphan"), Duration::from_secs(1));
+    manager.unload_plugin("owner").unwrap();
+    fixture.log.take();
+
+    manager
+        .pump_scheduled(start + Duration::from_secs(5))
+        .unwrap();
+    assert_eq!(
+        fixture.log.calls(),
+        ["alpha:handle_event:early", "alpha:handle_event:late"]
+    );
+}
+
+// 狀態快照
+
+#[test]
+fn states_match_individual_lookups() {
+    let fixture = Fixture::new();
+    fixture.add(&fixture.plugin("alpha"));
+    fixture.add(&fixture.plugin("beta"));
+    fixture.add(&fixture.plugin("gamma").failing("on_enable", "boom"));
+    let mut manager = fixture.manager();
+    let _ = manager.load_all_plugins();
+    manager.disable_plugin("beta").unwrap();
+
+    let states = manager.states();
+    assert_eq!(states.len(), 3);
+    for (name, state) in &states {
+        assert_eq!(manager.state_of(name), Some(state));
+    }
+    assert_eq!(states["beta"], PluginState::Disabled);
+    assert!(matches!(states["gamma"], PluginState::Error(_)));
+}
+
+// 插件資料目錄
+
+#[test]
+fn data_dir_is_created_and_passed_to_the_plugin() {
+    let fixture = Fixture::new();
+    fixture.add(&fixture.plugin("alpha"));
+    let mut manager = fixture.manager();
+    manager.load_all_plugins().unwrap();
+
+    let dir = manager.plugin_data_dir("alpha").unwrap();
+    assert_eq!(dir, fixture.dir.join(DATA_DIR_NAME).join("alpha"));
+    assert!(dir.is_dir());
+    assert_eq!(
+        manager.plugin_config("alpha").unwrap()[DATA_DIR_CONFIG_KEY],
+        dir.to_string_lossy()
+    );
+    assert!(manager.plugin_data_dir("missing").is_none());
+}
+
+#[test]
+fn data_dir_is_stable_across_reloads_and_kept_by_default() {
+    let fixture = Fixture::new();
+    fixture.add(&fixture.plugin("alpha"));
+    let mut manager = fixture.manager();
+    manager.load_all_plugins().unwrap();
+    let dir = manager.plugin_data_dir("alpha").unwrap();
+    write_file(&dir.join("cache.bin"), b"cached");
+
+    manager.reload_plugin("alpha").unwrap();
+    assert_eq!(manager.plugin_data_dir("alpha").unwrap(), dir);
+    manager.unload_plugin("alpha").unwrap();
+    assert!(dir.join("cache.bin").exists());
+
+    manager.load_all_plugins().unwrap();
+    assert_eq!(manager.plugin_data_dir("alpha").unwrap(), dir);
+}
+
+#[test]
+fn data_dir_is_purged_on_unload_when_requested() {
+    let fixture = Fixture::new();
+    fixture.add(&fixture.plugin("alpha"));
+    let mut manager = fixture.manager();
+    manager.set_purge_data_on_unload(true);
+    manager.load_all_plugins().unwrap();
+    let dir = manager.plugin_data_dir("alpha").unwrap();
+    write_file(&dir.join("cache.bin"), b"cached");
+
+    manager.unload_plugin("alpha").unwrap();
+    assert!(!dir.exists());
+}
+
+// 釋放時不卸載
+
+#[test]
+fn leak_on_drop_skips_unload_hooks() {
+    let fixture = Fixture::new();
+    fixture.add(&fixture.plugin("alpha"));
+    let mut manager = fixture.manager();
+    manager.set_leak_on_drop(true);
+    manager.load_all_plugins().unwrap();
+    fixture.log.take();
+    drop(manager);
+    assert!(fixture.log.calls().is_empty());
+}
+
+#[test]
+fn drop_unloads_plugins_by_default() {
+    let fixture = Fixture::new();
+    fixture.add(&fixture.plugin("alpha"));
+    let mut manager = fixture.manager();
+    manager.load_all_plugins().unwrap();
+    fixture.log.take();
+    drop(manager);
+    assert_eq!(fixture.log.calls(), ["alpha:on_disable", "alpha:on_unload"]);
+}
+
+// 請求與回應
+
+#[test]
+fn request_returns_the_first_reply_by_priority() {
+    let fixture = Fixture::new();
+    for (name, priority) in [("low", 1), ("high", 10), ("silent", 20)] {
+        let mut plugin = fixture.plugin(name).subscribe(&["query"]);
+        if name != "silent" {
+            plugin = plugin.reply("query", event(&format!("answer-{}", name)));
+        }
+        fixture.install(
+            &format!("{}.so", name),
+            FakeLibrary::new(&plugin).priority(priority),
+        );
+    }
+    let mut manager = fixture.manager();
+    manager.load_all_plugins().unwrap();
+    fixture.log.take();
+
+    let reply = manager.request(event("query")).unwrap().unwrap();
+    assert_eq!(reply.name, "answer-high");
+    assert_eq!(
+        fixture.log.order_of("handle_event:query"),
+        ["silent", "high"]
+    );
+}
+
+#[test]
+fn request_without_a_reply_returns_none() {
+    let fixture = Fixture::new();
+    fixture.add(&fixture.plugin("alpha").subscribe(&["query"]));
+    fixture.add(&fixture.plugin("beta").subscribe(&["query"]));
+    let mut manager = fixture.manager();
+    manager.load_all_plugins().unwrap();
+    assert!(manager.request(event("query")).unwrap().is_none());
+    assert!(manager.request(event("unknown")).unwrap().is_none());
+    assert_eq!(
+        fixture.log.order_of("handle_event:query"),
+        ["alpha", "beta"]
+    );
+}
+
+// 事件統計
+
+#[test]
+fn event_metrics_count_events_calls_and_errors() {
+    let fixture = Fixture::new();
+    fixture.add(&fixture.plugin("alpha").subscribe(&["tick", "save"]));
+    fixture.add(
+        &fixture
+            .plugin("beta")
+            .subscribe(&["tick"])
+            .failing("handle_event", "bad"),
+    );
+    let mut manager = fixture.manager();
+    manager.load_all_plugins().unwrap();
+    for _ in 0..3 {
+        manager.broadcast_event(event("tick")).unwrap();
+    }
+    manager.broadcast_event(event("save")).unwrap();
+    manager.broadcast_event(event("nobody")).unwrap();
+
+    let metrics = manager.event_metrics();
+    assert_eq!(metrics.events_dispatched, 5);
+    assert_eq!(metrics.handler_calls, 7);
+    assert_eq!(metrics.handler_errors, 3);
+    assert_eq!(metrics.per_event["tick"], 3);
+    assert_eq!(metrics.per_event["save"], 1);
+    assert_eq!(metrics.per_event["nobody"], 1);
+
+    manager.reset_event_metrics();
+    let metrics = manager.event_metrics();
+    assert_eq!(metrics.events_dispatched, 0);
+    assert!(metrics.per_event.is_empty());
+}
+
+// 不存在的插件
+
+#[test]
+fn unload_reports_whether_a_plugin_was_removed() {
+    let fixture = Fixture::new();
+    fixture.add(&fixture.plugin("alpha"));
+    let mut manager = fixture.manager();
+    manager.load_all_plugins().unwrap();
+
+    assert!(manager.unload_plugin("alpha").is_ok());
+    for err in [
+        manager.unload_plugin("alpha").unwrap_err(),
+        manager.unload_plugin("typo").unwrap_err(),
+    ] {
+        assert!(err.to_string().contains("not loaded"), "{}", err);
+    }
+}
+
+#[test]
+fn enable_and_disable_reject_missing_plugins_like_unload() {
+    let fixture = Fixture::new();
+    let mut manager = fixture.manager();
+    assert!(matches!(
+        manager.enable_plugin("typo"),
+        Err(PluginError::EnableError(msg)) if msg.contains("not loaded")
+    ));
+    assert!(matches!(
+        manager.disable_plugin("typo"),
+        Err(PluginError::DisableError(msg)) if msg.contains("not loaded")
+    ));
+}
+
+// 副檔名
+
+#[test]
+fn custom_extensions_replace_platform_defaults() {
+    let fixture = Fixture::new();
+    let alpha = fixture.install("alpha.plug", FakeLibrary::new(&fixture.plugin("alpha")));
+    let beta = fixture.install("beta.so", FakeLibrary::new(&fixture.plugin("beta")));
+    let mut manager = fixture.manager();
+    manager.set_extensions(vec!["plug".into()]);
+    assert_eq!(manager.check_plugin_file(&alpha), PluginFileCheck::Valid);
+    assert_eq!(
+        manager.check_plugin_file(&beta),
+        PluginFileCheck::WrongExtension
+    );
+
+    manager.load_all_plugins().unwrap();
+    assert_eq!(manager.plugin_names(), ["alpha"]);
+}
+
+#[test]
+fn extensions_match_any_suffix_case_insensitively() {
+    let fixture = Fixture::new();
+    let versioned = fixture.install("libfoo.so.1", FakeLibrary::new(&fixture.plugin("foo")));
+    let upper = fixture.install("BAR.PLUG", FakeLibrary::new(&fixture.plugin("bar")));
+    let mut manager = fixture.manager();
+    manager.set_extensions(vec!["so".into(), "plug".into()]);
+    assert_eq!(
+        manager.check_plugin_file(&versioned),
+        PluginFileCheck::Valid
+    );
+    assert_eq!(manager.check_plugin_file(&upper), PluginFileCheck::Valid);
+}
+
+#[test]
+fn extensionless_files_require_opt_in() {
+    let fixture = Fixture::new();
+    let path = fixture.install("gamma", FakeLibrary::new(&fixture.plugin("gamma")));
+    let mut manager = fixture.manager();
+    assert_eq!(
+        manager.check_plugin_file(&path),
+        PluginFileCheck::WrongExtension
+    );
+    manager.set_accept_extensionless(true);
+    assert_eq!(manager.check_plugin_file(&path), PluginFileCheck::Valid);
+    manager.load_all_plugins().unwrap();
+    assert_eq!(manager.plugin_names(), ["gamma"]);
+}
+
+#[test]
+fn default_extensions_follow_the_platform() {
+    let extensions = default_plugin_extensions();
+    #[cfg(target_os = "linux")]
+    assert_eq!(extensions, ["so"]);
+    #[cfg(target_os = "macos")]
+    assert_eq!(extensions, ["dylib", "so"]);
+    #[cfg(target_os = "windows")]
+    assert_eq!(extensions, ["dll"]);
+    let fixture = Fixture::new();
+    let path = fixture.install(
+        &format!("alpha.{}", std::env::consts::DLL_EXTENSION),
+        FakeLibrary::new(&fixture.plugin("alpha")),
+    );
+    assert_eq!(
+        fixture.manager().check_plugin_file(&path),
+        PluginFileCheck::Valid
+    );
+}
+
+// 取消載入
+
+#[test]
+fn cancelled_load_keeps_plugins_loaded_before_the_signal() {
+    let fixture = Fixture::new();
+    let cancel = Arc::new(AtomicBool::new(false));
+    for name in ["alpha", "beta", "gamma"] {
+        fixture.add(&fixture.plugin(name).sets_flag("on_enable", &cancel));
+    }
+    let mut manager = fixture.manager();
+    let report = manager.load_all_plugins_cancellable(&cancel).unwrap();
+
+    assert!(report.cancelled);
+    assert_eq!(report.loaded.len(), 1);
+    assert!(report.failed.is_empty());
+    assert_eq!(manager.plugin_names(), report.loaded);
+    assert_eq!(
+        manager.state_of(&report.loaded[0]),
+        Some(&PluginState::Enabled)
+    );
+    assert_eq!(fixture.log.order_of("on_load"), report.loaded);
+}
+
+#[test]
+fn uncancelled_load_loads_everything() {
+    let fixture = Fixture::new();
+    fixture.add(&fixture.plugin("alpha"));
+    fixture.add(&fixture.plugin("beta"));
+    let mut manager = fixture.manager();
+    let report = manager
+        .load_all_plugins_cancellable(&AtomicBool::new(false))
+        .unwrap();
+    assert!(!report.cancelled);
+    assert_eq!(manager.plugin_names(), ["alpha", "beta"]);
+}
+
+// 存取動態庫
+
+/// 以假動態庫提供插件符號，並以目前行程的動態庫作為底層 `Library`
+#[cfg(unix)]
+struct ProcessLibrary {
+    fake: FakeLibrary,
+    this: Library,
+}
+#[cfg(unix)]
+impl LibraryHandle for ProcessLibrary {
+    fn symbol(&self, symbol: &[u8]) -> Option<*const ()> {
+        self.fake.symbol(symbol)
+    }
+    fn as_library(&self) -> Option<&Library> {
+        Some(&self.this)
+    }
+}
+#[cfg(unix)]
+struct ProcessLoader(FakeLibrary);
+#[cfg(unix)]
+impl PluginLoader for ProcessLoader {
+    fn load(&self, _path: &Path) -> Result<LoadedLibrary> {
+        Ok(LoadedLibrary::new(ProcessLibrary {
+            fake: self.0.clone(),
+            this: libloading::os::unix::Library::this().into(),
+        }))
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn with_library_looks_up_extra_symbols() {
+    let fixture = Fixture::new();
+    let path = fixture.add(&fixture.plugin("alpha"));
+    let mut manager = fixture.manager();
+    manager.set_loader(Box::new(ProcessLoader(FakeLibrary::new(
+        &fixture.plugin("alpha"),
+    ))));
+    manager.load_plugin(&path).unwrap();
+
+    let pid = manager
+        .with_library("alpha", |library| unsafe {
+            let getpid = library
+                .get::<unsafe extern "C" fn() -> i32>(b"getpid")
+                .unwrap();
+            getpid()
+        })
+        .unwrap();
+    assert_eq!(pid as u32, std::process::id());
+    assert!(manager.with_library("missing", |_| ()).is_none());
+}
+
+#[test]
+fn with_library_is_none_for_other_loaders() {
+    let fixture = Fixture::new();
+    fixture.add(&fixture.plugin("alpha"));
+    let mut manager = fixture.manager();
+    manager.load_all_plugins().unwrap();
+    assert!(manager.with_library("alpha", |_| ()).is_none());
+}
+
+// 輪詢更新
+
+/// 將檔案的修改時間設為未來，模擬部署工具寫入新檔案
+fn bump_mtime(path: &Path) {
+    let file = std::fs::File::options().write(true).open(path).unwrap();
+    file.set_modified(SystemTime::now() + Duration::from_secs(60))
+        .unwrap();
+}
+
+#[test]
+fn refresh_stale_reloads_only_changed_files() {
+    let fixture = Fixture::new();
+    let alpha = fixture.add(&fixture.plugin("alpha"));
+    let beta = fixture.add(&fixture.plugin("beta"));
+    let mut manager = fixture.manager();
+    manager.load_all_plugins().unwrap();
+    manager.disable_plugin("alpha").unwrap();
+
+    // alpha 換成新內容，beta 只更新修改時間
+    fixture.loader.register(
+        "alpha v2",
+        FakeLibrary::new(&fixture.plugin("alpha").version("2.0.0")),
+    );
+    write_file(&alpha, b"alpha v2");
+    bump_mtime(&alpha);
+    bump_mtime(&beta);
+    assert_eq!(manager.stale_plugins(), ["alpha"]);
+    fixture.log.take();
+
+    let report = manager.refresh_stale();
+    assert_eq!(report.loaded, ["alpha"]);
+    assert!(report.failed.is_empty());
+    assert_eq!(manager.get_plugin("alpha").unwrap().version(), "2.0.0");
+    assert_eq!(manager.state_of("alpha"), Some(&PluginState::Disabled));
+    assert_eq!(fixture.log.order_of("on_load"), ["alpha"]);
+    assert!(manager.refresh_stale().loaded.is_empty());
+}
+
+// 已知的事件名稱
+
+/// 訂閱了拼錯的事件的插件
+fn typo_fixture() -> Fixture {
+    let fixture = Fixture::new();
+    fixture.add(
+        &fixture
+            .plugin("orders")
+            .subscribe(&["ordr.created", "order.*"]),
+    );
+    fixture.add(&fixture.plugin("audit").subscribe(&["order.created"]));
+    fixture
+}
+
+fn known_events() -> HashSet<String> {
+    HashSet::from(["order.created".to_string(), "order.paid".to_string()])
+}
+
+#[test]
+fn unknown_subscriptions_only_warn_in_lenient_mode() {
+    let fixture = typo_fixture();
+    let mut manager = fixture.manager();
+    manager.set_known_events(known_events());
+    manager.load_all_plugins().unwrap();
+    assert_eq!(manager.plugin_names(), ["audit", "orders"]);
+}
+
+#[test]
+fn unknown_subscriptions_are_rejected_in_strict_mode() {
+    let fixture = typo_fixture();
+    let mut manager = fixture.manager();
+    manager.set_known_events(known_events());
+    manager.set_strict(true);
+    let err = manager.load_all_plugins().unwrap_err();
+    assert!(err.to_string().contains("unknown events: ordr.created"));
+    // 萬用字元訂閱匹配已知事件，不視為未知
+    assert!(!err.to_string().contains("order.*"));
+    assert_eq!(manager.plugin_names(), ["audit"]);
+}
+
+// 事件處理耗時
+
+#[test]
+fn handler_time_accumulates_and_the_policy_disables_slow_plugins() {
+    let fixture = Fixture::new();
+    let slow = fixture.plugin("slow").subscribe(&["tick"]);
+    slow.state().delay = Some(Duration::from_millis(15));
+    fixture.add(&slow);
+    fixture.add(&fixture.plugin("fast").subscribe(&["tick"]));
+    let mut manager = fixture.manager();
+    manager.load_all_plugins().unwrap();
+    let budget = Duration::from_millis(25);
+    manager.set_handler_time_policy(Box::new(move |_, total| total > budget));
+
+    manager.broadcast_event(event("tick")).unwrap();
+    let first = manager.plugin_cpu_time("slow").unwrap();
+    assert!(first >= Duration::from_millis(15));
+    assert_eq!(manager.state_of("slow"), Some(&PluginState::Enabled));
+
+    manager.broadcast_event(event("tick")).unwrap();
+    assert!(manager.plugin_cpu_time("slow").unwrap() >= first + Duration::from_millis(15));
+    assert_eq!(manager.state_of("slow"), Some(&PluginState::Disabled));
+    assert_eq!(manager.state_of("fast"), Some(&PluginState::Enabled));
+    assert!(manager.plugin_cpu_time("missing").is_none());
+}
+
+// 可失敗的建構函數
+
+#[test]
+fn try_create_plugin_is_preferred_and_reports_errors() {
+    let fixture = Fixture::new();
+    let ok = fixture.install(
+        "ok.so",
+        FakeLibrary::new(&fixture.plugin("ok")).fallible(None),
+    );
+    let failing = fixture.install(
+        "failing.so",
+        FakeLibrary::new(&fixture.plugin("failing")).fallible(Some("missing dependency")),
+    );
+    let mut manager = fixture.manager();
+    manager.load_plugin(&ok).unwrap();
+    let err = manager.load_plugin(&failing).unwrap_err();
+    assert!(matches!(err, PluginError::LoadError(msg) if msg.contains("missing dependency")));
+    assert_eq!(manager.plugin_names(), ["ok"]);
+    assert_eq!(fixture.log.order_of("on_load"), ["ok"]);
+}
+
+#[test]
+fn legacy_create_plugin_is_used_without_try_create_plugin() {
+    let fixture = Fixture::new();
+    fixture.add(&fixture.plugin("legacy"));
+    let mut manager = fixture.manager();
+    manager.load_all_plugins().unwrap();
+    assert_eq!(manager.state_of("legacy"), Some(&PluginState::Enabled));
+}
+
+// 從錯誤狀態恢復
+
+#[test]
+fn recover_plugin_retries_a_failed_enable() {
+    let fixture = Fixture::new();
+    let flaky = fixture.plugin("flaky").failing("on_enable", "service down");
+    fixture.add(&flaky);
+    let mut manager = fixture.manager();
+    let _ = manager.load_all_plugins();
+    assert!(matches!(
+        manager.state_of("flaky"),
+        Some(PluginState::Error(msg)) if msg.contains("service down")
+    ));
+
+    flaky.set_failing("on_enable", Some("still down"));
+    assert!(manager.recover_plugin("flaky").is_err());
+    assert!(matches!(
+        manager.state_of("flaky"),
+        Some(PluginState::Error(msg)) if msg.contains("still down")
+    ));
+
+    flaky.set_failing("on_enable", None);
+    manager.recover_plugin("flaky").unwrap();
+    assert_eq!(manager.state_of("flaky"), Some(&PluginState::Enabled));
+    // 不重新載入動態庫
+    assert_eq!(fixture.log.count("flaky:on_load"), 1);
+    assert_eq!(fixture.log.count("flaky:on_enable"), 3);
+}
+
+#[test]
+fn recover_plugin_requires_the_error_state() {
+    let fixture = Fixture::new();
+    fixture.add(&fixture.plugin("alpha"));
+    let mut manager = fixture.manager();
+    manager.load_all_plugins().unwrap();
+    assert!(matches!(
+        manager.recover_plugin("alpha"),
+        Err(PluginError::EnableError(msg)) if msg.contains("not in error state")
+    ));
+    assert!(manager.recover_plugin("missing").is_err());
+}
+
+// 指定目標發送
+
+/// 依序載入 ui、worker、other，只有 ui 與 other 訂閱 `notice`
+fn notice_fixture() -> (Fixture, PluginManager) {
+    let fixture = Fixture::new();
+    let ui = fixture.add(&fixture.plugin("ui").subscribe(&["notice"]));
+    let worker = fixture.add(&fixture.plugin("worker"));
+    let other = fixture.add(&fixture.plugin("other").subscribe(&["notice"]));
+    let mut manager = fixture.manager();
+    for path in [ui, worker, other] {
+        manager.load_plugin(&path).unwrap();
+    }
+    (fixture, manager)
+}
+
+#[test]
+fn broadcast_to_ignores_subscriptions_by_default() {
+    let (fixture, mut manager) = notice_fixture();
+    manager.disable_plugin("other").unwrap();
+    let outcome = manager
+        .broadcast_to(
+            &event("notice"),
+            &["worker", "ui", "other", "missing", "ui"],
+            false,
+        )
+        .unwrap();
+    assert_eq!(outcome.delivered, ["ui", "worker"]);
+    assert_eq!(outcome.skipped, ["other", "missing"]);
+    assert!(outcome.failed.is_empty());
+    assert_eq!(
+        fixture.log.order_of("handle_event:notice"),
+        ["ui", "worker"]
+    );
+}
+
+#[test]
+fn broadcast_to_can_require_a_subscription() {
+    let (fixture, mut manager) = notice_fixture();
+    fixture
+        .plugin("other")
+        .set_failing("handle_event", Some("busy"));
+    let outcome = manager
+        .broadcast_to(&event("notice"), &["ui", "worker"], true)
+        .unwrap();
+    assert_eq!(outcome.delivered, ["ui"]);
+    assert_eq!(outcome.skipped, ["worker"]);
+    // 未列為目標的訂閱者不會收到事件
+    assert_eq!(fixture.log.order_of("handle_event:notice"), ["ui"]);
+}
+
+// 符號連結
+
+#[cfg(unix)]
+#[test]
+fn symlinks_are_validated_by_their_target() {
+    let fixture = Fixture::new();
+    let elsewhere = TempDir::new();
+    fixture
+        .loader
+        .register("alpha library", FakeLibrary::new(&fixture.plugin("alpha")));
+    write_file(&elsewhere.join("alpha.so"), b"alpha library");
+    write_file(&elsewhere.join("notes.txt"), b"");
+    let valid = fixture.dir.join("alpha.so");
+    let not_a_plugin = fixture.dir.join("notes.so");
+    std::os::unix::fs::symlink(elsewhere.join("alpha.so"), &valid).unwrap();
+    std::os::unix::fs::symlink(elsewhere.join("notes.txt"), &not_a_plugin).unwrap();
+
+    let mut manager = fixture.manager();
+    assert_eq!(manager.check_plugin_file(&valid), PluginFileCheck::Valid);
+    assert_eq!(
+        manager.check_plugin_file(&not_a_plugin),
+        PluginFileCheck::WrongExtension
+    );
+    manager.load_all_plugins().unwrap();
+    assert_eq!(manager.plugin_names(), ["alpha"]);
+}
+
+#[cfg(unix)]
+#[test]
+fn symlinks_are_rejected_when_not_followed() {
+    let fixture = Fixture::new();
+    let target = fixture.add(&fixture.plugin("alpha"));
+    let link = fixture.dir.join("link.so");
+    std::os::unix::fs::symlink(&target, &link).unwrap();
+    let dangling = fixture.dir.join("dangling.so");
+    std::os::unix::fs::symlink(fixture.dir.join("missing.so"), &dangling).unwrap();
+
+    let mut manager = fixture.manager();
+    assert_eq!(
+        manager.check_plugin_file(&dangling),
+        PluginFileCheck::NotAFile
+    );
+    manager.set_follow_symlinks(false);
+    assert_eq!(manager.check_plugin_file(&link), PluginFileCheck::Symlink);
+    assert_eq!(manager.check_plugin_file(&target), PluginFileCheck::Valid);
+    manager.load_all_plugins().unwrap();
+    assert_eq!(manager.plugin_names(), ["alpha"]);
+    assert_eq!(fixture.log.count("alpha:on_load"), 1);
+}
+
+// 分頁查詢
+
+/// 載入 `count` 個名稱為 `plugin-00`、`plugin-01`… 的插件，偶數編號的描述為 `UI widget`
+fn many_plugins(count: usize) -> (Fixture, PluginManager) {
+    let fixture = Fixture::new();
+    for i in 0..count {
+        let plugin = fixture.plugin(&format!("plugin-{:02}", i));
+        let plugin = if i % 2 == 0 {
+            plugin.description("UI widget")
+        } else {
+            plugin
+        };
+        fixture.add(&plugin);
+    }
+    let mut manager = fixture.manager();
+    manager.load_all_plugins().unwrap();
+    (fixture, manager)
+}
+
+fn page_names(page: &PluginPage) -> Vec<&str> {
+    page.items.iter().map(|item| item.name.as_str()).collect()
+}
+
+#[test]
+fn plugin_pages_are_ordered_by_name_and_bounded() {
+    let (_fixture, manager) = many_plugins(5);
+    let first = manager.get_plugins_page(0, 2, None);
+    assert_eq!(first.total, 5);
+    assert_eq!(page_names(&first), ["plugin-00", "plugin-01"]);
+    let last = manager.get_plugins_page(4, 2, None);
+    assert_eq!(page_names(&last), ["plugin-04"]);
+    assert!(manager.get_plugins_page(5, 2, None).items.is_empty());
+    assert!(manager.get_plugins_page(0, 0, None).items.is_empty());
+    assert_eq!(manager.get_plugins_page(0, 0, None).total, 5);
+    assert_eq!(
+        manager.get_plugins_page(1, 2, None),
+        manager.get_plugins_page(1, 2, None)
+    );
+}
+
+#[test]
+fn plugin_pages_filter_name_and_description_case_insensitively() {
+    let (_fixture, manager) = many_plugins(5);
+    let widgets = manager.get_plugins_page(1, 10, Some("ui WIDGET"));
+    assert_eq!(widgets.total, 3);
+    assert_eq!(page_names(&widgets), ["plugin-02", "plugin-04"]);
+    let by_name = manager.get_plugins_page(0, 10, Some("PLUGIN-03"));
+    assert_eq!(page_names(&by_name), ["plugin-03"]);
+    assert_eq!(by_name.items[0].state, PluginState::Enabled);
+    assert_eq!(manager.get_plugins_page(0, 10, Some("nothing")).total, 0);
+}
+
+// 不相容的插件
+
+#[test]
+fn missing_create_symbol_is_incompatible() {
+    let fixture = Fixture::new();
+    let path = fixture.install("empty.so", FakeLibrary::without_create());
+    let mut manager = fixture.manager();
+    let err = manager.load_plugin(&path).unwrap_err();
+    assert!(is_incompatible_plugin(&err), "{}", err);
+    assert!(err.to_string().contains("create_plugin"));
+}
+
+#[test]
+fn io_and_open_failures_are_not_incompatible() {
+    let fixture = Fixture::new();
+    let mut manager = fixture.manager();
+    let err = manager
+        .load_plugin(&fixture.dir.join("missing.so"))
+        .unwrap_err();
+    assert!(!is_incompatible_plugin(&err), "{}", err);
+
+    // 檔案存在但載入後端無法開啟
+    let path = fixture.dir.join("garbage.so");
+    write_file(&path, b"not a library");
+    let err = manager.load_plugin(&path).unwrap_err();
+    assert!(matches!(&err, PluginError::LoadError(msg) if msg.contains("Failed to load library")));
+    assert!(!is_incompatible_plugin(&err), "{}", err);
+}
+
+// 加權輪詢
+
+/// 安裝訂閱 `job` 且優先級為 `priority` 的工作插件
+fn add_worker(fixture: &Fixture, name: &str, priority: i32) {
+    fixture.install(
+        &format!("{}.so", name),
+        FakeLibrary::new(&fixture.plugin(name).subscribe(&["job"])).priority(priority),
+    );
+}
+
+#[test]
+fn round_robin_distributes_events_evenly() {
+    let fixture = Fixture::new();
+    for name in ["w1", "w2", "w3"] {
+        add_worker(&fixture, name, 0);
+    }
+    let mut manager = fixture.manager();
+    manager.set_dispatch_mode(DispatchMode::RoundRobin);
+    manager.load_all_plugins().unwrap();
+    for _ in 0..30 {
+        manager.broadcast_event(event("job")).unwrap();
+    }
+    for name in ["w1", "w2", "w3"] {
+        assert_eq!(fixture.log.count(&format!("{}:handle_event:job", name)), 10);
+    }
+}
+
+#[test]
+fn round_robin_weights_follow_priority() {
+    let fixture = Fixture::new();
+    add_worker(&fixture, "big", 2);
+    add_worker(&fixture, "small", 1);
+    add_worker(&fixture, "tiny", 1);
+    let mut manager = fixture.manager();
+    manager.set_dispatch_mode(DispatchMode::RoundRobin);
+    manager.load_all_plugins().unwrap();
+    for _ in 0..40 {
+        manager.broadcast_event(event("job")).unwrap();
+    }
+    assert_eq!(fixture.log.count("big:handle_event:job"), 20);
+    assert_eq!(fixture.log.count("small:handle_event:job"), 10);
+    assert_eq!(fixture.log.count("tiny:handle_event:job"), 10);
+
+    // 禁用的插件不參與輪詢，個別事件可改回廣播
+    manager.disable_plugin("big").unwrap();
+    manager.broadcast_event(event("job")).unwrap();
+    manager.broadcast_event(event("job")).unwrap();
+    assert_eq!(fixture.log.count("small:handle_event:job"), 11);
+    assert_eq!(fixture.log.count("tiny:handle_event:job"), 11);
+    manager.set_event_dispatch_mode("job", Some(DispatchMode::Broadcast));
+    manager.broadcast_event(event("job")).unwrap();
+    assert_eq!(fixture.log.count("small:handle_event:job"), 12);
+    assert_eq!(fixture.log.count("tiny:handle_event:job"), 12);
+}
+
+// 健康檢查
+
+#[test]
+fn health_checks_report_each_enabled_plugin() {
+    let fixture = Fixture::new();
+    let flaky = fixture.plugin("flaky");
+    fixture.add(&fixture.plugin("plain"));
+    for plugin in [&flaky, &fixture.plugin("steady")] {
+        fixture.install(
+            &format!("{}.so", plugin.id()),
+            FakeLibrary::new(plugin).export("plugin_health_check"),
+        );
+    }
+    fixture.add(&fixture.plugin("idle"));
+    let mut manager = fixture.manager();
+    manager.load_all_plugins().unwrap();
+    manager.disable_plugin("idle").unwrap();
+    flaky.set_failing("health_check", Some("connection lost"));
+
+    let results = manager.run_health_checks();
+    let names: Vec<&str> = results.iter().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(names, ["flaky", "plain", "steady"]);
+    assert!(matches!(&results[0].1, Err(e) if e.to_string().contains("connection lost")));
+    assert!(results[1].1.is_ok() && results[2].1.is_ok());
+    // 未啟用自動禁用時只回報結果
+    assert_eq!(manager.state_of("flaky"), Some(&PluginState::Enabled));
+    assert_eq!(fixture.log.count("steady:health_check"), 1);
+}
+
+#[test]
+fn unhealthy_plugins_can_be_disabled_automatically() {
+    let fixture = Fixture::new();
+    let flaky = fixture
+        .plugin("flaky")
+        .failing("health_check", "connection lost");
+    fixture.install(
+        "flaky.so",
+        FakeLibrary::new(&flaky).export("plugin_health_check"),
+    );
+    fixture.add(&fixture.plugin("plain"));
+    let mut manager = fixture.manager();
+    manager.set_disable_unhealthy(true);
+    manager.load_all_plugins().unwrap();
+
+    assert!(manager.run_health_checks()[0].1.is_err());
+    assert_eq!(manager.state_of("flaky"), Some(&PluginState::Disabled));
+    assert_eq!(manager.state_of("plain"), Some(&PluginState::Enabled));
+    // 已禁用的插件不再檢查
+    assert_eq!(manager.run_health_checks().len(), 1);
+    assert_eq!(fixture.log.count("flaky:health_check"), 1);
+}
+
+// 目錄與特殊檔案
+
+#[test]
+fn directories_with_plugin_names_are_skipped() {
+    let fixture = Fixture::new();
+    fixture.add(&fixture.plugin("alpha"));
+    let weird = fixture.dir.join("weird.so");
+    std::fs::create_dir(&weird).unwrap();
+    let mut manager = fixture.manager();
+    assert_eq!(
+        manager.check_plugin_file(&weird),
+        PluginFileCheck::Directory
+    );
+    manager.load_all_plugins().unwrap();
+    assert_eq!(manager.plugin_names(), ["alpha"]);
+
+    // 預設的載入後端在開啟前拒絕目錄
+    let mut manager = PluginManager::new(fixture.dir.path());
+    let err = manager.load_plugin(&weird).unwrap_err();
+    assert!(matches!(err, PluginError::LoadError(msg) if msg.contains("is a directory")));
+}
+
+#[cfg(unix)]
+#[test]
+fn sockets_with_plugin_names_are_special_files() {
+    let fixture = Fixture::new();
+    let socket = fixture.dir.join("socket.so");
+    let _listener = std::os::unix::net::UnixListener::bind(&socket).unwrap();
+    let mut manager = PluginManager::new(fixture.dir.path());
+    assert_eq!(
+        manager.check_plugin_file(&socket),
+        PluginFileCheck::SpecialFile
+    );
+    let err = manager.load_plugin(&socket).unwrap_err();
+    assert!(matches!(err, PluginError::LoadError(msg) if msg.contains("not a regular file")));
+    manager.load_all_plugins().unwrap();
+    assert!(manager.plugin_names().is_empty());
+}
+
+// 事件歷史
+
+#[test]
+fn event_history_is_off_by_default() {
+    let fixture = Fixture::new();
+    fixture.add(&fixture.plugin("alpha").subscribe(&["tick"]));
+    let mut manager = fixture.manager();
+    manager.load_all_plugins().unwrap();
+    manager.broadcast_event(event("tick")).unwrap();
+    assert!(manager.event_history().is_empty());
+}
+
+#[test]
+fn event_history_keeps_the_latest_events_in_order() {
+    let fixture = Fixture::new();
+    fixture.add(&fixture.plugin("alpha").subscribe(&["a", "b", "c"]));
+    fixture.add(
+        &fixture
+            .plugin("beta")
+            .subscribe(&["c"])
+            .failing("handle_event", "boom"),
+    );
+    let mut manager = fixture.manager();
+    manager.load_all_plugins().unwrap();
+    manager.set_event_history_capacity(2);
+    for name in ["a", "b", "c"] {
+        manager.broadcast_event(event(name)).unwrap();
+    }
+
+    let history = manager.event_history();
+    let names: Vec<&str> = history.iter().map(|(e, _)| e.name.as_str()).collect();
+    assert_eq!(names, ["b", "c"]);
+    assert_eq!(history[0].1.delivered, ["alpha"]);
+    assert_eq!(history[1].1.delivered, ["alpha"]);
+    assert_eq!(history[1].1.failed.len(), 1);
+    assert_eq!(history[1].1.failed[0].0, "beta");
+
+    manager.set_event_history_capacity(1);
+    assert_eq!(manager.event_history()[0].0.name, "c");
+}
+
+// 內容雜湊
+
+#[test]
+fn changed_bytes_with_the_same_mtime_are_assumed_unchanged() {
+    let fixture = Fixture::new();
+    let alpha = fixture.add(&fixture.plugin("alpha"));
+    let mut manager = fixture.manager();
+    manager.load_all_plugins().unwrap();
+
+    // 修改時間未改變時不讀取檔案內容
+    let modified = std::fs::metadata(&alpha).unwrap().modified().unwrap();
+    fixture.loader.register(
+        "alpha v2",
+        FakeLibrary::new(&fixture.plugin("alpha").version("2.0.0")),
+    );
+    write_file(&alpha, b"alpha v2");
+    std::fs::File::options()
+        .write(true)
+        .open(&alpha)
+        .unwrap()
+        .set_modified(modified)
+        .unwrap();
+    assert!(manager.stale_plugins().is_empty());
+
+    bump_mtime(&alpha);
+    assert_eq!(manager.stale_plugins(), ["alpha"]);
+    assert_eq!(manager.refresh_stale().loaded, ["alpha"]);
+    assert_eq!(manager.get_plugin("alpha").unwrap().version(), "2.0.0");
+}
+
+// 合併管理器
+
+#[test]
+fn merge_moves_plugins_without_reloading_them() {
+    let fixture = Fixture::new();
+    let alpha = fixture.add(&fixture.plugin("alpha").subscribe(&["tick"]));
+    let beta = fixture.add(&fixture.plugin("beta").subscribe(&["tick"]));
+    let mut primary = fixture.manager();
+    primary.load_plugin(&alpha).unwrap();
+    let mut other = fixture.manager();
+    other.load_plugin(&beta).unwrap();
+    other.disable_plugin("beta").unwrap();
+    fixture.log.take();
+
+    let report = primary.merge(other);
+    assert_eq!(report.loaded, ["beta"]);
+    assert!(report.failed.is_empty());
+    // 釋放 `other` 不會卸載已轉移的插件
+    assert!(fixture.log.calls().is_empty());
+    assert_eq!(primary.plugin_names(), ["alpha", "beta"]);
+    assert_eq!(primary.state_of("beta"), Some(&PluginState::Disabled));
+
+    primary.enable_plugin("beta").unwrap();
+    primary.broadcast_event(event("tick")).unwrap();
+    assert_eq!(fixture.log.order_of("handle_event:tick"), ["alpha", "beta"]);
+}
+
+#[test]
+fn merge_keeps_the_existing_plugin_on_a_name_collision() {
+    let fixture = Fixture::new();
+    let alpha = fixture.add(&fixture.plugin("alpha"));
+    let beta = fixture.add(&fixture.plugin("beta"));
+    let mut primary = fixture.manager();
+    primary.load_plugin(&alpha).unwrap();
+    let mut other = fixture.manager();
+    other.load_plugin(&alpha).unwrap();
+    other.load_plugin(&beta).unwrap();
+    fixture.log.take();
+
+    let report = primary.merge(other);
+    assert_eq!(report.loaded, ["beta"]);
+    assert_eq!(report.failed.len(), 1);
+    assert_eq!(report.failed[0].0, alpha);
+    assert!(report.failed[0].1.contains("already loaded"));
+    // 未轉移的插件隨 `other` 釋放而卸載，此管理器的插件不受影響
+    assert_eq!(fixture.log.order_of("on_unload"), ["alpha"]);
+    assert_eq!(primary.state_of("alpha"), Some(&PluginState::Enabled));
+    assert_eq!(fixture.log.count("beta:on_unload"), 0);
+}
+
+// 事件與載入錯誤的來源插件
+
+#[test]
+fn event_and_load_errors_name_the_plugin() {
+    let fixture = Fixture::new();
+    fixture.add(
+        &fixture
+            .plugin("alpha")
+            .subscribe(&["tick"])
+            .failing("handle_event", "boom"),
+    );
+    let broken = fixture.add(&fixture.plugin("broken").failing("on_load", "no config"));
+    let mut manager = fixture.manager();
+    let err = manager.load_plugin(&broken).unwrap_err();
+    assert_eq!(error_plugin(&err), Some("broken"));
+
+    manager.load_all_plugins().unwrap_err();
+    let outcome = manager
+        .broadcast_to(&event("tick"), &["alpha"], false)
+        .unwrap();
+    assert_eq!(outcome.failed.len(), 1);
+    assert!(
+        outcome.failed[0].1.contains("[alpha] boom"),
+        "{:?}",
+        outcome
+    );
+}
+
+// 錯誤的來源插件
+
+#[test]
+fn hook_errors_are_prefixed_even_when_they_start_with_brackets() {
+    let fixture = Fixture::new();
+    fixture.add(
+        &fixture
+            .plugin("alpha")
+            .failing("on_enable", "[db] connection refused"),
+    );
+    let mut manager = fixture.manager();
+    manager.set_auto_enable(false);
+    manager.load_all_plugins().unwrap();
+    let err = manager.enable_plugin("alpha").unwrap_err();
+    assert_eq!(error_plugin(&err), Some("alpha"));
+    assert!(matches!(
+        &err,
+        PluginError::EnableError(msg) if msg == "[alpha] [db] connection refused"
+    ));
+}
+
+#[test]
+fn manager_errors_name_the_plugin_once() {
+    let fixture = Fixture::new();
+    fixture.add(&fixture.plugin("alpha"));
+    let mut manager = fixture.manager();
+    manager.set_auto_enable(false);
+    manager.load_all_plugins().unwrap();
+    let err = manager.disable_plugin("alpha").unwrap_err();
+    assert_eq!(error_plugin(&err), Some("alpha"));
+    assert_eq!(
+        err.to_string(),
+        "Plugin disable error: [alpha] Can't disable plugin"
+    );
+    assert_eq!(
+        error_plugin(&PluginError::LoadError("no name".into())),
+        None
+    );
+}
+
+// 待處理事件的優先級
+
+#[test]
+fn pending_events_drain_highest_priority_first() {
+    let fixture = Fixture::new();
+    let alpha = fixture
+        .plugin("alpha")
+        .subscribe(&["low", "mid", "high", "mid-later"]);
+    fixture.install(
+        "alpha.so",
+        FakeLibrary::new(&alpha).export("plugin_set_host"),
+    );
+    let mut manager = fixture.manager();
+    manager.load_all_plugins().unwrap();
+    let host = alpha.state().host.clone().unwrap();
+    for (name, priority) in [("low", 1), ("mid", 5), ("high", 9), ("mid-later", 5)] {
+        host.broadcast(Event {
+            priority,
+            ..event(name)
+        });
+    }
+    fixture.log.take();
+
+    manager.process_host_events().unwrap();
+    assert_eq!(
+        fixture.log.calls(),
+        [
+            "alpha:handle_event:high",
+            "alpha:handle_event:mid",
+            "alpha:handle_event:mid-later",
+            "alpha:handle_event:low",
+        ]
+    );
+}
+
+// 心跳
+
+#[test]
+fn stale_heartbeats_only_include_plugins_that_checked_in() {
+    let fixture = Fixture::new();
+    let alpha = fixture.plugin("alpha");
+    fixture.install(
+        "alpha.so",
+        FakeLibrary::new(&alpha).export("plugin_set_host"),
+    );
+    fixture.add(&fixture.plugin("beta"));
+    let mut manager = fixture.manager();
+    manager.load_all_plugins().unwrap();
+    assert!(manager.plugin_last_heartbeat("alpha").is_none());
+
+    let before = Instant::now();
+    alpha.state().host.clone().unwrap().heartbeat();
+    let last = manager.plugin_last_heartbeat("alpha").unwrap();
+    assert!(last >= before);
+    assert!(manager.stale_heartbeats(Duration::from_secs(60)).is_empty());
+
+    std::thread::sleep(Duration::from_millis(30));
+    assert_eq!(
+        manager.stale_heartbeats(Duration::from_millis(10)),
+        ["alpha"]
+    );
+    alpha.state().host.clone().unwrap().heartbeat();
+    assert!(manager
+        .stale_heartbeats(Duration::from_millis(10))
+        .is_empty());
+    assert!(manager.plugin_last_heartbeat("beta").is_none());
+    assert!(manager.plugin_last_heartbeat("missing").is_none());
+}
+
+// 安全模式
+
+#[test]
+fn safe_mode_disables_enabled_plugins_until_cleared() {
+    let fixture = Fixture::new();
+    fixture.add(&fixture.plugin("good"));
+    fixture.add(&fixture.plugin("bad1").failing("on_enable", "boom"));
+    fixture.add(&fixture.plugin("bad2").failing("on_enable", "boom"));
+    let mut manager = fixture.manager();
+    manager.set_safe_mode_threshold(Some((2, Duration::from_secs(60))));
+    manager.set_auto_enable(false);
+    manager.load_all_plugins().unwrap();
+
+    manager.enable_plugin("good").unwrap();
+    assert!(manager.enable_plugin("bad1").is_err());
+    assert!(!manager.is_safe_mode());
+    assert!(manager.enable_plugin("bad2").is_err());
+    assert!(manager.is_safe_mode());
+    assert_eq!(manager.state_of("good"), Some(&PluginState::Disabled));
+    assert!(matches!(
+        manager.state_of("bad1"),
+        Some(PluginState::Error(_))
+    ));
+    assert!(matches!(
+        manager.state_of("bad2"),
+        Some(PluginState::Error(_))
+    ));
+
+    let err = manager.enable_plugin("good").unwrap_err();
+    assert!(matches!(err, PluginError::EnableError(msg) if msg.contains("safe mode")));
+    assert_eq!(manager.state_of("good"), Some(&PluginState::Disabled));
+
+    manager.clear_safe_mode();
+    assert!(!manager.is_safe_mode());
+    manager.enable_plugin("good").unwrap();
+    assert_eq!(manager.state_of("good"), Some(&PluginState::Enabled));
+}
+
+#[test]
+fn safe_mode_disables_dependents_first() {
+    let fixture = Fixture::new();
+    fixture.add(&fixture.plugin("base"));
+    fixture.install(
+        "consumer.so",
+        FakeLibrary::new(&fixture.plugin("consumer")).dependencies(&["base"]),
+    );
+    fixture.add(&fixture.plugin("bad").failing("on_enable", "boom"));
+    let mut manager = fixture.manager();
+    manager.set_strict(true);
+    manager.set_safe_mode_threshold(Some((1, Duration::from_secs(60))));
+    manager.set_auto_enable(false);
+    manager.load_all_plugins().unwrap();
+    manager.enable_plugin("base").unwrap();
+    manager.enable_plugin("consumer").unwrap();
+
+    assert!(manager.enable_plugin("bad").is_err());
+    assert!(manager.is_safe_mode());
+    assert_eq!(fixture.log.order_of("on_disable"), ["consumer", "base"]);
+}
+
+// 以索引存取插件
+
+#[test]
+fn plugin_indices_follow_plugin_names() {
+    let fixture = Fixture::new();
+    for name in ["gamma", "alpha", "beta"] {
+        fixture.add(&fixture.plugin(name));
+    }
+    let mut manager = fixture.manager();
+    manager.load_all_plugins().unwrap();
+    for (index, name) in manager.plugin_names().iter().enumerate() {
+        let (at, plugin) = manager.plugin_at(index).unwrap();
+        assert_eq!(at, name);
+        assert_eq!(plugin.name(), name);
+        assert_eq!(manager.plugin_index(name), Some(index));
+    }
+    assert_eq!(
+        manager.plugin_at(0).unwrap().0,
+        manager.plugin_at(0).unwrap().0
+    );
+    assert!(manager.plugin_at(3).is_none());
+    assert!(manager.plugin_index("missing").is_none());
+
+    manager.unload_plugin("alpha").unwrap();
+    assert_eq!(manager.plugin_at(0).unwrap().0, "beta");
+    assert_eq!(manager.plugin_index("gamma"), Some(1));
+}
+
+// 延遲初始化
+
+#[test]
+fn lazy_plugins_initialize_once_on_the_first_event() {
+    let fixture = Fixture::new();
+    fixture.add(&fixture.plugin("alpha").subscribe(&["tick"]));
+    fixture.add(&fixture.plugin("idle").subscribe(&["other"]));
+    let mut manager = fixture.manager();
+    manager.set_lazy_init(true);
+    manager.load_all_plugins().unwrap();
+    assert!(fixture.log.calls().is_empty());
+    assert_eq!(manager.state_of("alpha"), Some(&PluginState::Loaded));
+
+    manager.broadcast_event(event("tick")).unwrap();
+    manager.broadcast_event(event("tick")).unwrap();
+    assert_eq!(
+        fixture.log.calls(),
+        [
+            "alpha:on_load",
+            "alpha:on_enable",
+            "alpha:handle_event:tick",
+            "alpha:handle_event:tick",
+        ]
+    );
+    assert_eq!(manager.state_of("alpha"), Some(&PluginState::Enabled));
+
+    // 從未收到事件的插件卸載時不調用 `on_unload`
+    manager.unload_plugin("idle").unwrap();
+    assert_eq!(fixture.log.count("idle:on_unload"), 0);
+    assert_eq!(fixture.log.count("idle:on_load"), 0);
+}
+
+#[test]
+fn failed_lazy_initialization_puts_the_plugin_in_error() {
+    let fixture = Fixture::new();
+    let alpha = fixture
+        .plugin("alpha")
+        .subscribe(&["tick"])
+        .failing("on_load", "no database");
+    fixture.add(&alpha);
+    let mut manager = fixture.manager();
+    manager.set_lazy_init(true);
+    manager.load_all_plugins().unwrap();
+    manager.broadcast_event(event("tick")).unwrap();
+    assert!(matches!(
+        manager.state_of("alpha"),
+        Some(PluginState::Error(_))
+    ));
+    assert_eq!(fixture.log.count("alpha:handle_event:tick"), 0);
+
+    alpha.set_failing("on_load", None);
+    manager.recover_plugin("alpha").unwrap();
+    assert_eq!(manager.state_of("alpha"), Some(&PluginState::Enabled));
+    assert_eq!(fixture.log.order_of("on_load").len(), 2);
+}
+
+// 卸載時的借用檢查
+
+#[cfg(unix)]
+#[test]
+fn library_access_is_counted_as_an_in_flight_borrow() {
+    let fixture = Fixture::new();
+    let path = fixture.add(&fixture.plugin("alpha"));
+    let mut manager = fixture.manager();
+    manager.set_loader(Box::new(ProcessLoader(FakeLibrary::new(
+        &fixture.plugin("alpha"),
+    ))));
+    manager.load_plugin(&path).unwrap();
+    let during = manager
+        .with_library("alpha", |_| manager.plugins["alpha"].in_flight.get())
+        .unwrap();
+    assert_eq!(during, 1);
+    assert_eq!(manager.plugins["alpha"].in_flight.get(), 0);
+    manager.unload_plugin("alpha").unwrap();
+}
+
+#[cfg(debug_assertions)]
+#[test]
+#[should_panic(expected = "plugin alpha was unloaded while still borrowed")]
+fn unloading_a_borrowed_plugin_panics_in_debug_builds() {
+    let fixture = Fixture::new();
+    fixture.add(&fixture.plugin("alpha"));
+    let mut manager = fixture.manager();
+    manager.load_all_plugins().unwrap();
+    // 模擬經由原始指針在借用期間卸載插件
+    let entry = manager.plugins.remove("alpha").unwrap();
+    entry.in_flight.set(1);
+    let _ = PluginManager::finalize_entry(entry);
+}
+
+// 批次派送
+
+/// 載入訂閱不同事件組合的插件，並記錄事件歷史
+fn batch_fixture() -> (Fixture, PluginManager) {
+    let fixture = Fixture::new();
+    fixture.add(&fixture.plugin("alpha").subscribe(&["a", "b"]));
+    fixture.add(
+        &fixture
+            .plugin("beta")
+            .subscribe(&["b"])
+            .failing("handle_event", "boom"),
+    );
+    fixture.add(&fixture.plugin("gamma").subscribe(&["a", "*"]));
+    let mut manager = fixture.manager();
+    manager.load_all_plugins().unwrap();
+    manager.set_event_history_capacity(16);
+    fixture.log.take();
+    (fixture, manager)
+}
+
+#[test]
+fn broadcast_batch_matches_single_broadcasts() {
+    let events = || {
+        ["a", "b", "a", "c", "b"]
+            .into_iter()
+            .enumerate()
+            .map(|(i, name)| {
+                let mut event = event(name);
+                event.data.insert("seq".into(), i.to_string());
+                event
+            })
+            .collect::<Vec<_>>()
+    };
+    let (single_fixture, mut single) = batch_fixture();
+    for event in events() {
+        single.broadcast_event(event).unwrap();
+    }
+    let (batch_fixture, mut batch) = batch_fixture();
+    let outcomes = batch.broadcast_batch(events()).unwrap();
+
+    assert_eq!(batch_fixture.log.calls(), single_fixture.log.calls());
+    let expected: Vec<BroadcastOutcome> = single
+        .event_history()
+        .into_iter()
+        .map(|(_, outcome)| outcome)
+        .collect();
+    assert_eq!(outcomes, expected);
+    assert_eq!(outcomes[1].delivered, ["alpha", "gamma"]);
+    assert_eq!(outcomes[1].failed[0].0, "beta");
+    assert_eq!(outcomes[3].delivered, ["gamma"]);
+    assert_eq!(batch.event_metrics(), single.event_metrics());
+}
+
+// 資源用量
+
+/// 安裝回報指定資源用量的插件
+fn add_with_usage(fixture: &Fixture, name: &str, approx_bytes: u64, open_handles: u32) {
+    let plugin = fixture.plugin(name);
+    plugin.state().usage = ResourceUsage {
+        approx_bytes,
+        open_handles,
+    };
+    fixture.install(
+        &format!("{}.so", name),
+        FakeLibrary::new(&plugin).export("plugin_resource_usage"),
+    );
+}
+
+#[test]
+fn total_resource_usage_sums_enabled_plugins() {
+    let fixture = Fixture::new();
+    add_with_usage(&fixture, "alpha", 1024, 2);
+    add_with_usage(&fixture, "beta", 4096, 3);
+    add_with_usage(&fixture, "gamma", 1 << 20, 10);
+    fixture.add(&fixture.plugin("silent"));
+    let mut manager = fixture.manager();
+    manager.load_all_plugins().unwrap();
+    manager.disable_plugin("gamma").unwrap();
+
+    assert_eq!(
+        manager.total_resource_usage(),
+        ResourceUsage {
+            approx_bytes: 5120,
+            open_handles: 5,
+        }
+    );
+    assert_eq!(
+        manager.plugin_resource_usage("gamma").unwrap().open_handles,
+        10
+    );
+    assert_eq!(
+        manager.plugin_resource_usage("silent"),
+        Some(ResourceUsage::default())
+    );
+    assert!(manager.plugin_resource_usage("missing").is_none());
+}
+
+// 具名通道
+
+#[test]
+fn plugins_exchange_events_over_a_named_channel() {
+    let fixture = Fixture::new();
+    let producer = fixture.plugin("producer");
+    let consumer = fixture.plugin("consumer");
+    for plugin in [&producer, &consumer] {
+        fixture.install(
+            &format!("{}.so", plugin.id()),
+            FakeLibrary::new(plugin).export("plugin_set_host"),
+        );
+    }
+    let mut manager = fixture.manager();
+    manager.load_all_plugins().unwrap();
+    manager.create_channel("pipe").unwrap();
+    assert!(manager.create_channel("pipe").is_err());
+
+    let sender = producer
+        .state()
+        .host
+        .clone()
+        .unwrap()
+        .sender("pipe")
+        .unwrap();
+    let consumer_host = consumer.state().host.clone().unwrap();
+    let receiver = consumer_host.receiver("pipe").unwrap();
+    // 接收端只能取得一次
+    assert!(consumer_host.receiver("pipe").is_none());
+    sender.send(event("job")).unwrap();
+    assert_eq!(receiver.try_recv().unwrap().name, "job");
+    // 通道不經過全域廣播
+    assert!(fixture
+        .log
+        .calls()
+        .iter()
+        .all(|call| !call.contains("handle_event")));
+
+    // 取得過端點的插件卸載時，通道從登記中移除
+    assert_eq!(manager.channel_names(), ["pipe"]);
+    manager.unload_plugin("consumer").unwrap();
+    assert!(manager.channel_names().is_empty());
+    assert!(producer
+        .state()
+        .host
+        .clone()
+        .unwrap()
+        .sender("pipe")
+        .is_none());
+}
+
+// 模擬派送
+
+#[test]
+fn dry_run_matches_the_real_delivery_order() {
+    let fixture = Fixture::new();
+    for (name, priority) in [("low", 1), ("high", 10), ("mid", 5), ("off", 20)] {
+        fixture.install(
+            &format!("{}.so", name),
+            FakeLibrary::new(&fixture.plugin(name).subscribe(&["tick"])).priority(priority),
+        );
+    }
+    fixture.add(&fixture.plugin("other").subscribe(&["tock"]));
+    let mut manager = fixture.manager();
+    manager.load_all_plugins().unwrap();
+    manager.disable_plugin("off").unwrap();
+    fixture.log.take();
+
+    let planned = manager.broadcast_event_dry_run(&event("tick"));
+    assert_eq!(planned, ["high", "mid", "low"]);
+    // 模擬派送不調用任何插件
+    assert!(fixture.log.calls().is_empty());
+    manager.broadcast_event(event("tick")).unwrap();
+    assert_eq!(fixture.log.order_of("handle_event:tick"), planned);
+    assert!(manager.broadcast_event_dry_run(&event("none")).is_empty());
+}
+
+// 最少插件數量
+
+#[test]
+fn empty_directory_succeeds_without_a_minimum() {
+    let fixture = Fixture::new();
+    let mut manager = fixture.manager();
+    manager.load_all_plugins().unwrap();
+    assert!(manager.plugin_names().is_empty());
+}
+
+#[test]
+fn too_few_plugins_fail_load_all_with_a_minimum() {
+    let fixture = Fixture::new();
+    let mut manager = fixture.manager();
+    manager.set_require_at_least(1);
+    assert!(matches!(
+        manager.load_all_plugins(),
+        Err(PluginError::LoadError(_))
+    ));
+
+    fixture.add(&fixture.plugin("alpha"));
+    fixture.add(&fixture.plugin("beta").failing("on_load", "boom"));
+    let mut manager = fixture.manager();
+    manager.set_require_at_least(2);
+    assert!(manager.load_all_plugins().is_err());
+    let mut manager = fixture.manager();
+    manager.set_require_at_least(1);
+    // beta 載入失敗仍會返回錯誤，但 alpha 已達到最少數量
+    let _ = manager.load_all_plugins();
+    assert_eq!(manager.plugin_names(), ["alpha"]);
+}
+
+// 執行期間更新設定
+
+#[test]
+fn update_plugin_config_calls_the_change_hook() {
+    let fixture = Fixture::new();
+    let alpha = fixture.plugin("alpha");
+    fixture.install(
+        "alpha.so",
+        FakeLibrary::new(&alpha).export("plugin_config_change"),
+    );
+    fixture.add(&fixture.plugin("plain"));
+    let mut manager = fixture.manager();
+    manager.load_all_plugins().unwrap();
+
+    let config = HashMap::from([("log_level".to_string(), "debug".to_string())]);
+    manager
+        .update_plugin_config("alpha", config.clone())
+        .unwrap();
+    let received = alpha.state().config.clone().unwrap();
+    assert_eq!(received["log_level"], "debug");
+    assert!(received.contains_key(DATA_DIR_CONFIG_KEY));
+    assert_eq!(
+        manager.plugin_config("alpha").unwrap()["log_level"],
+        "debug"
+    );
+    assert_eq!(fixture.log.count("alpha:config_change"), 1);
+
+    // 未匯出鉤子的插件只保存設定
+    manager.update_plugin_config("plain", config).unwrap();
+    assert_eq!(
+        manager.plugin_config("plain").unwrap()["log_level"],
+        "debug"
+    );
+    assert!(matches!(
+        manager.update_plugin_config("missing", HashMap::new()),
+        Err(PluginError::ConfigError(_))
+    ));
+}
+
+#[test]
+fn rejected_config_changes_keep_the_old_config() {
+    let fixture = Fixture::new();
+    let alpha = fixture.plugin("alpha");
+    fixture.install(
+        "alpha.so",
+        FakeLibrary::new(&alpha).export("plugin_config_change"),
+    );
+    let mut manager = fixture.manager();
+    manager.load_all_plugins().unwrap();
+    alpha.set_failing("config_change", Some("invalid threshold"));
+    let config = HashMap::from([("threshold".to_string(), "-1".to_string())]);
+    let err = manager.update_plugin_config("alpha", config).unwrap_err();
+    assert_eq!(error_plugin(&err), Some("alpha"));
+    assert!(!manager
+        .plugin_config("alpha")
+        .unwrap()
+        .contains_key("threshold"));
+}
+
+// 原生清理函數的檢查
+
+#[test]
+fn native_cleanup_is_known_at_load_time() {
+    let fixture = Fixture::new();
+    fixture.add(&fixture.plugin("alpha"));
+    fixture.install(
+        "bare.so",
+        FakeLibrary::new(&fixture.plugin("bare")).without_unload(),
+    );
+    let mut manager = fixture.manager();
+    manager.load_all_plugins().unwrap();
+    assert_eq!(manager.plugin_has_native_cleanup("alpha"), Some(true));
+    assert_eq!(manager.plugin_has_native_cleanup("bare"), Some(false));
+    assert_eq!(manager.plugin_has_native_cleanup("missing"), None);
+    assert!(!manager.unload_plugin("bare").unwrap().ran_native_cleanup);
+    assert!(manager.unload_plugin("alpha").unwrap().ran_native_cleanup);
+}
+
+// 啟用重試
+
+#[test]
+fn enable_with_retry_succeeds_on_the_third_attempt() {
+    let fixture = Fixture::new();
+    fixture.add(
+        &fixture
+            .plugin("alpha")
+            .failing_times("on_enable", 2, "db not ready"),
+    );
+    let mut manager = fixture.manager();
+    manager.set_auto_enable(false);
+    manager.load_all_plugins().unwrap();
+
+    let started = Instant::now();
+    manager
+        .enable_plugin_with_retry("alpha", 5, Duration::from_millis(5))
+        .unwrap();
+    // 兩次重試前分別等待 5ms 與 10ms
+    assert!(started.elapsed() >= Duration::from_millis(15));
+    assert_eq!(manager.state_of("alpha"), Some(&PluginState::Enabled));
+    assert_eq!(fixture.log.count("alpha:on_enable"), 3);
+    assert_eq!(fixture.log.count("alpha:on_load"), 1);
+}
+
+#[test]
+fn enable_with_retry_ends_in_error_after_the_last_attempt() {
+    let fixture = Fixture::new();
+    fixture.add(&fixture.plugin("alpha").failing("on_enable", "db down"));
+    let mut manager = fixture.manager();
+    manager.set_auto_enable(false);
+    manager.load_all_plugins().unwrap();
+    let err = manager
+        .enable_plugin_with_retry("alpha", 3, Duration::from_millis(1))
+        .unwrap_err();
+    assert!(err.to_string().contains("db down"));
+    assert!(matches!(
+        manager.state_of("alpha"),
+        Some(PluginState::Error(_))
+    ));
+    assert_eq!(fixture.log.count("alpha:on_enable"), 3);
+}
+
+// 多插件動態庫
+
+#[test]
+fn multi_plugin_library_registers_every_plugin() {
+    let fixture = Fixture::new();
+    let alpha = fixture.plugin("alpha").subscribe(&["tick"]);
+    let beta = fixture.plugin("beta").subscribe(&["tick"]);
+    alpha.state().priority = Some(1);
+    alpha.state().dependencies = Some(vec!["beta".into()]);
+    beta.state().priority = Some(5);
+    let path = fixture.install(
+        "bundle.so",
+        FakeLibrary::multi(&[&alpha, &beta])
+            .export("plugin_priority")
+            .export("plugin_dependencies"),
+    );
+    let mut manager = fixture.manager();
+    manager.load_plugin(&path).unwrap();
+    assert_eq!(manager.plugin_names(), ["alpha", "beta"]);
+
+    // 優先級與依賴以插件實例查詢，每個插件有各自的值
+    assert_eq!(manager.dependencies_of("alpha"), ["beta"]);
+    assert!(manager.dependencies_of("beta").is_empty());
+    manager.broadcast_event(event("tick")).unwrap();
+    assert_eq!(fixture.log.order_of("handle_event:tick"), ["beta", "alpha"]);
+    fixture.log.take();
+
+    // 每個插件有各自的狀態與訂閱
+    manager.disable_plugin("alpha").unwrap();
+    manager.broadcast_event(event("tick")).unwrap();
+    assert_eq!(fixture.log.order_of("handle_event:tick"), ["beta"]);
+
+    // 動態庫在最後一個插件卸載時才執行清理
+    assert!(!manager.unload_plugin("alpha").unwrap().ran_native_cleanup);
+    assert_eq!(manager.state_of("beta"), Some(&PluginState::Enabled));
+    assert!(manager.unload_plugin("beta").unwrap().ran_native_cleanup);
+}
+
+// 插件關係圖
+
+#[test]
+fn export_graph_connects_publishers_events_and_subscribers() {
+    let fixture = Fixture::new();
+    let shop = fixture.plugin("shop");
+    shop.state().published = vec!["order.created".into()];
+    fixture.install(
+        "shop.so",
+        FakeLibrary::new(&shop).export("plugin_published_events"),
+    );
+    fixture.add(&fixture.plugin("mailer").subscribe(&["order.created"]));
+    fixture.add(&fixture.plugin("audit").subscribe(&["order.*"]));
+    fixture.add(&fixture.plugin("lonely").subscribe(&["orphan"]));
+    let mut manager = fixture.manager();
+    manager.load_all_plugins().unwrap();
+
+    let graph = manager.export_graph();
+    assert!(graph.starts_with("digraph plugins {"));
+    for line in [
+        "\"plugin:shop\" [label=\"shop\", shape=box];",
+        "\"event:order.created\" [label=\"order.created\", shape=ellipse];",
+        "\"event:orphan\" [label=\"orphan\", shape=ellipse, style=dashed, color=red];",
+        "\"plugin:shop\" -> \"event:order.created\";",
+        "\"event:order.created\" -> \"plugin:mailer\";",
+        "\"event:order.created\" -> \"event:order.*\" [style=dashed];",
+        "\"event:order.*\" -> \"plugin:audit\";",
+        "\"event:orphan\" -> \"plugin:lonely\";",
+    ] {
+        assert!(
+            graph.lines().any(|l| l.trim() == line),
+            "{}\n{}",
+            line,
+            graph
+        );
+    }
+    assert_eq!(graph, manager.export_graph());
+}
+
+// 損壞的插件實例
+
+/// 讀取名稱時 panic 的插件
+#[derive(Debug)]
+struct PanickingPlugin;
+impl Plugin for PanickingPlugin {
+    fn name(&self) -> &str {
+        panic!("corrupted vtable")
+    }
+    fn version(&self) -> &str {
+        "1.0.0"
+    }
+    fn description(&self) -> &str {
+        ""
+    }
+    fn on_load(&self) -> Result<()> {
+        Ok(())
+    }
+    fn on_unload(&self) -> Result<()> {
+        Ok(())
+    }
+    fn handle_event(&self, _event: &Event) -> Result<Option<Event>> {
+        Ok(None)
+    }
+    fn subscribed_events(&self) -> Vec<String> {
+        Vec::new()
+    }
+}
+
+#[test]
+fn plugins_with_empty_names_are_incompatible() {
+    let fixture = Fixture::new();
+    let path = fixture.install("empty.so", FakeLibrary::new(&fixture.plugin("")));
+    let mut manager = fixture.manager();
+    let err = manager.load_plugin(&path).unwrap_err();
+    assert!(is_incompatible_plugin(&err), "{}", err);
+    assert!(err.to_string().contains("name"));
+    assert!(manager.plugin_names().is_empty());
+    assert!(fixture.log.calls().is_empty());
+}
+
+#[test]
+fn panicking_metadata_is_incompatible() {
+    let err = check_instance(&PanickingPlugin).unwrap_err();
+    assert!(is_incompatible_plugin(&err));
+    assert!(err.to_string().contains("panicked"));
+    let err =
+        check_instance(&FakePlugin::new("alpha", &CallLog::default()).version("")).unwrap_err();
+    assert!(err.to_string().contains("version"));
+}
+
+// 事件中介函數
+
+#[test]
+fn middleware_runs_in_order_and_can_modify_events() {
+    let fixture = Fixture::new();
+    fixture.add(&fixture.plugin("alpha").subscribe(&["tick"]));
+    let mut manager = fixture.manager();
+    manager.load_all_plugins().unwrap();
+    manager.set_event_history_capacity(10);
+
+    let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+    manager.add_event_middleware(Box::new(|event| {
+        event
+            .data
+            .insert("correlation_id".into(), format!("{}-1", event.name));
+        MiddlewareAction::Modify
+    }));
+    let recorder = Arc::clone(&seen);
+    manager.add_event_middleware(Box::new(move |event| {
+        recorder
+            .lock()
+            .unwrap()
+            .push(event.data.get("correlation_id").cloned());
+        MiddlewareAction::Continue
+    }));
+
+    manager.broadcast_event(event("tick")).unwrap();
+    assert_eq!(*seen.lock().unwrap(), [Some("tick-1".to_string())]);
+    assert_eq!(fixture.log.count("alpha:handle_event:tick"), 1);
+    let history = manager.event_history();
+    assert_eq!(history[0].0.data["correlation_id"], "tick-1");
+    assert_eq!(history[0].1.delivered, ["alpha"]);
+}
+
+#[test]
+fn dropping_middleware_cancels_delivery() {
+    let fixture = Fixture::new();
+    fixture.add(&fixture.plugin("alpha").subscribe(&["tick", "secret"]));
+    let mut manager = fixture.manager();
+    manager.load_all_plugins().unwrap();
+
+    let later = Arc::new(std::sync::Mutex::new(0));
+    manager.add_event_middleware(Box::new(|event| {
+        if event.name == "secret" && !event.data.contains_key("token") {
+            MiddlewareAction::Drop
+        } else {
+            MiddlewareAction::Continue
+        }
+    }));
+    let counter = Arc::clone(&later);
+    manager.add_event_middleware(Box::new(move |_| {
+        *counter.lock().unwrap() += 1;
+        MiddlewareAction::Continue
+    }));
+
+    manager.broadcast_event(event("secret")).unwrap();
+    manager.broadcast_event(event("tick")).unwrap();
+    let mut authorized = event("secret");
+    authorized.data.insert("token".into(), "ok".into());
+    manager.broadcast_event(authorized).unwrap();
+    assert_eq!(
+        fixture.log.calls()[2..],
+        ["alpha:handle_event:tick", "alpha:handle_event:secret"]
+    );
+    assert_eq!(*later.lock().unwrap(), 2);
+    assert_eq!(manager.event_metrics().events_dropped, 1);
+
+    manager.clear_event_middleware();
+    manager.broadcast_event(event("secret")).unwrap();
+    assert_eq!(fixture.log.count("alpha:handle_event:secret"), 2);
+}
+
+// 凍結管理器
+
+#[test]
+fn frozen_manager_rejects_mutations() {
+    let fixture = Fixture::new();
+    fixture.add(&fixture.plugin("alpha").subscribe(&["tick"]));
+    let beta = fixture.add(&fixture.plugin("beta"));
+    let mut manager = fixture.manager();
+    manager.load_plugin(&fixture.dir.join("alpha.so")).unwrap();
+    manager.freeze();
+    assert!(manager.is_frozen());
+
+    let errors = [
+        manager.load_plugin(&beta).unwrap_err(),
+        manager.unload_plugin("alpha").unwrap_err(),
+        manager.disable_plugin("alpha").unwrap_err(),
+        manager.enable_plugin("alpha").unwrap_err(),
+        manager.reload_plugin("alpha").unwrap_err(),
+        manager.recover_plugin("alpha").unwrap_err(),
+        manager.disable_plugin_cascade("alpha").unwrap_err(),
+        manager.clear().unwrap_err(),
+    ];
+    for err in &errors {
+        assert!(is_frozen_error(err), "{}", err);
+    }
+    assert!(!is_frozen_error(&PluginError::LoadError("other".into())));
+    // 恢復快照返回報告，每個插件都記錄為凍結的錯誤
+    let mut snapshot = manager.snapshot();
+    snapshot.plugins[0].state = PluginState::Disabled;
+    let report = manager.restore(&snapshot);
+    assert!(report.loaded.is_empty());
+    assert_eq!(report.failed.len(), 1);
+    assert!(report.failed[0].1.contains(FROZEN_ERROR_PREFIX));
+    assert_eq!(manager.plugin_names(), ["alpha"]);
+    assert_eq!(manager.state_of("alpha"), Some(&PluginState::Enabled));
+}
+
+#[test]
+fn frozen_manager_still_serves_reads_and_events() {
+    let fixture = Fixture::new();
+    fixture.add(&fixture.plugin("alpha").subscribe(&["tick"]));
+    let mut manager = fixture.manager();
+    manager.load_all_plugins().unwrap();
+    manager.freeze();
+
+    assert_eq!(manager.get_plugin("alpha").unwrap().name(), "alpha");
+    assert_eq!(manager.list_plugins().len(), 1);
+    assert_eq!(manager.plugin_subscriptions("alpha"), ["tick"]);
+    manager.broadcast_event(event("tick")).unwrap();
+    assert_eq!(fixture.log.count("alpha:handle_event:tick"), 1);
+}
+
+// 輪詢派送
+
+#[test]
+fn round_robin_weights_do_not_keep_unloaded_names_interned() {
+    let fixture = Fixture::new();
+    fixture.add(&fixture.plugin("alpha").subscribe(&["job"]));
+    fixture.add(&fixture.plugin("beta").subscribe(&["job"]));
+    let mut manager = fixture.manager();
+    manager.set_event_dispatch_mode("job", Some(DispatchMode::RoundRobin));
+    manager.load_all_plugins().unwrap();
+    for _ in 0..4 {
+        manager.broadcast_event(event("job")).unwrap();
+    }
+    assert_eq!(fixture.log.count("alpha:handle_event:job"), 2);
+    assert_eq!(fixture.log.count("beta:handle_event:job"), 2);
+
+    manager.unload_plugin("alpha").unwrap();
+    assert!(!manager.event_bus.is_interned("alpha"));
+    assert!(manager.round_robin["job"]
+        .keys()
+        .all(|name| name != "alpha"));
+    manager.unload_plugin("beta").unwrap();
+    assert!(!manager.event_bus.is_interned("beta"));
+    assert!(!manager.event_bus.is_interned("job"));
+}
+
+// 狀態遷移
+
+#[test]
+fn reload_migrates_state_from_the_old_version() {
+    let fixture = Fixture::new();
+    let v1 = fixture.plugin("alpha");
+    v1.state().export = Some(b"counter=3".to_vec());
+    fixture.install(
+        "alpha.so",
+        FakeLibrary::new(&v1).export("plugin_export_state"),
+    );
+    let mut manager = fixture.manager();
+    manager.load_all_plugins().unwrap();
+
+    let v2 = fixture.plugin("alpha").version("2.0.0");
+    fixture.loader.register(
+        "alpha.so",
+        FakeLibrary::new(&v2).export("plugin_on_migrate"),
+    );
+    manager.reload_plugin("alpha").unwrap();
+    assert_eq!(manager.get_plugin("alpha").unwrap().version(), "2.0.0");
+    assert_eq!(
+        v2.state().migrated,
+        Some(("1.0.0".to_string(), b"counter=3".to_vec()))
+    );
+    // 遷移在新實例啟用前完成
+    let calls = fixture.log.calls();
+    let migrated = calls.iter().position(|c| c == "alpha:on_migrate").unwrap();
+    let enabled = calls.iter().rposition(|c| c == "alpha:on_enable").unwrap();
+    assert!(migrated < enabled, "{:?}", calls);
+    assert_eq!(manager.state_of("alpha"), Some(&PluginState::Enabled));
+}
+
+#[test]
+fn migration_needs_both_hooks() {
+    let fixture = Fixture::new();
+    let v1 = fixture.plugin("alpha");
+    v1.state().export = Some(b"counter=3".to_vec());
+    fixture.add(&v1);
+    let mut manager = fixture.manager();
+    manager.load_all_plugins().unwrap();
+
+    let v2 = fixture.plugin("alpha").version("2.0.0");
+    fixture.loader.register(
+        "alpha.so",
+        FakeLibrary::new(&v2).export("plugin_on_migrate"),
+    );
+    manager.reload_plugin("alpha").unwrap();
+    assert!(v2.state().migrated.is_none());
+}
+
+// 執行緒安全
+
+#[cfg(feature = "send")]
+#[test]
+fn manager_can_move_to_another_thread() {
+    let fixture = Fixture::new();
+    fixture.add(&fixture.plugin("alpha").subscribe(&["tick"]));
+    let mut manager = fixture.manager();
+    manager.set_name_validator(Box::new(|name| !name.is_empty()));
+    manager.add_event_middleware(Box::new(|_| MiddlewareAction::Continue));
+    manager.load_all_plugins().unwrap();
+
+    let manager = std::thread::spawn(move || {
+        manager.broadcast_event(event("tick")).unwrap();
+        manager
+    })
+    .join()
+    .unwrap();
+    assert_eq!(fixture.log.count("alpha:handle_event:tick"), 1);
+
+    // 也可以放入 `Mutex` 由多個執行緒輪流使用
+    let shared = Arc::new(std::sync::Mutex::new(manager));
+    let workers: Vec<_> = (0..2)
+        .map(|_| {
+            let shared = Arc::clone(&shared);
+            std::thread::spawn(move || shared.lock().unwrap().broadcast_event(event("tick")))
+        })
+        .collect();
+    for worker in workers {
+        worker.join().unwrap().unwrap();
+    }
+    assert_eq!(fixture.log.count("alpha:handle_event:tick"), 3);
+}
+
+#[cfg(feature = "send")]
+#[test]
+fn libraries_without_send_constructors_are_incompatible() {
+    let fixture = Fixture::new();
+    let path = fixture.install(
+        "alpha.so",
+        FakeLibrary::new(&fixture.plugin("alpha")).without_send(),
+    );
+    let err = fixture.manager().load_plugin(&path).unwrap_err();
+    assert!(is_incompatible_plugin(&err), "{}", err);
+    assert!(err.to_string().contains("create_plugin_send"));
+    assert!(fixture.log.calls().is_empty());
+}
+
+// 批次啟用與禁用
+
+#[test]
+fn disable_all_disables_dependents_first_in_strict_mode() {
+    let fixture = Fixture::new();
+    fixture.add(&fixture.plugin("base"));
+    fixture.install(
+        "consumer.so",
+        FakeLibrary::new(&fixture.plugin("consumer")).dependencies(&["base"]),
+    );
+    fixture.add(&fixture.plugin("alone"));
+    let mut manager = fixture.manager();
+    manager.set_strict(true);
+    manager.load_all_plugins().unwrap();
+    fixture.log.take();
+
+    let results = manager.try_disable_all();
+    let order: Vec<&str> = results.iter().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(order, ["alone", "consumer", "base"]);
+    assert!(results.iter().all(|(_, ret)| ret.is_ok()));
+    assert_eq!(
+        fixture.log.order_of("on_disable"),
+        ["alone", "consumer", "base"]
+    );
+
+    manager.enable_all_plugins().unwrap();
+    manager.disable_all_plugins().unwrap();
+    assert!(manager
+        .states()
+        .values()
+        .all(|state| *state == PluginState::Disabled));
+}
+
+// 連鎖禁用
+
+#[test]
+fn disabling_a_required_plugin_warns_or_fails_in_strict_mode() {
+    let fixture = Fixture::new();
+    dependency_chain(&fixture);
+    let mut manager = fixture.manager();
+    manager.load_all_plugins().unwrap();
+
+    manager.disable_plugin("base").unwrap();
+    assert_eq!(manager.state_of("base"), Some(&PluginState::Disabled));
+    assert_eq!(manager.state_of("middle"), Some(&PluginState::Enabled));
+    manager.enable_plugin("base").unwrap();
+
+    manager.set_strict(true);
+    let err = manager.disable_plugin("base").unwrap_err();
+    assert!(
+        matches!(&err, PluginError::DisableError(msg) if msg.contains("still required by enabled plugins: middle")),
+        "{}",
+        err
+    );
+    assert_eq!(manager.state_of("base"), Some(&PluginState::Enabled));
+    // 依賴者已禁用時不再阻擋
+    manager.disable_plugin("top").unwrap();
+    manager.disable_plugin("middle").unwrap();
+    manager.disable_plugin("base").unwrap();
+}
+
+#[test]
+fn cascade_disables_dependents_first_and_enables_them_back() {
+    let fixture = Fixture::new();
+    dependency_chain(&fixture);
+    let mut manager = fixture.manager();
+    manager.load_all_plugins().unwrap();
+    manager.set_strict(true);
+    fixture.log.take();
+
+    assert_eq!(
+        manager.disable_plugin_cascade("base").unwrap(),
+        ["top", "middle", "base"]
+    );
+    assert_eq!(
+        fixture.log.order_of("on_disable"),
+        ["top", "middle", "base"]
+    );
+    assert_eq!(
+        manager.enable_plugin_cascade("base").unwrap(),
+        ["base", "middle", "top"]
+    );
+    assert_eq!(fixture.log.order_of("on_enable"), ["base", "middle", "top"]);
+    for name in ["base", "middle", "top"] {
+        assert_eq!(manager.state_of(name), Some(&PluginState::Enabled));
+    }
+    assert!(manager.disable_plugin_cascade("missing").is_err());
+}
+
+// 插件摘要
+
+#[test]
+fn plugin_summaries_match_the_individual_accessors() {
+    let fixture = Fixture::new();
+    let beta = fixture.add(
+        &fixture
+            .plugin("beta")
+            .version("2.1.0")
+            .description("second"),
+    );
+    let alpha = fixture.add(&fixture.plugin("alpha").description("first"));
+    let mut manager = fixture.manager();
+    manager.load_all_plugins().unwrap();
+    manager.disable_plugin("beta").unwrap();
+
+    let summaries = manager.list_plugins();
+    let names: Vec<&str> = summaries.iter().map(|s| s.name.as_str()).collect();
+    assert_eq!(names, ["alpha", "beta"]);
+    for (summary, path) in summaries.iter().zip([&alpha, &beta]) {
+        let plugin = manager.get_plugin(&summary.name).unwrap();
+        assert_eq!(summary.version, plugin.version());
+        assert_eq!(summary.description, plugin.description());
+        assert_eq!(Some(&summary.state), manager.state_of(&summary.name));
+        assert_eq!(&summary.path, path);
+    }
+    assert_eq!(summaries[1].state, PluginState::Disabled);
+    assert_eq!(summaries[1].version, "2.1.0");
+}
+
+// 插件日誌
+
+#[test]
+fn plugin_logs_are_attributed_and_bounded() {
+    let fixture = Fixture::new();
+    let alpha = fixture.plugin("alpha");
+    fixture.install(
+        "alpha.so",
+        FakeLibrary::new(&alpha).export("plugin_set_host"),
+    );
+    fixture.add(&fixture.plugin("beta"));
+    let mut manager = fixture.manager();
+    manager.load_all_plugins().unwrap();
+    let host = alpha.state().host.clone().unwrap();
+
+    // 未設定上限時只輸出，不保留
+    host.log(log::Level::Info, "dropped");
+    assert!(manager.plugin_logs("alpha").is_empty());
+
+    manager.set_plugin_log_capacity(2);
+    host.log(log::Level::Info, "one");
+    host.log(log::Level::Warn, "two");
+    host.log(log::Level::Error, "three");
+    let logs = manager.plugin_logs("alpha");
+    let entries: Vec<(&str, log::Level, &str)> = logs
+        .iter()
+        .map(|r| (r.plugin.as_str(), r.level, r.message.as_str()))
+        .collect();
+    assert_eq!(
+        entries,
+        [
+            ("alpha", log::Level::Warn, "two"),
+            ("alpha", log::Level::Error, "three"),
+        ]
+    );
+    assert!(manager.plugin_logs("beta").is_empty());
+
+    manager.unload_plugin("alpha").unwrap();
+    assert!(manager.plugin_logs("alpha").is_empty());
+}
+
+// 交易式載入
+
+#[test]
+fn failed_transaction_rolls_back_only_its_own_plugins() {
+    let fixture = Fixture::new();
+    let base = fixture.add(&fixture.plugin("base"));
+    let paths = [
+        fixture.add(&fixture.plugin("first")),
+        fixture.add(&fixture.plugin("second")),
+        fixture.add(&fixture.plugin("third").failing("on_enable", "broken")),
+        fixture.add(&fixture.plugin("fourth")),
+    ];
+    let mut manager = fixture.manager();
+    manager.load_plugin(&base).unwrap();
+    fixture.log.take();
+
+    let paths: Vec<&Path> = paths.iter().map(|p| p.as_path()).collect();
+    let err = manager.load_transaction(&paths).unwrap_err();
+    assert!(err.to_string().contains("third"), "{}", err);
+    assert_eq!(manager.plugin_names(), ["base"]);
+    assert_eq!(manager.state_of("base"), Some(&PluginState::Enabled));
+    let unloaded = fixture.log.order_of("on_unload");
+    assert_eq!(unloaded.len(), 3);
+    for name in ["first", "second", "third"] {
+        assert!(unloaded.contains(&name.to_string()), "{:?}", unloaded);
+    }
+    assert_eq!(fixture.log.count("fourth:on_load"), 0);
+    assert_eq!(fixture.log.count("base:on_disable"), 0);
+}
+
+#[test]
+fn successful_transaction_loads_every_plugin() {
+    let fixture = Fixture::new();
+    let paths = [
+        fixture.add(&fixture.plugin("first")),
+        fixture.add(&fixture.plugin("second")),
+    ];
+    let mut manager = fixture.manager();
+    let paths: Vec<&Path> = paths.iter().map(|p| p.as_path()).collect();
+    manager.load_transaction(&paths).unwrap();
+    assert_eq!(manager.plugin_names(), ["first", "second"]);
+}
+
+// 同優先級的派送順序
+
+#[test]
+fn equal_priority_follows_load_order_then_names() {
+    let fixture = Fixture::new();
+    let mut paths = Vec::new();
+    for name in ["gamma", "alpha", "beta"] {
+        paths.push(fixture.add(&fixture.plugin(name).subscribe(&["tick"])));
+    }
+    fixture.install(
+        "urgent.so",
+        FakeLibrary::new(&fixture.plugin("urgent").subscribe(&["tick"])).priority(5),
+    );
+    let mut manager = fixture.manager();
+    for path in &paths {
+        manager.load_plugin(path).unwrap();
+    }
+    manager.load_plugin(&fixture.dir.join("urgent.so")).unwrap();
+    manager.set_event_history_capacity(10);
+
+    manager.broadcast_event(event("tick")).unwrap();
+    // 重新載入的插件維持原本的位置
+    manager.reload_plugin("gamma").unwrap();
+    manager.broadcast_event(event("tick")).unwrap();
+    // 註冊順序也相同時依名稱排序
+    let seq = manager.plugins["gamma"].load_seq;
+    manager.plugins.get_mut("beta").unwrap().load_seq = seq;
+    manager.invalidate_dispatch_order();
+    manager.broadcast_event(event("tick")).unwrap();
+
+    let orders: Vec<Vec<String>> = manager
+        .event_history()
+        .into_iter()
+        .map(|(_, outcome)| outcome.delivered)
+        .collect();
+    assert_eq!(orders[0], ["urgent", "gamma", "alpha", "beta"]);
+    assert_eq!(orders[1], ["urgent", "gamma", "alpha", "beta"]);
+    assert_eq!(orders[2], ["urgent", "beta", "gamma", "alpha"]);
+}
+
+#[test]
+fn loading_a_subscriber_refreshes_the_cached_dispatch_order() {
+    let fixture = Fixture::new();
+    fixture.add(&fixture.plugin("alpha").subscribe(&["tick"]));
+    let urgent = fixture.install(
+        "urgent.so",
+        FakeLibrary::new(&fixture.plugin("urgent")).priority(5),
+    );
+    let mut manager = fixture.manager();
+    manager.load_plugin(&fixture.dir.join("alpha.so")).unwrap();
+    // 訂閱先於插件存在，載入與卸載插件都不會改變事件總線的訂閱
+    manager.event_bus.subscribe("tick", "urgent").unwrap();
+    assert_eq!(manager.broadcast_event_dry_run(&event("tick")), ["alpha"]);
+
+    manager.load_plugin(&urgent).unwrap();
+    assert_eq!(
+        manager.broadcast_event_dry_run(&event("tick")),
+        ["urgent", "alpha"]
+    );
+    manager.unload_plugin("urgent").unwrap();
+    assert_eq!(manager.broadcast_event_dry_run(&event("tick")), ["alpha"]);
+}
+
+// 清除管理器
+
+#[test]
+fn clear_keeps_subscription_limits() {
+    let fixture = Fixture::new();
+    fixture.add(&fixture.plugin("alpha").subscribe(&["a", "b"]));
+    let mut manager = fixture.manager();
+    manager.set_max_subscriptions_per_plugin(Some(2));
+    manager.load_all_plugins().unwrap();
+    manager.clear().unwrap();
+    assert!(manager.get_all_plugins().is_empty());
+
+    fixture.add(&fixture.plugin("beta").subscribe(&["a", "b"]));
+    fixture.add(&fixture.plugin("gamma").subscribe(&["a", "b", "c"]));
+    let err = manager.load_all_plugins().unwrap_err();
+    assert!(err.to_string().contains("gamma"));
+    assert_eq!(manager.plugin_names(), ["alpha", "beta"]);
+}
+
+// 訂閱數量上限
+
+#[test]
+fn subscription_limits_reject_plugins_on_load() {
+    let fixture = Fixture::new();
+    fixture.add(&fixture.plugin("alpha").subscribe(&["a", "b"]));
+    fixture.add(&fixture.plugin("beta").subscribe(&["a", "b", "c"]));
+    fixture.add(&fixture.plugin("gamma").subscribe(&["c"]));
+    let mut manager = fixture.manager();
+    manager.set_max_subscriptions_per_plugin(Some(2));
+    manager.set_max_total_subscriptions(Some(2));
+    assert!(manager.load_all_plugins().is_err());
+    assert_eq!(manager.plugin_names(), ["alpha"]);
+    let err = manager
+        .load_plugin(&fixture.dir.join("beta.so"))
+        .unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("beta would have 3 subscriptions, limit is 2"),
+        "{}",
+        err
+    );
+    let err = manager
+        .load_plugin(&fixture.dir.join("gamma.so"))
+        .unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("event bus to 3 subscriptions, limit is 2"),
+        "{}",
+        err
+    );
+    assert_eq!(manager.event_bus.total_subscriptions(), 2);
+
+    // 上限只限制之後的載入
+    manager.set_max_total_subscriptions(None);
+    manager.load_plugin(&fixture.dir.join("gamma.so")).unwrap();
+    assert_eq!(manager.event_bus.total_subscriptions(), 3);
+}
+
+// 全部載入後的鉤子
+
+#[test]
+fn on_all_loaded_runs_after_the_whole_batch_is_enabled() {
+    let fixture = Fixture::new();
+    for name in ["alpha", "beta"] {
+        fixture.install(
+            &format!("{}.so", name),
+            FakeLibrary::new(&fixture.plugin(name)).export("plugin_on_all_loaded"),
+        );
+    }
+    fixture.add(&fixture.plugin("plain"));
+    let mut manager = fixture.manager();
+    manager.load_all_plugins().unwrap();
+    let calls = fixture.log.calls();
+    let last_enable = calls
+        .iter()
+        .rposition(|c| c.ends_with(":on_enable"))
+        .unwrap();
+    assert_eq!(
+        calls[last_enable + 1..],
+        ["alpha:on_all_loaded", "beta:on_all_loaded"]
+    );
+
+    // 單獨載入的插件不屬於任何批次
+    fixture.log.take();
+    manager.unload_plugin("alpha").unwrap();
+    manager.load_plugin(&fixture.dir.join("alpha.so")).unwrap();
+    assert_eq!(fixture.log.count("alpha:on_all_loaded"), 0);
+}
+
+#[test]
+fn failing_on_all_loaded_only_marks_that_plugin() {
+    let fixture = Fixture::new();
+    fixture.install(
+        "alpha.so",
+        FakeLibrary::new(&fixture.plugin("alpha").failing("on_all_loaded", "no peer"))
+            .export("plugin_on_all_loaded"),
+    );
+    fixture.install(
+        "beta.so",
+        FakeLibrary::new(&fixture.plugin("beta")).export("plugin_on_all_loaded"),
+    );
+    let mut manager = fixture.manager();
+    let paths = [fixture.dir.join("alpha.so"), fixture.dir.join("beta.so")];
+    let paths: Vec<&Path> = paths.iter().map(|p| p.as_path()).collect();
+    manager.load_transaction(&paths).unwrap();
+    assert!(matches!(
+        manager.state_of("alpha"),
+        Some(PluginState::Error(msg)) if msg.contains("no peer")
+    ));
+    assert_eq!(fixture.log.count("alpha:on_disable"), 1);
+    assert_eq!(manager.state_of("beta"), Some(&PluginState::Enabled));
+    assert_eq!(fixture.log.count("beta:on_all_loaded"), 1);
+}
+
+// 依賴關係查詢
+
+#[test]
+fn dependency_queries_list_direct_relations_both_ways() {
+    let fixture = Fixture::new();
+    dependency_chain(&fixture);
+    fixture.install(
+        "extra.so",
+        FakeLibrary::new(&fixture.plugin("extra")).dependencies(&["middle", "base", "middle"]),
+    );
+    let mut manager = fixture.manager();
+    manager.load_all_plugins().unwrap();
+
+    assert_eq!(manager.dependencies_of("top"), ["middle"]);
+    assert_eq!(manager.dependencies_of("extra"), ["base", "middle"]);
+    assert!(manager.dependencies_of("base").is_empty());
+    assert_eq!(manager.dependents_of("base"), ["extra", "middle"]);
+    assert_eq!(manager.dependents_of("middle"), ["extra", "top"]);
+    assert!(manager.dependents_of("top").is_empty());
+    assert!(manager.dependencies_of("missing").is_empty());
+    assert!(manager.dependents_of("missing").is_empty());
+}
+
+// 禁用失敗時的卸載策略
+
+#[test]
+fn failing_on_disable_is_forced_through_by_default() {
+    let fixture = Fixture::new();
+    fixture.add(&fixture.plugin("stuck").failing("on_disable", "stuck"));
+    let mut manager = fixture.manager();
+    manager.load_all_plugins().unwrap();
+
+    manager.unload_plugin("stuck").unwrap();
+    assert!(manager.state_of("stuck").is_none());
+    assert_eq!(fixture.log.count("stuck:on_unload"), 1);
+}
+
+#[test]
+fn strict_disable_policy_keeps_the_plugin_loaded() {
+    let fixture = Fixture::new();
+    let stuck = fixture.plugin("stuck").failing("on_disable", "stuck");
+    fixture.add(&stuck);
+    let mut manager = fixture.manager();
+    manager.set_disable_error_policy(DisableErrorPolicy::StrictDisable);
+    manager.load_all_plugins().unwrap();
+
+    let err = manager.unload_plugin("stuck").unwrap_err();
+    assert!(err.to_string().contains("stuck"), "{}", err);
+    assert!(manager.state_of("stuck").is_some());
+    assert_eq!(fixture.log.count("stuck:on_unload"), 0);
+    // 插件恢復後即可正常卸載
+    stuck.set_failing("on_disable", None);
+    manager.set_disable_error_policy(DisableErrorPolicy::ForceUnloadOnDisableError);
+    manager.unload_plugin("stuck").unwrap();
+    assert!(manager.state_of("stuck").is_none());
+}
+
+// 載入順序的記錄與重播
+
+#[test]
+fn recorded_load_order_is_replayed() {
+    let fixture = Fixture::new();
+    for name in ["alpha", "beta", "gamma"] {
+        fixture.add(&fixture.plugin(name));
+    }
+    let mut manager = fixture.manager();
+    for name in ["gamma", "alpha", "beta"] {
+        manager
+            .load_plugin(&fixture.dir.join(format!("{}.so", name)))
+            .unwrap();
+    }
+    // 重新載入不改變記錄的位置
+    manager.reload_plugin("gamma").unwrap();
+    let order = manager.record_load_order();
+    assert_eq!(order, ["gamma", "alpha", "beta"]);
+    drop(manager);
+
+    fixture.log.take();
+    let mut manager = fixture.manager();
+    let mut replay = order.clone();
+    replay.insert(1, "missing".to_string());
+    let report = manager.load_in_order(&replay);
+    assert_eq!(report.loaded, order);
+    assert!(report.failed.is_empty());
+    assert_eq!(manager.record_load_order(), order);
+    assert_eq!(fixture.log.order_of("on_load"), order);
+}
+
+// ABI 版本範圍
+
+#[test]
+fn abi_ranges_overlapping_the_host_are_accepted() {
+    let fixture = Fixture::new();
+    let current = crate::PLUGIN_ABI_VERSION;
+    fixture.install(
+        "exact.so",
+        FakeLibrary::new(&fixture.plugin("exact")).abi(current, None),
+    );
+    fixture.install(
+        "newer.so",
+        FakeLibrary::new(&fixture.plugin("newer")).abi(current + 1, Some(current)),
+    );
+    fixture.add(&fixture.plugin("unversioned"));
+    let mut manager = fixture.manager();
+    manager.load_all_plugins().unwrap();
+    assert_eq!(manager.plugin_names(), ["exact", "newer", "unversioned"]);
+}
+
+#[test]
+fn abi_ranges_outside_the_host_are_rejected() {
+    let fixture = Fixture::new();
+    let (min, current) = (crate::MIN_SUPPORTED_ABI_VERSION, crate::PLUGIN_ABI_VERSION);
+    let cases = [
+        (
+            "old.so",
+            (min - 1, None),
+            "is older than the supported range",
+        ),
+        ("new.so", (current + 1, None), "or newer is required"),
+        ("invalid.so", (current, Some(current + 1)), "is invalid"),
+    ];
+    let mut manager = fixture.manager();
+    for (file, (version, abi_min), expected) in cases {
+        let name = file.trim_end_matches(".so");
+        let path = fixture.install(
+            file,
+            FakeLibrary::new(&fixture.plugin(name)).abi(version, abi_min),
+        );
+        let err = manager.load_plugin(&path).unwrap_err();
+        assert!(is_incompatible_plugin(&err), "{}", err);
+        assert!(err.to_string().contains(expected), "{}", err);
+    }
+    assert!(manager.plugin_names().is_empty());
+    assert!(fixture.log.calls().is_empty());
+}
+
+// 斷路器
+
+#[test]
+fn circuit_breaker_opens_cools_down_and_closes() {
+    let fixture = Fixture::new();
+    let alpha = fixture
+        .plugin("alpha")
+        .subscribe(&["tick", "tock"])
+        .failing("handle_event", "flaky");
+    fixture.add(&alpha);
+    let mut manager = fixture.manager();
+    manager.load_all_plugins().unwrap();
+    manager.set_circuit_breaker(Some((2, Duration::from_millis(50))));
+
+    manager.broadcast_event(event("tick")).unwrap();
+    assert_eq!(manager.circuit_state("alpha", "tick"), CircuitState::Closed);
+    manager.broadcast_event(event("tick")).unwrap();
+    assert_eq!(manager.circuit_state("alpha", "tick"), CircuitState::Open);
+    // 斷開期間不派送此事件，其他事件不受影響
+    manager.broadcast_event(event("tick")).unwrap();
+    assert_eq!(fixture.log.count("alpha:handle_event:tick"), 2);
+    manager.broadcast_event(event("tock")).unwrap();
+    assert_eq!(fixture.log.count("alpha:handle_event:tock"), 1);
+    assert_eq!(manager.circuit_state("alpha", "tock"), CircuitState::Closed);
+
+    std::thread::sleep(Duration::from_millis(60));
+    assert_eq!(
+        manager.circuit_state("alpha", "tick"),
+        CircuitState::HalfOpen
+    );
+    alpha.set_failing("handle_event", None);
+    manager.broadcast_event(event("tick")).unwrap();
+    assert_eq!(fixture.log.count("alpha:handle_event:tick"), 3);
+    assert_eq!(manager.circuit_state("alpha", "tick"), CircuitState::Closed);
+}
+
+#[test]
+fn failed_half_open_trial_reopens_the_circuit() {
+    let fixture = Fixture::new();
+    fixture.add(
+        &fixture
+            .plugin("alpha")
+            .subscribe(&["tick"])
+            .failing("handle_event", "down"),
+    );
+    let mut manager = fixture.manager();
+    manager.load_all_plugins().unwrap();
+    manager.set_circuit_breaker(Some((1, Duration::from_millis(50))));
+
+    manager.broadcast_event(event("tick")).unwrap();
+    assert_eq!(manager.circuit_state("alpha", "tick"), CircuitState::Open);
+    std::thread::sleep(Duration::from_millis(60));
+    assert_eq!(
+        manager.circuit_state("alpha", "tick"),
+        CircuitState::HalfOpen
+    );
+    manager.broadcast_event(event("tick")).unwrap();
+    assert_eq!(manager.circuit_state("alpha", "tick"), CircuitState::Open);
+    manager.broadcast_event(event("tick")).unwrap();
+    assert_eq!(fixture.log.count("alpha:handle_event:tick"), 2);
+
+    manager.set_circuit_breaker(None);
+    assert_eq!(manager.circuit_state("alpha", "tick"), CircuitState::Closed);
+}
+
+// 依賴順序啟用
+
+#[test]
+fn enable_all_enables_dependencies_first_regardless_of_names() {
+    let fixture = Fixture::new();
+    fixture.install(
+        "alpha.so",
+        FakeLibrary::new(&fixture.plugin("alpha")).dependencies(&["beta"]),
+    );
+    fixture.add(&fixture.plugin("beta"));
+    let mut manager = fixture.manager();
+    manager.set_auto_enable(false);
+    manager.load_all_plugins().unwrap();
+
+    manager.enable_all_plugins().unwrap();
+    assert_eq!(fixture.log.order_of("on_enable"), ["beta", "alpha"]);
+}
+
+#[test]
+fn dependents_of_a_failed_plugin_are_skipped() {
+    let fixture = Fixture::new();
+    fixture.install(
+        "alpha.so",
+        FakeLibrary::new(&fixture.plugin("alpha")).dependencies(&["beta"]),
+    );
+    fixture.add(&fixture.plugin("beta").failing("on_enable", "broken"));
+    fixture.add(&fixture.plugin("gamma"));
+    let mut manager = fixture.manager();
+    manager.set_auto_enable(false);
+    manager.load_all_plugins().unwrap();
+
+    let results = manager.try_enable_all();
+    let skipped = &results.iter().find(|(name, _)| name == "alpha").unwrap().1;
+    assert!(
+        skipped
+            .as_ref()
+            .is_err_and(|e| e.to_string().contains("dependency beta failed to enable")),
+        "{:?}",
+        skipped
+    );
+    assert_eq!(fixture.log.count("alpha:on_enable"), 0);
+    assert_eq!(manager.state_of("alpha"), Some(&PluginState::Loaded));
+    assert_eq!(manager.state_of("gamma"), Some(&PluginState::Enabled));
+}
+
+// 穩定的派送順序
+
+#[test]
+fn broadcast_reaches_enabled_subscribers_in_a_stable_order() {
+    let fixture = Fixture::new();
+    // 訂閱數量不影響順序
+    for (name, priority, events) in [
+        ("many", 1, &["tick", "a", "b", "c"][..]),
+        ("few", 5, &["tick"][..]),
+        ("off", 9, &["tick"][..]),
+        ("other", 7, &["tock"][..]),
+    ] {
+        fixture.install(
+            &format!("{}.so", name),
+            FakeLibrary::new(&fixture.plugin(name).subscribe(events)).priority(priority),
+        );
+    }
+    let mut manager = fixture.manager();
+    manager.load_all_plugins().unwrap();
+    manager.disable_plugin("off").unwrap();
+    manager.set_event_history_capacity(10);
+    fixture.log.take();
+
+    for _ in 0..3 {
+        manager.broadcast_event(event("tick")).unwrap();
+    }
+    for (_, outcome) in manager.event_history() {
+        assert_eq!(outcome.delivered, ["few", "many"]);
+        assert_eq!(outcome.skipped, ["off"]);
+    }
+    assert_eq!(fixture.log.count("off:handle_event:tick"), 0);
+    assert_eq!(fixture.log.count("other:handle_event:tick"), 0);
+}
@@ -0,0 +1,196 @@
+//! 插件目錄的啟動前檢查
+use super::{optional_symbol, PluginFileCheck, PluginManager};
+use chm_core_define::{PluginError, Result};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// [`PluginManager::validate_plugin_dir`] 的檢查結果
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ValidationReport {
+    /// 有效的插件名稱及其檔案路徑，依名稱排序
+    pub plugins: Vec<(String, PathBuf)>,
+    /// 會註冊相同名稱的插件名稱及其所有檔案路徑
+    pub duplicates: Vec<(String, Vec<PathBuf>)>,
+    /// 無法作為插件載入的檔案及原因
+    pub invalid: Vec<(PathBuf, String)>,
+    /// 因權限不足而無法檢查的檔案及原因
+    pub permission_denied: Vec<(PathBuf, String)>,
+}
+impl ValidationReport {
+    /// 檢查是否通過
+    /// - 返回值: 至少有一個有效插件，且沒有重複名稱、無效檔案或權限問題時為 `true`
+    pub fn is_ok(&self) -> bool {
+        !self.plugins.is_empty()
+            && self.duplicates.is_empty()
+            && self.invalid.is_empty()
+            && self.permission_denied.is_empty()
+    }
+}
+
+#[allow(unused)]
+impl PluginManager {
+    /// 檢查插件目錄是否可以正常載入
+    /// - 依 [`PluginManager::check_plugin_file`] 篩選檔案，副檔名不符、目錄與不存在的路徑會被略過
+    /// - 每個候選檔案會暫時開啟並創建實例以讀取插件名稱，之後立即釋放;
+    ///   不調用加載鉤子、不建立資料目錄，也不會註冊到管理器中
+    /// - 注意: 開啟動態庫仍會執行其靜態初始化程式碼
+    /// - 返回值: 檢查結果，插件目錄不存在或無法讀取時返回錯誤
+    pub fn validate_plugin_dir(&self) -> Result<ValidationReport> {
+        if !self.plugin_dir.exists() {
+            return Err(PluginError::LoadError(
+                "Plugin directory does not exist".into(),
+            ));
+        }
+        let dir_entries = std::fs::read_dir(&self.plugin_dir).map_err(|e| {
+            PluginError::LoadError(format!("Failed to read plugin directory: {}", e))
+        })?;
+
+        let mut report = ValidationReport::default();
+        let mut names: BTreeMap<String, Vec<PathBuf>> = BTreeMap::new();
+        for entry in dir_entries {
+            let path = match entry {
+                Ok(entry) => entry.path(),
+                Err(e) => {
+                    report.permission_denied.push((
+                        self.plugin_dir.clone(),
+                        format!("Failed to read directory entry: {}", e),
+                    ));
+                    continue;
+                }
+            };
+            match self.check_plugin_file(&path) {
+                PluginFileCheck::Valid => {}
+                PluginFileCheck::WrongExtension
+                | PluginFileCheck::Directory
+                | PluginFileCheck::NotAFile => continue,
+                reason @ (PluginFileCheck::NotExecutable | PluginFileCheck::Unreadable) => {
+                    report
+                        .permission_denied
+                        .push((path, format!("{:?}", reason)));
+                    continue;
+                }
+                reason => {
+                    report.invalid.push((path, format!("{:?}", reason)));
+                    continue;
+                }
+            }
+            match self.probe_plugin_names(&path) {
+                Ok(probed) => {
+                    for name in probed {
+                        names.entry(name).or_default().push(path.clone());
+                    }
+                }
+                Err(e) => report.invalid.push((path, e.to_string())),
+            }
+        }
+        for (name, mut paths) in names {
+            if paths.len() > 1 {
+                paths.sort();
+                report.duplicates.push((name, paths));
+            } else {
+                report
+                    .plugins
+                    .extend(paths.into_iter().map(|path| (name.clone(), path)));
+            }
+        }
+        report.invalid.sort();
+        report.permission_denied.sort();
+        Ok(report)
+    }
+    /// 暫時開啟插件檔案以讀取插件名稱
+    /// - `path`: 插件檔案的路徑
+    /// - 返回值: 動態庫提供的所有插件名稱，無法載入或任一名稱無效時返回錯誤
+    pub(super) fn probe_plugin_names(&self, path: &Path) -> Result<Vec<String>> {
+        let opened = Self::open_library(&*self.loader, path)?;
+        unsafe {
+            let plugins = Self::create_instances(&opened.library)?;
+            let names: Vec<String> = plugins.iter().map(|p| p.name().to_string()).collect();
+            if let Some(unload_plugin) = optional_symbol::<fn()>(&opened.library, b"unload_plugin")
+            {
+                unload_plugin();
+            }
+            // 插件實例必須在動態庫之前釋放
+            drop(plugins);
+            drop(opened);
+            if let Some(name) = names.iter().find(|name| !(self.name_validator)(name)) {
+                return Err(PluginError::LoadError(format!(
+                    "Invalid plugin name: {:?}",
+                    name
+                )));
+            }
+            Ok(names)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_util::{write_file, FakeLibrary, Fixture};
+    use super::*;
+
+    #[test]
+    fn clean_directory_passes_without_loading_plugins() {
+        let fixture = Fixture::new();
+        let alpha = fixture.add(&fixture.plugin("alpha"));
+        let beta = fixture.add(&fixture.plugin("beta"));
+        write_file(&fixture.dir.join("README.md"), b"");
+        let manager = fixture.manager();
+        let report = manager.validate_plugin_dir().unwrap();
+        assert!(report.is_ok(), "{:?}", report);
+        assert_eq!(
+            report.plugins,
+            [("alpha".to_string(), alpha), ("beta".to_string(), beta)]
+        );
+        assert!(fixture.log.calls().is_empty());
+        assert!(manager.plugin_names().is_empty());
+    }
+
+    #[test]
+    fn duplicate_names_and_invalid_files_are_reported() {
+        let fixture = Fixture::new();
+        let alpha = fixture.add(&fixture.plugin("alpha"));
+        let copy = fixture.install("alpha-copy.so", FakeLibrary::new(&fixture.plugin("alpha")));
+        let beta = fixture.add(&fixture.plugin("beta"));
+        let garbage = fixture.dir.join("garbage.so");
+        write_file(&garbage, b"not a library");
+        let report = fixture.manager().validate_plugin_dir().unwrap();
+        assert!(!report.is_ok());
+        assert_eq!(report.plugins, [("beta".to_string(), beta)]);
+        assert_eq!(
+            report.duplicates,
+            [("alpha".to_string(), vec![copy, alpha])]
+        );
+        assert_eq!(report.invalid.len(), 1);
+        assert_eq!(report.invalid[0].0, garbage);
+        assert!(report.invalid[0].1.contains("Failed to load library"));
+    }
+
+    #[cfg(all(unix, not(target_os = "macos")))]
+    #[test]
+    fn unexecutable_files_are_permission_problems() {
+        use std::os::unix::fs::PermissionsExt;
+        let fixture = Fixture::new();
+        fixture.add(&fixture.plugin("alpha"));
+        let beta = fixture.add(&fixture.plugin("beta"));
+        std::fs::set_permissions(&beta, std::fs::Permissions::from_mode(0o644)).unwrap();
+        let report = fixture.manager().validate_plugin_dir().unwrap();
+        assert!(!report.is_ok());
+        assert_eq!(report.plugins.len(), 1);
+        assert_eq!(
+            report.permission_denied,
+            [(beta, "NotExecutable".to_string())]
+        );
+    }
+
+    #[test]
+    fn missing_or_empty_directories_fail() {
+        let fixture = Fixture::new();
+        let report = fixture.manager().validate_plugin_dir().unwrap();
+        assert!(!report.is_ok());
+        let manager = PluginManager::new(fixture.dir.join("missing"));
+        assert!(matches!(
+            manager.validate_plugin_dir(),
+            Err(PluginError::LoadError(msg)) if msg.contains("does not exist")
+        ));
+    }
+}